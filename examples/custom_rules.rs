@@ -0,0 +1,113 @@
+//! A template for building your own variant of snakebit against `snakebit-core`.
+//!
+//! This variant, "time attack", keeps the core game's movement and collisions but changes the
+//! rules around them:
+//!
+//! - Scoring: food is worth more the faster the snake is moving when it eats it.
+//! - An outcome override: the game ends once its time is up, however well it is going.
+//!
+//! Everything game-specific lives in [`TimeAttack`], which implements the core crate's `Rules`
+//! trait; the rest is the minimum needed to show the game on the LED matrix (without greyscale)
+//! and turn the snake with the buttons. Flash it with `cargo embed --example custom_rules`.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use microbit::Board;
+use microbit::display::blocking::Display;
+use microbit::hal::{Rng, Timer};
+use microbit::hal::prelude::*;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+use snakebit_core::game::{count_matrix, Game, GameStatus, Turn};
+use snakebit_core::game::rules::{Outcome, Rules};
+
+/// How long a game lasts, in milliseconds.
+const TIME_LIMIT_MS: u32 = 60_000;
+/// How often the buttons are checked, in milliseconds.
+const POLL_MS: u32 = 20;
+
+/// The variant's rules. Hooks that aren't overridden behave as in the standard game.
+struct TimeAttack {
+    points: u32,
+    remaining_ms: u32
+}
+
+impl TimeAttack {
+    fn new() -> Self {
+        Self { points: 0, remaining_ms: TIME_LIMIT_MS }
+    }
+
+    /// Count down the time left by `ms` milliseconds.
+    fn elapse(&mut self, ms: u32) {
+        self.remaining_ms = self.remaining_ms.saturating_sub(ms);
+    }
+}
+
+impl Rules for TimeAttack {
+    /// Each piece of food is worth as many points as the snake's current speed. The game's own
+    /// score still counts food eaten, which keeps the standard speed-ups.
+    fn food_points(&mut self, score: u8) -> u8 {
+        self.points += self.speed(score) as u32;
+        1
+    }
+
+    /// Running out of time ends the game. The core game only knows about winning and losing, so
+    /// it counts as a win.
+    fn status(&mut self, _outcome: Outcome, status: GameStatus) -> GameStatus {
+        if self.remaining_ms == 0 && status == GameStatus::Ongoing {
+            GameStatus::Won
+        } else {
+            status
+        }
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = Board::take().unwrap();
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+    let mut rng = Rng::new(board.RNG);
+    let button_a = board.buttons.button_a;
+    let button_b = board.buttons.button_b;
+
+    loop {
+        let mut game = Game::new(rng.random_u32()).unwrap().with_rules(TimeAttack::new());
+        let (mut a_was_pressed, mut b_was_pressed) = (false, false);
+        let mut turn = Turn::None;
+
+        while game.status == GameStatus::Ongoing {
+            // Show the game for one step, picking up button presses (not holds) as it goes. The
+            // blocking display can't tell brightnesses apart, so everything is shown fully lit.
+            let matrix = game.game_matrix(1, 1, 1, 1);
+            let mut shown_ms = 0;
+            while shown_ms < game.step_len_ms() {
+                display.show(&mut timer, matrix, POLL_MS);
+                shown_ms += POLL_MS;
+                let a_pressed = button_a.is_low().unwrap();
+                let b_pressed = button_b.is_low().unwrap();
+                if a_pressed && !a_was_pressed {
+                    turn = Turn::Left;
+                } else if b_pressed && !b_was_pressed {
+                    turn = Turn::Right;
+                }
+                a_was_pressed = a_pressed;
+                b_was_pressed = b_pressed;
+            }
+
+            game.rules_mut().elapse(shown_ms);
+            if let Err(e) = game.step(turn) {
+                rprintln!("Game error: {:?}", e);
+                break;
+            }
+            turn = Turn::None;
+        }
+
+        let points = game.rules().points;
+        rprintln!("Game over: {:?}, {} points", game.status, points);
+        display.show(&mut timer, count_matrix(points as usize), 3000);
+    }
+}
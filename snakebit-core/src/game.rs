@@ -0,0 +1,1246 @@
+use heapless::FnvIndexSet;
+use heapless::spsc::Queue;
+
+pub mod rules;
+pub mod snapshot;
+
+use rules::{FoodGrid, FullGrid, Outcome, Rules, SelfCollision, StandardRules};
+
+#[cfg(all(feature = "grid8x8", feature = "grid9x9"))]
+compile_error!("Only one of the `grid8x8` and `grid9x9` features can be enabled");
+
+/// Width and height of our grid: the micro:bit's LED matrix, unless a feature picks a bigger grid
+/// for a bigger display.
+#[cfg(not(any(feature = "grid8x8", feature = "grid9x9")))]
+const GRID_SIZE: usize = 5;
+#[cfg(feature = "grid8x8")]
+const GRID_SIZE: usize = 8;
+#[cfg(all(feature = "grid9x9", not(feature = "grid8x8")))]
+const GRID_SIZE: usize = 9;
+
+/// Number of rows in our grid
+pub const N_ROWS: usize = GRID_SIZE;
+/// Number of columns in our grid
+pub const N_COLS: usize = GRID_SIZE;
+/// The row the snake starts in, halfway down the grid. Walls are never placed here, so it doesn't
+/// crash straight away.
+pub const START_ROW: usize = N_ROWS / 2;
+
+/// Number of tiles on the grid.
+const GRID_AREA: usize = N_ROWS * N_COLS;
+/// Capacity of a set of coordinates: enough for every tile, rounded up to a power of two as
+/// `FnvIndexSet` requires.
+const COORD_SET_CAPACITY: usize = GRID_AREA.next_power_of_two();
+/// Capacity of the queue holding the snake's tail. An `spsc::Queue` holds one element fewer than
+/// its capacity, which still leaves room for every tile but the head's. Powers of two make the
+/// queue faster.
+const TAIL_CAPACITY: usize = GRID_AREA.next_power_of_two();
+
+// Check at compile time that the collections can never overflow, whatever the size of the grid
+const _: () = assert!(COORD_SET_CAPACITY >= GRID_AREA && COORD_SET_CAPACITY.is_power_of_two());
+const _: () = assert!(TAIL_CAPACITY > GRID_AREA - 1);
+
+type CoordSet = FnvIndexSet<Coords, COORD_SET_CAPACITY>;
+
+/// Insert `coords` into `set`, failing if the set is full.
+fn insert_coords(set: &mut CoordSet, coords: Coords) -> Result<(), GameError> {
+    set.insert(coords).map(|_| ()).map_err(|_| GameError::CapacityExceeded)
+}
+
+/// Number of extra pieces of food placed for a bonus round (fewer if there isn't room).
+const BONUS_FOOD: usize = 3;
+
+/// A chase is played every time the snake reaches a level that is a multiple of this.
+const CHASE_EVERY_LEVELS: u8 = 5;
+
+/// Number of walls in a generated wall layout. Three walls can never completely enclose a cell
+/// (even with wraparound), so the food is always reachable.
+const GENERATED_WALLS: usize = 3;
+
+/// Define the directions the snake can move
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl Direction {
+    /// Every direction, clockwise from up.
+    const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    /// The direction 90 degrees counter-clockwise from this one.
+    pub fn left(self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+            Direction::Right => Direction::Up
+        }
+    }
+
+    /// The direction 90 degrees clockwise from this one.
+    pub fn right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+            Direction::Right => Direction::Down
+        }
+    }
+
+    fn opposite(self) -> Self {
+        self.left().left()
+    }
+}
+
+/// The player's input for a single step.
+#[derive(Debug, Copy, Clone)]
+pub enum Turn {
+    /// Turn 90 degrees counter-clockwise.
+    Left,
+    /// Turn 90 degrees clockwise.
+    Right,
+    /// Carry on in the current direction.
+    None,
+    /// Head in the given direction, regardless of the current one.
+    To(Direction)
+}
+
+impl Turn {
+    /// The direction a snake currently heading in `heading` should head in after this turn.
+    pub fn target(self, heading: Direction) -> Direction {
+        match self {
+            Turn::Left => heading.left(),
+            Turn::Right => heading.right(),
+            Turn::None => heading,
+            Turn::To(direction) => direction
+        }
+    }
+}
+
+
+/// Whether a game is still being played and, if not, how it ended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameStatus {
+    /// The snake filled every free tile.
+    Won,
+    /// The snake crashed into itself, a wall or (if it doesn't wrap around) an edge.
+    Lost,
+    Ongoing
+}
+
+/// Errors that can occur while setting up or playing a game. These all indicate a bug (the game's
+/// internal state becoming inconsistent) rather than anything the player did.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameError {
+    /// A fixed-capacity collection (the snake's tail or a set of coordinates) was already full.
+    CapacityExceeded,
+    /// There was no free tile to place something on.
+    NoFreeTile,
+    /// The snake's tail was unexpectedly empty.
+    EmptyTail,
+    /// The game state failed one of the checks in [`Game::check_invariants`].
+    InvariantViolated(&'static str)
+}
+
+/// The outcome of a single move/step.
+enum StepOutcome {
+    /// Grid full (player wins, unless the rules say to carry on)
+    Full(Coords),
+    /// Snake has collided with itself or a wall (player loses)
+    Collision(Coords),
+    /// Snake has collided with itself and is cut short at the given coordinates
+    Truncate(Coords),
+    /// Snake has collided with itself during a bonus round, or crashed while invincible, so stays
+    /// where it is
+    Blocked,
+    /// Snake has run into the hunter during a chase (player loses)
+    Caught,
+    /// Snake has eaten some bonus food
+    EatBonus(Coords),
+    /// Snake has eaten some food
+    Eat(Coords),
+    /// Snake has moved (and nothing else has happened)
+    Move(Coords)
+}
+
+/// The stages of a step, in the order they happen. Everything a step does belongs to one of them,
+/// and each happens at most once, so that the same inputs always change the game, and draw from
+/// its generator, in the same order. That is what keeps replays and games handed off to other
+/// boards bit-identical, so anything new that a step does should go in one of these stages (or a
+/// new one in its proper place), not wherever is convenient. Debug builds assert the order.
+///
+/// Front ends carry on after a step in their own fixed order: first any sound the step calls for,
+/// then drawing the next frame. Input for a step is gathered before it starts, never during it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Phase {
+    /// Between steps, when the game can be changed in any order, eg to reset it.
+    Idle,
+    /// Turning the snake as the player directs, then off any wall ahead.
+    Input,
+    /// Working out what the snake moves into and moving it, with any change to the score and
+    /// speed that follows.
+    Movement,
+    /// Placing new food, which draws from the generator.
+    SpawnFood,
+    /// Starting a bonus round, whose food also draws from the generator.
+    SpawnBonus,
+    /// Starting a chase, placing the hunter.
+    SpawnChase,
+    /// Counting down any bonus round and chase that were already going, and moving the hunter.
+    Decay,
+    /// Checking the game's invariants, in debug builds.
+    Checks
+}
+
+/// A basic pseudo-random number generator.
+struct Prng {
+    value: u32
+}
+
+impl Prng {
+    fn new(seed: u32) -> Self {
+        Self {value: seed}
+    }
+
+    /// Basic xorshift PRNG function: see https://en.wikipedia.org/wiki/Xorshift
+    fn xorshift32(mut input: u32) -> u32 {
+        input ^= input << 13;
+        input ^= input >> 17;
+        input ^= input << 5;
+        input
+    }
+
+    /// Return a pseudo-random u32.
+    fn random_u32(&mut self) -> u32 {
+        self.value = Self::xorshift32(self.value);
+        self.value
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+struct Coords {
+    // Signed ints to allow negative values (handy when checking if we have gone off the top or left
+    // of the grid)
+    row: i8,
+    col: i8
+}
+
+impl Coords {
+
+    /// Get random coordinates within a grid. `exclude` is an optional set of coordinates which
+    /// should be excluded from the output. Fails if `exclude` covers the whole grid.
+    fn random(
+        rng: &mut Prng,
+        exclude: Option<&CoordSet>
+    ) -> Result<Self, GameError> {
+        if exclude.is_some_and(|exc| exc.len() >= N_ROWS * N_COLS) {
+            return Err(GameError::NoFreeTile);
+        }
+        let mut coords = Coords {
+            row: ((rng.random_u32() as usize) % N_ROWS) as i8,
+            col: ((rng.random_u32() as usize) % N_COLS) as i8
+        };
+        while exclude.is_some_and(|exc| exc.contains(&coords)) {
+            coords = Coords {
+                row: ((rng.random_u32() as usize) % N_ROWS) as i8,
+                col: ((rng.random_u32() as usize) % N_COLS) as i8
+            }
+        }
+        Ok(coords)
+    }
+
+    fn is_out_of_bounds(&self) -> bool {
+        self.row < 0 || self.row >= (N_ROWS as i8) || self.col < 0 || self.col >= (N_COLS as i8)
+    }
+
+    /// Whether `other` is directly above, below, left or right of these coordinates, counting tiles
+    /// on opposite edges of the grid as next to each other if `wraparound` is set.
+    fn is_adjacent(&self, other: &Coords, wraparound: bool) -> bool {
+        if !wraparound {
+            return (self.row - other.row).abs() + (self.col - other.col).abs() == 1;
+        }
+        let d_row = (self.row - other.row).rem_euclid(N_ROWS as i8);
+        let d_col = (self.col - other.col).rem_euclid(N_COLS as i8);
+        let row_adjacent = d_row == 1 || d_row == (N_ROWS as i8) - 1;
+        let col_adjacent = d_col == 1 || d_col == (N_COLS as i8) - 1;
+        (row_adjacent && d_col == 0) || (col_adjacent && d_row == 0)
+    }
+}
+
+struct Snake {
+    /// Coordinates of the snake's head.
+    head: Coords,
+    /// Queue of coordinates of the rest of the snake's body. The end of the tail is at the front.
+    tail: Queue<Coords, TAIL_CAPACITY>,
+    /// A set containing all coordinates currently occupied by the snake (for fast collision
+    /// checking).
+    coord_set: CoordSet,
+    /// The direction the snake is currently moving in.
+    direction: Direction
+}
+
+impl Snake {
+    fn new() -> Result<Self, GameError> {
+        let head = Coords { row: START_ROW as i8, col: 2 };
+        let initial_tail = Coords { row: START_ROW as i8, col: 1 };
+        Self::at(head, initial_tail, Direction::Right)
+    }
+
+    /// A snake of the starting length with its head at `head`, heading in `direction`, and the
+    /// segment behind its head at `initial_tail`.
+    fn at(head: Coords, initial_tail: Coords, direction: Direction) -> Result<Self, GameError> {
+        let mut tail = Queue::new();
+        tail.enqueue(initial_tail).map_err(|_| GameError::CapacityExceeded)?;
+        let mut coord_set: CoordSet = FnvIndexSet::new();
+        insert_coords(&mut coord_set, head)?;
+        insert_coords(&mut coord_set, initial_tail)?;
+        Ok(Self {
+            head,
+            tail,
+            coord_set,
+            direction,
+        })
+    }
+
+    /// Move the snake onto the given coordinates. If `extend` is false, the snake's tail vacates
+    /// the rearmost tile.
+    fn move_snake(&mut self, coords: Coords, extend: bool) -> Result<(), GameError> {
+        // Vacate the rearmost tile first, as the head may be about to move onto it
+        if !extend {
+            let back = self.tail.dequeue().ok_or(GameError::EmptyTail)?;
+            self.coord_set.remove(&back);
+        }
+        // Location of head becomes front of tail
+        self.tail.enqueue(self.head).map_err(|_| GameError::CapacityExceeded)?;
+        // Head moves to new coords
+        self.head = coords;
+        insert_coords(&mut self.coord_set, coords)
+    }
+
+    /// The snake's segments in order, from its head to the end of its tail.
+    fn segments(&self) -> impl DoubleEndedIterator<Item = &Coords> {
+        core::iter::once(&self.head).chain(self.tail.iter().rev())
+    }
+
+    /// Cut the snake at `coords`, which must be part of its tail, removing that segment and every
+    /// segment behind it. Returns the number of segments removed.
+    fn truncate_at(&mut self, coords: Coords) -> Result<usize, GameError> {
+        let mut removed = 0;
+        loop {
+            let back = self.tail.dequeue().ok_or(GameError::EmptyTail)?;
+            self.coord_set.remove(&back);
+            removed += 1;
+            if back == coords {
+                return Ok(removed);
+            }
+        }
+    }
+
+    /// Cut the snake back to its starting length, keeping the head and the segment just behind it.
+    fn trim_to_start(&mut self) -> Result<(), GameError> {
+        while self.tail.len() > 1 {
+            let back = self.tail.dequeue().ok_or(GameError::EmptyTail)?;
+            self.coord_set.remove(&back);
+        }
+        Ok(())
+    }
+
+    /// Turn the snake to head in `target`, unless that would mean reversing back onto itself.
+    fn turn(&mut self, target: Direction) {
+        if target != self.direction.opposite() {
+            self.direction = target;
+        }
+    }
+}
+
+/// A bonus round in progress.
+struct BonusRound {
+    /// Game time left in the round, in milliseconds.
+    remaining_ms: u32,
+    /// Coordinates of the bonus food not yet eaten.
+    food: CoordSet
+}
+
+/// A chase in progress.
+struct Chase {
+    /// Coordinates of the hunter pursuing the snake.
+    hunter: Coords,
+    /// Steps left for the snake to survive. The hunter moves when this is even.
+    steps_left: u8
+}
+
+/// Struct to hold game state and associated behaviour, played according to the rules `R`.
+pub struct Game<R = StandardRules> {
+    rng: Prng,
+    snake: Snake,
+    food_coords: Coords,
+    /// Coordinates of any walls on the grid, which the snake dies if it runs into.
+    walls: CoordSet,
+    speed: u8,
+    /// Whether the game is still going. Variant games can set this themselves, eg to end a game
+    /// early under their own rules.
+    pub status: GameStatus,
+    score: u8,
+    /// Whether the snake wraps around to the opposite edge of the grid when it moves off one edge,
+    /// rather than crashing.
+    wraparound: bool,
+    /// Whether running into a wall turns the snake along it, rather than crashing. With wraparound
+    /// off, the edges of the grid count as walls.
+    bouncing_walls: bool,
+    /// Whether crashes (into walls, the snake itself or the hunter) stop the snake where it is
+    /// rather than ending the game.
+    invincible: bool,
+    /// The bonus round being played, if any.
+    bonus: Option<BonusRound>,
+    /// The chase being played, if any.
+    chase: Option<Chase>,
+    /// Number of times the snake has filled the grid and carried on.
+    loops: u8,
+    /// Number of pieces of food eaten (bonus food included), however many the snake has since
+    /// lost. It isn't saved in snapshots; a restored game counts from how far its snake has grown.
+    pieces_eaten: u32,
+    /// The stage of the step being taken.
+    phase: Phase,
+    /// Where the snake crashed at the last step, if it did (and wasn't invincible). This can be off
+    /// the grid, for a snake that ran off its edge. It isn't saved in snapshots, as it is only of
+    /// use once the game is over.
+    crash: Option<Coords>,
+    rules: R
+}
+
+impl Game {
+
+    /// Create a game with no walls, placing the food using a generator seeded with `rng_seed`.
+    pub fn new(rng_seed: u32) -> Result<Self, GameError> {
+        let mut rng = Prng::new(rng_seed);
+        let snake = Snake::new()?;
+        let food_coords = Coords::random(&mut rng, Some(&snake.coord_set))?;
+        Ok(Self {
+            rng,
+            snake,
+            food_coords,
+            walls: FnvIndexSet::new(),
+            speed: 1,
+            status: GameStatus::Ongoing,
+            score: 0,
+            wraparound: true,
+            bouncing_walls: false,
+            invincible: false,
+            bonus: None,
+            chase: None,
+            loops: 0,
+            pieces_eaten: 0,
+            phase: Phase::Idle,
+            crash: None,
+            rules: StandardRules
+        })
+    }
+
+    /// Create a game with a wall layout and food sequence determined entirely by `seed`, so that
+    /// every game created with the same seed plays out the same way given the same inputs.
+    pub fn with_generated_walls(seed: u32) -> Result<Self, GameError> {
+        let mut game = Self::new(seed)?;
+        let mut exclude = game.snake.coord_set.clone();
+        for col in 0..N_COLS {
+            insert_coords(&mut exclude, Coords { row: START_ROW as i8, col: col as i8 })?;
+        }
+        while game.walls.len() < GENERATED_WALLS {
+            let coords = Coords::random(&mut game.rng, Some(&exclude))?;
+            insert_coords(&mut game.walls, coords)?;
+            insert_coords(&mut exclude, coords)?;
+        }
+        game.place_food()?;
+        Ok(game)
+    }
+
+    /// Create a game with the given wall layout, where `true` marks a wall. Any walls in the
+    /// snake's starting row are ignored.
+    pub fn with_walls(seed: u32, layout: &[[bool; N_COLS]; N_ROWS]) -> Result<Self, GameError> {
+        let mut game = Self::new(seed)?;
+        for (row, cells) in layout.iter().enumerate() {
+            for (col, &wall) in cells.iter().enumerate() {
+                if wall && row != START_ROW {
+                    insert_coords(&mut game.walls, Coords { row: row as i8, col: col as i8 })?;
+                }
+            }
+        }
+        game.place_food()?;
+        Ok(game)
+    }
+}
+
+impl<R: Rules> Game<R> {
+    /// Replace the game's rules with `rules`, keeping the rest of its state. Any food already
+    /// placed stays where it is.
+    pub fn with_rules<S: Rules>(self, rules: S) -> Game<S> {
+        Game {
+            rng: self.rng,
+            snake: self.snake,
+            food_coords: self.food_coords,
+            walls: self.walls,
+            speed: self.speed,
+            status: self.status,
+            score: self.score,
+            wraparound: self.wraparound,
+            bouncing_walls: self.bouncing_walls,
+            invincible: self.invincible,
+            bonus: self.bonus,
+            chase: self.chase,
+            loops: self.loops,
+            pieces_eaten: self.pieces_eaten,
+            phase: self.phase,
+            crash: self.crash,
+            rules
+        }
+    }
+
+    /// The rules the game is played by.
+    pub fn rules(&self) -> &R {
+        &self.rules
+    }
+
+    /// The rules the game is played by, for variants whose rules keep state of their own.
+    pub fn rules_mut(&mut self) -> &mut R {
+        &mut self.rules
+    }
+
+    /// Reset the game state to start a new game, with no walls. Whether the snake wraps around is
+    /// kept.
+    pub fn reset(&mut self) -> Result<(), GameError> {
+        self.snake = Snake::new()?;
+        self.walls.clear();
+        self.bonus = None;
+        self.chase = None;
+        self.loops = 0;
+        self.pieces_eaten = 0;
+        self.crash = None;
+        self.place_food()?;
+        self.speed = self.rules.speed(0);
+        self.status = GameStatus::Ongoing;
+        self.score = 0;
+        Ok(())
+    }
+
+    /// Move the snake to a random starting position and direction, chosen with the game's own
+    /// generator so that games created with the same seed still play out the same way. The snake
+    /// keeps its starting length, and its head isn't next to a wall (or, with wraparound off, the
+    /// edge of the grid). The food is placed again, as the snake may now be covering it. If the
+    /// walls leave nowhere suitable, the snake stays where it is. This should be called before the
+    /// game's first step.
+    pub fn randomise_start(&mut self) -> Result<(), GameError> {
+        let starts = self.start_heads().count() * Direction::ALL.len();
+        if starts == 0 {
+            return Ok(());
+        }
+        let start = self.rng.random_u32() as usize % starts;
+        let head = self.start_heads()
+            .nth(start / Direction::ALL.len())
+            .ok_or(GameError::NoFreeTile)?;
+        let direction = Direction::ALL[start % Direction::ALL.len()];
+        let initial_tail = self.step_from(head, direction.opposite());
+        self.snake = Snake::at(head, initial_tail, direction)?;
+        self.place_food()?;
+        Ok(())
+    }
+
+    /// The tiles that [`Game::randomise_start`] can put the snake's head on: those that neither
+    /// are nor are next to a wall.
+    fn start_heads(&self) -> impl Iterator<Item = Coords> + '_ {
+        let tiles = (0..N_ROWS).flat_map(|row| {
+            (0..N_COLS).map(move |col| Coords { row: row as i8, col: col as i8 })
+        });
+        tiles.filter(move |&head| {
+            !self.is_wall(head)
+                && Direction::ALL.iter().all(|&d| !self.is_wall(self.step_from(head, d)))
+        })
+    }
+
+    /// The tiles occupied by the snake, walls, any bonus food and any hunter.
+    fn occupied(&self) -> Result<CoordSet, GameError> {
+        let mut occupied = self.snake.coord_set.clone();
+        for w in &self.walls {
+            insert_coords(&mut occupied, *w)?;
+        }
+        if let Some(bonus) = &self.bonus {
+            for f in &bonus.food {
+                insert_coords(&mut occupied, *f)?;
+            }
+        }
+        if let Some(chase) = &self.chase {
+            insert_coords(&mut occupied, chase.hunter)?;
+        }
+        Ok(occupied)
+    }
+
+    /// The grid as seen from the snake's head, with every tile in `occupied` taken.
+    fn grid(&self, occupied: &CoordSet) -> FoodGrid {
+        let mut grid = FoodGrid {
+            free: [[true; N_COLS]; N_ROWS],
+            head: (self.snake.head.row as usize, self.snake.head.col as usize),
+            wraparound: self.wraparound
+        };
+        for c in occupied {
+            grid.free[c.row as usize][c.col as usize] = false;
+        }
+        grid
+    }
+
+    /// Place food on the grid where the rules choose, or randomly if they don't mind.
+    fn place_food(&mut self) -> Result<Coords, GameError> {
+        self.enter_phase(Phase::SpawnFood);
+        let occupied = self.occupied()?;
+        let grid = self.grid(&occupied);
+        let coords = match self.rules.food_tile(&grid) {
+            Some((row, col)) if grid.is_free(row, col) => Coords { row: row as i8, col: col as i8 },
+            Some(_) => {
+                return Err(GameError::InvariantViolated("rules chose a taken tile for food"));
+            },
+            None => Coords::random(&mut self.rng, Some(&occupied))?
+        };
+        self.food_coords = coords;
+        Ok(coords)
+    }
+
+    /// "Wrap around" out of bounds coordinates (eg, coordinates that are off to the left of the
+    /// grid will appear in the rightmost column). Assumes that coordinates are out of bounds in one
+    /// dimension only.
+    fn wraparound(&self, coords: Coords) -> Coords {
+        if coords.row < 0 {
+            Coords { row: (N_ROWS - 1) as i8, ..coords }
+        } else if coords.row >= (N_ROWS as i8) {
+            Coords { row: 0, ..coords }
+        } else if coords.col < 0 {
+            Coords { col: (N_COLS - 1) as i8, ..coords }
+        } else {
+            Coords { col: 0, ..coords }
+        }
+    }
+
+    /// Determine the next tile that the snake will move on to (without actually moving the snake).
+    /// If wraparound is off, this may be out of bounds.
+    fn get_next_move(&self) -> Coords {
+        self.next_move_towards(self.snake.direction)
+    }
+
+    /// Determine the tile that the snake would move on to if it were heading in `direction`. If
+    /// wraparound is off, this may be out of bounds.
+    fn next_move_towards(&self, direction: Direction) -> Coords {
+        self.step_from(self.snake.head, direction)
+    }
+
+    /// Determine the tile next to `coords` in `direction`. If wraparound is off, this may be out of
+    /// bounds.
+    fn step_from(&self, coords: Coords, direction: Direction) -> Coords {
+        let next_move = match direction {
+            Direction::Up => Coords { row: coords.row - 1, col: coords.col },
+            Direction::Down => Coords { row: coords.row + 1, col: coords.col },
+            Direction::Left => Coords { row: coords.row, col: coords.col - 1 },
+            Direction::Right => Coords { row: coords.row, col: coords.col + 1 },
+        };
+        if self.wraparound && next_move.is_out_of_bounds() {
+            self.wraparound(next_move)
+        } else {
+            next_move
+        }
+    }
+
+    /// Whether the snake would crash into a wall (or the edge of the grid) on moving to `coords`.
+    fn is_wall(&self, coords: Coords) -> bool {
+        coords.is_out_of_bounds() || self.walls.contains(&coords)
+    }
+
+    /// Whether the snake can safely move on to `coords`.
+    fn is_open(&self, coords: Coords) -> bool {
+        // The end of the tail will have moved on by the time the head gets there
+        !self.is_wall(coords)
+            && (!self.snake.coord_set.contains(&coords) || self.snake.tail.peek() == Some(&coords))
+    }
+
+    /// With bouncing walls on, if the snake is about to run into a wall, turn it left or right
+    /// (whichever is open, preferring left) to slide along the wall instead. If neither is open,
+    /// the snake is left heading into the wall.
+    fn bounce_off_walls(&mut self) {
+        if let Some(direction) = self.bounce_direction() {
+            self.snake.direction = direction;
+        }
+    }
+
+    /// The direction that [`Game::bounce_off_walls`] would turn the snake in, if it would turn it.
+    fn bounce_direction(&self) -> Option<Direction> {
+        let heading = self.snake.direction;
+        if !self.bouncing_walls || !self.is_wall(self.next_move_towards(heading)) {
+            return None;
+        }
+        [heading.left(), heading.right()]
+            .iter()
+            .copied()
+            .find(|&direction| self.is_open(self.next_move_towards(direction)))
+    }
+
+    /// Start a bonus round, if the rules call for them, placing the bonus food on random free
+    /// tiles.
+    fn start_bonus_round(&mut self) -> Result<(), GameError> {
+        self.enter_phase(Phase::SpawnBonus);
+        let remaining_ms = self.rules.bonus_round_ms();
+        if remaining_ms == 0 {
+            return Ok(());
+        }
+        let mut occupied = self.occupied()?;
+        insert_coords(&mut occupied, self.food_coords)?;
+        let mut food = CoordSet::new();
+        while food.len() < BONUS_FOOD && occupied.len() < GRID_AREA {
+            let coords = Coords::random(&mut self.rng, Some(&occupied))?;
+            insert_coords(&mut food, coords)?;
+            insert_coords(&mut occupied, coords)?;
+        }
+        self.bonus = Some(BonusRound { remaining_ms, food });
+        Ok(())
+    }
+
+    /// Count down the bonus round by a step of `step_len_ms`, ending it once time runs out or all
+    /// the bonus food has been eaten.
+    fn tick_bonus_round(&mut self, step_len_ms: u32) {
+        if let Some(bonus) = &mut self.bonus {
+            bonus.remaining_ms = bonus.remaining_ms.saturating_sub(step_len_ms);
+            if bonus.remaining_ms == 0 || bonus.food.is_empty() {
+                self.bonus = None;
+            }
+        }
+    }
+
+    /// Start a chase, if the rules call for them, with the hunter on the free tile farthest from
+    /// the snake's head. If no free tile can be reached from the head there is no chase.
+    fn start_chase(&mut self) -> Result<(), GameError> {
+        self.enter_phase(Phase::SpawnChase);
+        let steps_left = self.rules.chase_steps();
+        if steps_left == 0 {
+            return Ok(());
+        }
+        let mut occupied = self.occupied()?;
+        insert_coords(&mut occupied, self.food_coords)?;
+        self.chase = self.grid(&occupied).farthest_reachable().map(|(row, col)| Chase {
+            hunter: Coords { row: row as i8, col: col as i8 },
+            steps_left
+        });
+        Ok(())
+    }
+
+    /// Count down the chase by a step, moving the hunter one tile closer to the snake's head on
+    /// every other step and ending the chase once the snake has survived it. Returns whether the
+    /// hunter caught the snake.
+    fn tick_chase(&mut self) -> Result<bool, GameError> {
+        let Some(chase) = &self.chase else {
+            return Ok(false);
+        };
+        let (hunter, steps_left) = (chase.hunter, chase.steps_left - 1);
+        if steps_left == 0 {
+            self.chase = None;
+            return Ok(false);
+        }
+        let mut next = hunter;
+        if steps_left.is_multiple_of(2) {
+            // The hunter goes only where food could, taking the first step of a shortest path to
+            // the head; if there is no such path it waits
+            let mut occupied = self.occupied()?;
+            insert_coords(&mut occupied, self.food_coords)?;
+            occupied.remove(&hunter);
+            let grid = self.grid(&occupied);
+            let distances = grid.distances_to_head();
+            let head = grid.head();
+            let mut best = distances[hunter.row as usize][hunter.col as usize];
+            for (row, col) in grid.neighbours((hunter.row as usize, hunter.col as usize)) {
+                if (row, col) == head {
+                    return Ok(true);
+                }
+                if distances[row][col].is_some_and(|d| best.is_some_and(|b| d < b)) {
+                    best = distances[row][col];
+                    next = Coords { row: row as i8, col: col as i8 };
+                }
+            }
+        }
+        self.chase = Some(Chase { hunter: next, steps_left });
+        Ok(false)
+    }
+
+    /// Assess the snake's next move and return the outcome. Doesn't actually update the game state.
+    fn get_step_outcome(&self) -> Result<StepOutcome, GameError> {
+        let next_move = self.get_next_move();
+        Ok(if self.is_wall(next_move) {
+            StepOutcome::Collision(next_move)
+        } else if self.chase.as_ref().is_some_and(|c| c.hunter == next_move) {
+            StepOutcome::Caught
+        } else if self.snake.coord_set.contains(&next_move) {
+            // We haven't moved the snake yet, so if the next move is at the end of the tail, there
+            // won't actually be any collision (as the tail will have moved by the time the head
+            // moves onto the tile)
+            if next_move != *self.snake.tail.peek().ok_or(GameError::EmptyTail)? {
+                match self.rules.self_collision() {
+                    _ if self.bonus.is_some() => StepOutcome::Blocked,
+                    SelfCollision::Crash => StepOutcome::Collision(next_move),
+                    SelfCollision::Truncate => StepOutcome::Truncate(next_move)
+                }
+            } else {
+                StepOutcome::Move(next_move)
+            }
+        } else if next_move == self.food_coords {
+            // The grid is full if, after eating, the snake will occupy every tile that isn't a wall
+            // (or bonus food, which isn't replaced once eaten, or the hunter)
+            let bonus_food = self.bonus.as_ref().map_or(0, |b| b.food.len());
+            let hunter = self.chase.is_some() as usize;
+            if self.snake.tail.len() + 2 + bonus_food + hunter
+                == N_ROWS * N_COLS - self.walls.len() {
+                StepOutcome::Full(next_move)
+            } else {
+                StepOutcome::Eat(next_move)
+            }
+        } else if self.bonus.as_ref().is_some_and(|b| b.food.contains(&next_move)) {
+            StepOutcome::EatBonus(next_move)
+        } else {
+            StepOutcome::Move(next_move)
+        })
+    }
+
+    /// The number of pieces of food the snake has eaten, less any it has since lost: how far it
+    /// has grown beyond its starting length.
+    fn food_eaten(&self) -> u8 {
+        (self.snake.tail.len() - 1) as u8
+    }
+
+    /// Handle the outcome of a step, updating the game's internal state.
+    fn handle_step_outcome(&mut self, outcome: StepOutcome) -> Result<(), GameError> {
+        let (outcome, status) = match outcome {
+            StepOutcome::Collision(c) => {
+                self.crash = Some(c);
+                (Outcome::Crashed, GameStatus::Lost)
+            },
+            StepOutcome::Full(c) => match self.rules.full_grid() {
+                FullGrid::Win => (Outcome::Filled, GameStatus::Won),
+                FullGrid::Loop => {
+                    self.snake.move_snake(c, true)?;
+                    self.snake.trim_to_start()?;
+                    self.loops = self.loops.saturating_add(1);
+                    self.pieces_eaten = self.pieces_eaten.saturating_add(1);
+                    let points = self.rules.food_points(self.score);
+                    self.score = self.score.saturating_add(points);
+                    self.speed = self.rules.speed(self.food_eaten());
+                    self.place_food()?;
+                    (Outcome::Looped, GameStatus::Ongoing)
+                }
+            },
+            StepOutcome::Caught => {
+                self.crash = self.chase.as_ref().map(|chase| chase.hunter);
+                (Outcome::Crashed, GameStatus::Lost)
+            },
+            StepOutcome::Blocked => (Outcome::Crashed, GameStatus::Ongoing),
+            StepOutcome::Truncate(c) => {
+                // The segment that was hit goes along with those behind it, and the head takes its
+                // place, so the snake loses one segment fewer than were removed. The head and the
+                // segment behind it are never removed, so the snake can't be cut too short to play.
+                let lost = self.snake.truncate_at(c)? - 1;
+                self.snake.move_snake(c, true)?;
+                self.score = self.score.saturating_sub(lost as u8);
+                self.speed = self.rules.speed(self.food_eaten());
+                (Outcome::Truncated, GameStatus::Ongoing)
+            },
+            StepOutcome::Eat(c) => {
+                self.snake.move_snake(c, true)?;
+                self.pieces_eaten = self.pieces_eaten.saturating_add(1);
+                let points = self.rules.food_points(self.score);
+                self.score = self.score.saturating_add(points);
+                let old_speed = self.speed;
+                self.speed = self.rules.speed(self.food_eaten());
+                self.place_food()?;
+                // Each speed-up marks a new level, so earns a bonus round
+                if self.speed > old_speed {
+                    self.start_bonus_round()?;
+                }
+                let level = self.rules.level(self.food_eaten());
+                if level > self.rules.level(self.food_eaten() - 1)
+                    && level.is_multiple_of(CHASE_EVERY_LEVELS)
+                    && self.chase.is_none() {
+                    self.start_chase()?;
+                }
+                (Outcome::Ate, GameStatus::Ongoing)
+            },
+            StepOutcome::EatBonus(c) => {
+                // Bonus food is worth points, but doesn't grow the snake
+                self.snake.move_snake(c, false)?;
+                if let Some(bonus) = &mut self.bonus {
+                    bonus.food.remove(&c);
+                }
+                self.pieces_eaten = self.pieces_eaten.saturating_add(1);
+                let points = self.rules.food_points(self.score);
+                self.score = self.score.saturating_add(points);
+                (Outcome::Ate, GameStatus::Ongoing)
+            },
+            StepOutcome::Move(c) => {
+                self.snake.move_snake(c, false)?;
+                (Outcome::Moved, GameStatus::Ongoing)
+            }
+        };
+        self.status = self.rules.status(outcome, status);
+        Ok(())
+    }
+
+    /// Advance the game by one step, first turning the snake as `turn` directs. An error means
+    /// the game state has become inconsistent, and the game should be abandoned. In debug builds
+    /// the game's invariants are also checked after every step.
+    ///
+    /// Every step goes through the same stages in the same order, so that replays play out
+    /// exactly the same way: turning the snake, moving it (and scoring), placing new food,
+    /// starting a bonus round, starting a chase, and finally counting down any bonus round and
+    /// chase that were already going.
+    pub fn step(&mut self, turn: Turn) -> Result<(), GameError> {
+        self.phase = Phase::Input;
+        let stepped = self.take_step(turn);
+        // Even a step that failed part way through is over
+        self.phase = Phase::Idle;
+        stepped
+    }
+
+    /// Take a step for [`Game::step`], going through each of its stages in turn.
+    fn take_step(&mut self, turn: Turn) -> Result<(), GameError> {
+        // The step takes as long as the speed before it says, even if it speeds the snake up
+        let step_len_ms = self.step_len_ms();
+        let in_bonus_round = self.bonus.is_some();
+        let in_chase = self.chase.is_some();
+        self.crash = None;
+        self.snake.turn(turn.target(self.snake.direction));
+        self.bounce_off_walls();
+        self.enter_phase(Phase::Movement);
+        let outcome = match self.get_step_outcome()? {
+            // An invincible snake stops short instead
+            StepOutcome::Collision(_) | StepOutcome::Caught if self.invincible => StepOutcome::Blocked,
+            outcome => outcome
+        };
+        self.handle_step_outcome(outcome)?;
+        self.enter_phase(Phase::Decay);
+        // A bonus round started by this step gets its full length
+        if in_bonus_round {
+            self.tick_bonus_round(step_len_ms);
+        }
+        // Likewise a chase started by this step, which also gives the snake a head start
+        let caught = in_chase && self.status == GameStatus::Ongoing && self.tick_chase()?;
+        if caught && !self.invincible {
+            self.crash = self.chase.as_ref().map(|chase| chase.hunter);
+            self.status = self.rules.status(Outcome::Crashed, GameStatus::Lost);
+        }
+        self.enter_phase(Phase::Checks);
+        #[cfg(debug_assertions)]
+        self.check_invariants().map_err(GameError::InvariantViolated)?;
+        Ok(())
+    }
+
+    /// Move on to `phase` of the step being taken, asserting (in debug builds) that it comes after
+    /// the stage the step is at. Between steps this does nothing, as things can happen in any
+    /// order then.
+    fn enter_phase(&mut self, phase: Phase) {
+        if self.phase == Phase::Idle {
+            return;
+        }
+        debug_assert!(phase > self.phase, "{:?} happened after {:?} in a step", phase, self.phase);
+        self.phase = phase;
+    }
+
+    /// Calculate the length of time to wait between game steps, in milliseconds. Generally this
+    /// will get lower as the player's score increases, as the rules decide.
+    pub fn step_len_ms(&self) -> u32 {
+        self.rules.step_len_ms(self.speed)
+    }
+
+    /// Return an array representing the game state, which can be used to display the state on the
+    /// microbit's LED matrix. Each `_brightness` parameter should be a value between 0 and 9.
+    pub fn game_matrix(
+        &self,
+        head_brightness: u8,
+        tail_brightness: u8,
+        food_brightness: u8,
+        wall_brightness: u8
+    ) -> [[u8; N_COLS]; N_ROWS] {
+        self.tile_matrix(head_brightness, tail_brightness, food_brightness, wall_brightness, 0)
+    }
+
+    /// Return an array with the given value for each kind of thing on each tile of the grid, eg a
+    /// colour for displays that can show them. [`Game::game_matrix`] is this with brightnesses.
+    pub fn tile_matrix<T: Copy>(
+        &self,
+        head: T,
+        tail: T,
+        food: T,
+        wall: T,
+        empty: T
+    ) -> [[T; N_COLS]; N_ROWS] {
+        let mut values = [[empty; N_COLS]; N_ROWS];
+        for w in &self.walls {
+            values[w.row as usize][w.col as usize] = wall
+        }
+        values[self.snake.head.row as usize][self.snake.head.col as usize] = head;
+        for t in &self.snake.tail {
+            values[t.row as usize][t.col as usize] = tail
+        }
+        values[self.food_coords.row as usize][self.food_coords.col as usize] = food;
+        if let Some(bonus) = &self.bonus {
+            for f in &bonus.food {
+                values[f.row as usize][f.col as usize] = food;
+            }
+        }
+        values
+    }
+
+    /// Return an array representing the game score, which can be used to display the score on the
+    /// microbit's LED matrix (by illuminating the equivalent number of LEDs, going left->right and
+    /// top->bottom).
+    pub fn score_matrix(&self) -> [[u8; N_COLS]; N_ROWS] {
+        count_matrix(self.score as usize)
+    }
+
+    /// Check that the game's internal state is consistent, returning a description of the first
+    /// problem found if not. Intended for tests and debugging; a game driven only through its
+    /// public methods should never fail this check.
+    pub fn check_invariants(&self) -> Result<(), &'static str> {
+        let snake = &self.snake;
+        if snake.coord_set.len() != snake.tail.len() + 1 {
+            return Err("snake's coordinate set and segments differ in size");
+        }
+        if !snake.coord_set.contains(&snake.head)
+            || snake.tail.iter().any(|t| !snake.coord_set.contains(t)) {
+            return Err("snake's coordinate set does not match its segments");
+        }
+        if snake.coord_set.iter().any(|c| c.is_out_of_bounds()) {
+            return Err("snake is out of bounds");
+        }
+        let mut segments = snake.tail.iter().chain(core::iter::once(&snake.head));
+        let mut prev = segments.next();
+        for segment in segments {
+            // Wraparound may have been turned off since the snake last crossed an edge
+            if prev.is_some_and(|p| !p.is_adjacent(segment, true)) {
+                return Err("snake's segments are not connected");
+            }
+            prev = Some(segment);
+        }
+        if snake.coord_set.iter().any(|c| self.walls.contains(c)) {
+            return Err("snake overlaps a wall");
+        }
+        if self.food_coords.is_out_of_bounds() {
+            return Err("food is out of bounds");
+        }
+        if matches!(self.status, GameStatus::Ongoing)
+            && (snake.coord_set.contains(&self.food_coords) || self.walls.contains(&self.food_coords)) {
+            return Err("food is on the snake or a wall");
+        }
+        if let Some(bonus) = &self.bonus {
+            if bonus.food.iter().any(|f| {
+                f.is_out_of_bounds() || snake.coord_set.contains(f) || self.walls.contains(f)
+                    || *f == self.food_coords
+            }) {
+                return Err("bonus food is out of bounds or on something else");
+            }
+        }
+        if let Some(chase) = &self.chase {
+            let h = &chase.hunter;
+            if h.is_out_of_bounds() || snake.coord_set.contains(h) || self.walls.contains(h)
+                || *h == self.food_coords
+                || self.bonus.as_ref().is_some_and(|b| b.food.contains(h)) {
+                return Err("hunter is out of bounds or on something else");
+            }
+        }
+        Ok(())
+    }
+
+    /// Set whether the snake wraps around to the opposite edge of the grid when it moves off one
+    /// edge (the default), or crashes.
+    pub fn set_wraparound(&mut self, wraparound: bool) {
+        self.wraparound = wraparound;
+    }
+
+    /// Whether the snake wraps around the edges of the grid.
+    pub fn wraps_around(&self) -> bool {
+        self.wraparound
+    }
+
+    /// Set whether running into a wall turns the snake along it rather than crashing, for a more
+    /// forgiving game. Off by default.
+    pub fn set_bouncing_walls(&mut self, bouncing_walls: bool) {
+        self.bouncing_walls = bouncing_walls;
+    }
+
+    /// Whether running into a wall turns the snake rather than crashing.
+    pub fn has_bouncing_walls(&self) -> bool {
+        self.bouncing_walls
+    }
+
+    /// Set whether the snake is invincible: crashing into a wall, itself or the hunter stops it
+    /// where it is (until it is turned out of the way) rather than ending the game. Off by default.
+    pub fn set_invincible(&mut self, invincible: bool) {
+        self.invincible = invincible;
+    }
+
+    /// Whether crashes stop the snake rather than ending the game.
+    pub fn is_invincible(&self) -> bool {
+        self.invincible
+    }
+
+    /// Whether a bonus round is being played.
+    pub fn in_bonus_round(&self) -> bool {
+        self.bonus.is_some()
+    }
+
+    /// Where the hunter is, as a `(row, col)` pair, if a chase is being played. The hunter isn't
+    /// drawn by [`Game::game_matrix`], so that front ends can make it stand out, eg by blinking.
+    pub fn hunter(&self) -> Option<(usize, usize)> {
+        self.chase.as_ref().map(|c| (c.hunter.row as usize, c.hunter.col as usize))
+    }
+
+    /// The number of times the snake has filled the grid and been trimmed back to carry on, under
+    /// rules that allow it.
+    pub fn loops(&self) -> u8 {
+        self.loops
+    }
+
+    /// The number of pieces of food the snake has eaten, bonus food included, even if it has since
+    /// lost the length it gained from them. Unlike the score, this doesn't depend on how many
+    /// points the rules give for food.
+    pub fn pieces_eaten(&self) -> u32 {
+        self.pieces_eaten
+    }
+
+    /// Where the snake's head is, as a `(row, col)` pair.
+    pub fn head(&self) -> (usize, usize) {
+        (self.snake.head.row as usize, self.snake.head.col as usize)
+    }
+
+    /// The snake's tiles as `(row, col)` pairs, from the end of its tail to its head.
+    pub fn snake_tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.segments().rev()
+    }
+
+    /// The snake's tiles as `(row, col)` pairs, in order from its head to the end of its tail.
+    pub fn segments(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + '_ {
+        self.snake.segments().map(|c| (c.row as usize, c.col as usize))
+    }
+
+    /// Where the snake crashed (into a wall, itself or the hunter) at the last step, as a
+    /// `(row, col)` pair, if it did. This is `None` if the crash was off the edge of the grid, and
+    /// for crashes that an invincible snake survived.
+    pub fn crash_site(&self) -> Option<(usize, usize)> {
+        self.crash
+            .filter(|coords| !coords.is_out_of_bounds())
+            .map(|coords| (coords.row as usize, coords.col as usize))
+    }
+
+    /// The tile the snake would move on to at the next step if it carried straight on, as a
+    /// `(row, col)` pair, or `None` if that would take it off the grid.
+    pub fn tile_ahead(&self) -> Option<(usize, usize)> {
+        let coords = self.get_next_move();
+        if coords.is_out_of_bounds() {
+            None
+        } else {
+            Some((coords.row as usize, coords.col as usize))
+        }
+    }
+
+    /// Where the food is, as a `(row, col)` pair.
+    pub fn food(&self) -> (usize, usize) {
+        (self.food_coords.row as usize, self.food_coords.col as usize)
+    }
+
+    /// Move the food to the tile at `(row, col)`, for testing. Returns whether it was moved, which
+    /// it isn't if the tile is off the grid or taken by the snake, a wall, bonus food or the
+    /// hunter.
+    pub fn move_food(&mut self, row: usize, col: usize) -> Result<bool, GameError> {
+        if row >= N_ROWS || col >= N_COLS {
+            return Ok(false);
+        }
+        let coords = Coords { row: row as i8, col: col as i8 };
+        if self.occupied()?.contains(&coords) {
+            return Ok(false);
+        }
+        self.food_coords = coords;
+        Ok(true)
+    }
+
+    /// The direction the snake is currently heading in.
+    pub fn direction(&self) -> Direction {
+        self.snake.direction
+    }
+
+    /// The number of pieces of food eaten so far.
+    pub fn score(&self) -> u8 {
+        self.score
+    }
+
+    /// How fast the snake is moving, from 1 (its slowest) up to the rules' top speed.
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    /// The level the snake has reached, as the rules count levels from the food it has eaten.
+    pub fn level(&self) -> u8 {
+        self.rules.level(self.food_eaten())
+    }
+
+    /// Whether carrying straight on at the next step would run the snake into something that
+    /// ends the game. Front ends can use this to spot near misses: if there was danger ahead and
+    /// the game goes on after the next step, the player turned out of the way just in time.
+    /// Walls that the snake would bounce off don't count, and neither does a hunter moving onto
+    /// the snake's head. Nothing counts for an invincible snake.
+    pub fn danger_ahead(&self) -> bool {
+        !self.invincible
+            && self.bounce_direction().is_none()
+            && matches!(
+                self.get_step_outcome(),
+                Ok(StepOutcome::Collision(_) | StepOutcome::Caught)
+            )
+    }
+
+    /// The length of the snake, including its head.
+    pub fn snake_len(&self) -> usize {
+        self.snake.tail.len() + 1
+    }
+}
+
+/// Return an array representing a count, by illuminating the equivalent number of LEDs (going
+/// left->right and top->bottom), in a matrix the size of the grid or of any other display. Counts
+/// larger than the matrix light every LED.
+pub fn count_matrix<const ROWS: usize, const COLS: usize>(count: usize) -> [[u8; COLS]; ROWS] {
+    let mut values = [[0u8; COLS]; ROWS];
+    let count = count.min(ROWS * COLS);
+    let full_rows = count / COLS;
+    for row in values.iter_mut().take(full_rows) {
+        *row = [1; COLS];
+    }
+    if let Some(row) = values.get_mut(full_rows) {
+        for value in row.iter_mut().take(count % COLS) {
+            *value = 1;
+        }
+    }
+    values
+}
+
+/// Return the matrix `elapsed` out of `total` of the way from `from` to `to`, fading each LED
+/// between its two brightnesses. Blending the matrices shown before and after a step animates the
+/// step: the snake's head brightens into its new tile as the tile it left dims to the brightness of
+/// the rest of the snake, and the tile its tail left fades out. `elapsed` and `total` can be in any
+/// units; once `elapsed` reaches `total`, `to` is returned as it is. The matrices can be of any size,
+/// as long as they are the same size.
+pub fn blend_matrix<const ROWS: usize, const COLS: usize>(
+    from: &[[u8; COLS]; ROWS],
+    to: &[[u8; COLS]; ROWS],
+    elapsed: u32,
+    total: u32
+) -> [[u8; COLS]; ROWS] {
+    if elapsed >= total {
+        return *to;
+    }
+    let mut values = *to;
+    for (row, from_row) in values.iter_mut().zip(from.iter()) {
+        for (value, &from_value) in row.iter_mut().zip(from_row.iter()) {
+            let change = (*value as i32 - from_value as i32) * elapsed as i32 / total as i32;
+            *value = (from_value as i32 + change) as u8;
+        }
+    }
+    values
+}
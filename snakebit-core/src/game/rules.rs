@@ -0,0 +1,285 @@
+//! Hooks for changing the rules of the game without changing how the snake moves.
+//!
+//! A [`Game`](super::Game) consults its [`Rules`] whenever the outcome of a step is decided, the
+//! snake eats or food is placed. [`StandardRules`] gives the normal game; a variant implements
+//! [`Rules`] itself, overriding only the hooks it needs, and is attached with
+//! [`Game::with_rules`](super::Game::with_rules).
+
+use core::cmp::{max, min};
+
+use super::{GameStatus, GRID_AREA, N_COLS, N_ROWS};
+
+/// What happened to the snake on a single step, before the rules decide how the game is affected.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    /// The snake ate the last piece of food there was room for, filling the grid.
+    Filled,
+    /// The snake filled the grid and, as the rules call for, was trimmed back to its starting
+    /// length to carry on.
+    Looped,
+    /// The snake ran into itself, a wall or (if it doesn't wrap around) an edge, and didn't move.
+    Crashed,
+    /// The snake ran into its own body and was cut short where it hit, losing the segments
+    /// behind that point.
+    Truncated,
+    /// The snake ate a piece of food.
+    Ate,
+    /// The snake moved on to an empty tile.
+    Moved
+}
+
+/// How the game responds to the snake running into its own body.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SelfCollision {
+    /// The snake crashes, as it would into a wall.
+    Crash,
+    /// The snake is cut at the point it hit, losing the segments (and as many points) behind it.
+    /// As the snake can't run into the segment just behind its head, it is always left long
+    /// enough to carry on, so this never loses the game.
+    Truncate
+}
+
+/// How the game responds to the snake filling the grid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FullGrid {
+    /// The game is won.
+    Win,
+    /// The snake is trimmed back to its starting length, keeping its score, and the game carries
+    /// on. The speed goes back to what it was at that length.
+    Loop
+}
+
+/// The rules of a game. Every hook has a default matching the standard game, so implementors
+/// only need to override the ones they want to change.
+pub trait Rules {
+    /// Scoring: the number of points to award for eating a piece of food, given the score before
+    /// eating it. The score saturates rather than overflowing.
+    fn food_points(&mut self, _score: u8) -> u8 {
+        1
+    }
+
+    /// The speed the snake should move at once it has eaten `food_eaten` pieces of food (not
+    /// counting any it has since lost), starting at 1 and getting faster as the number gets
+    /// higher.
+    fn speed(&self, food_eaten: u8) -> u8 {
+        SpeedSchedule::default().speed(food_eaten)
+    }
+
+    /// The level the snake has reached once it has eaten `food_eaten` pieces of food, counting
+    /// from 1. Levels go up with each speed-up, but carry on going up once the snake can't get
+    /// any faster.
+    fn level(&self, food_eaten: u8) -> u8 {
+        SpeedSchedule::default().level(food_eaten)
+    }
+
+    /// The time between steps at the given speed, in milliseconds. Speeds above 5 are no faster
+    /// than 5.
+    fn step_len_ms(&self, speed: u8) -> u32 {
+        let result = 1000 - (200 * ((speed as i32) - 1));
+        max(result, 200) as u32
+    }
+
+    /// Outcome overrides: decide the game's status after a step with the given outcome, where
+    /// `status` is what the standard rules would decide. Returning [`GameStatus::Ongoing`] after a
+    /// crash lets the game carry on with the snake where it was, still heading into the obstacle
+    /// unless the player turns it.
+    fn status(&mut self, _outcome: Outcome, status: GameStatus) -> GameStatus {
+        status
+    }
+
+    /// The length of the bonus round played each time the snake speeds up, in milliseconds of
+    /// game time, or 0 for no bonus rounds (the default). During a bonus round extra food is
+    /// placed, worth points but not growing the snake, and running into itself just stops the
+    /// snake rather than ending the game.
+    fn bonus_round_ms(&self) -> u32 {
+        0
+    }
+
+    /// The number of steps the snake must survive in the chase played at every fifth level, or 0
+    /// for no chases. During a chase a hunter pursues the snake's head, moving every other step;
+    /// the snake is caught, and the game lost, if either runs into the other.
+    fn chase_steps(&self) -> u8 {
+        30
+    }
+
+    /// What happens when the snake runs into its own body.
+    fn self_collision(&self) -> SelfCollision {
+        SelfCollision::Crash
+    }
+
+    /// What happens when the snake fills the grid.
+    fn full_grid(&self) -> FullGrid {
+        FullGrid::Win
+    }
+
+    /// Spawn policy: choose the tile for the next piece of food, as a `(row, col)` pair, or return
+    /// `None` to place it on a random free tile. Choosing a tile that isn't free in `grid` is an
+    /// error.
+    fn food_tile(&mut self, _grid: &FoodGrid) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+/// How the snake speeds up as it eats.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SpeedSchedule {
+    /// Number of pieces of food to eat between each speed-up.
+    pub food_per_speed_up: u8,
+    /// The speed after which the snake stops speeding up.
+    pub max_speed: u8
+}
+
+impl Default for SpeedSchedule {
+    /// The standard schedule, speeding up every five pieces of food up to the fastest speed.
+    fn default() -> Self {
+        Self { food_per_speed_up: 5, max_speed: 5 }
+    }
+}
+
+impl SpeedSchedule {
+    /// The speed once the snake has eaten `food_eaten` pieces of food.
+    pub fn speed(&self, food_eaten: u8) -> u8 {
+        min(self.level(food_eaten), max(self.max_speed, 1))
+    }
+
+    /// The level once the snake has eaten `food_eaten` pieces of food: the speed it would be
+    /// moving at if there were no maximum.
+    pub fn level(&self, food_eaten: u8) -> u8 {
+        1 + food_eaten / max(self.food_per_speed_up, 1)
+    }
+}
+
+/// The state of the grid when food is about to be placed, as seen by [`Rules::food_tile`].
+pub struct FoodGrid {
+    /// Which tiles are free of the snake and walls.
+    pub(super) free: [[bool; N_COLS]; N_ROWS],
+    pub(super) head: (usize, usize),
+    pub(super) wraparound: bool
+}
+
+impl FoodGrid {
+    /// Whether the tile at `row`, `col` is free of the snake and walls. Tiles off the grid aren't.
+    pub fn is_free(&self, row: usize, col: usize) -> bool {
+        row < N_ROWS && col < N_COLS && self.free[row][col]
+    }
+
+    /// The tile the snake's head is on.
+    pub fn head(&self) -> (usize, usize) {
+        self.head
+    }
+
+    /// The tiles the snake could move to from `tile` in one step, whether or not they are free.
+    pub(super) fn neighbours(
+        &self,
+        (row, col): (usize, usize)
+    ) -> impl Iterator<Item = (usize, usize)> {
+        const STEPS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        let wraparound = self.wraparound;
+        STEPS.iter().filter_map(move |&(d_row, d_col)| {
+            let (row, col) = (row as isize + d_row, col as isize + d_col);
+            let (n_rows, n_cols) = (N_ROWS as isize, N_COLS as isize);
+            if (0..n_rows).contains(&row) && (0..n_cols).contains(&col) {
+                Some((row as usize, col as usize))
+            } else if wraparound {
+                Some((row.rem_euclid(n_rows) as usize, col.rem_euclid(n_cols) as usize))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The free tile that takes the snake's head the most steps to reach, going only through free
+    /// tiles, or `None` if no free tile can be reached. Ties go to whichever tile is found first.
+    pub fn farthest_reachable(&self) -> Option<(usize, usize)> {
+        // The last tile visited is the farthest away
+        let mut farthest = None;
+        self.search(|tile, _| farthest = Some(tile));
+        farthest
+    }
+
+    /// The number of steps it takes to get from each free tile to the snake's head, going only
+    /// through free tiles, or `None` for tiles that aren't free or can't reach the head.
+    pub(super) fn distances_to_head(&self) -> [[Option<u8>; N_COLS]; N_ROWS] {
+        let mut distances = [[None; N_COLS]; N_ROWS];
+        self.search(|(row, col), distance| distances[row][col] = Some(distance));
+        distances
+    }
+
+    /// Breadth-first search from the head through free tiles, calling `visit` with each tile
+    /// reached and the number of steps it took, nearest first.
+    fn search(&self, mut visit: impl FnMut((usize, usize), u8)) {
+        let mut visited = [[false; N_COLS]; N_ROWS];
+        let mut queue = [((0, 0), 0); GRID_AREA];
+        let (mut next, mut len) = (0, 0);
+        visited[self.head.0][self.head.1] = true;
+        queue[len] = (self.head, 0);
+        len += 1;
+        while next < len {
+            let (tile, distance) = queue[next];
+            next += 1;
+            for (row, col) in self.neighbours(tile) {
+                if self.free[row][col] && !visited[row][col] {
+                    visited[row][col] = true;
+                    queue[len] = ((row, col), distance + 1);
+                    len += 1;
+                    visit((row, col), distance + 1);
+                }
+            }
+        }
+    }
+}
+
+/// The rules of the standard game.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StandardRules;
+
+impl Rules for StandardRules {}
+
+/// The standard game, except that running into itself cuts the snake short rather than ending the
+/// game.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TruncatingRules;
+
+impl Rules for TruncatingRules {
+    fn self_collision(&self) -> SelfCollision {
+        SelfCollision::Truncate
+    }
+}
+
+/// An endless game, which can never be won: each time the snake fills the grid it is trimmed back
+/// to its starting length and play carries on.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MarathonRules;
+
+impl Rules for MarathonRules {
+    fn full_grid(&self) -> FullGrid {
+        FullGrid::Loop
+    }
+}
+
+/// A harder game, where each piece of food is placed as far from the snake's head as it can get,
+/// making every piece a longer trek as the snake grows.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AdversarialRules;
+
+impl Rules for AdversarialRules {
+    fn food_tile(&mut self, grid: &FoodGrid) -> Option<(usize, usize)> {
+        grid.farthest_reachable()
+    }
+}
+
+/// A faster game, where the snake starts at 400ms a step and each piece of food is worth two
+/// points. The snake speeds up on the same schedule as in the standard game.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BlitzRules;
+
+impl Rules for BlitzRules {
+    fn food_points(&mut self, _score: u8) -> u8 {
+        2
+    }
+
+    fn step_len_ms(&self, speed: u8) -> u32 {
+        let result = 400 - (80 * ((speed as i32) - 1));
+        max(result, 80) as u32
+    }
+}
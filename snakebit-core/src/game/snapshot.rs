@@ -0,0 +1,338 @@
+//! Compact binary serialization of the full game state, so that a game can be saved to flash,
+//! streamed over serial for debugging or sent to another device.
+//!
+//! The format is a version byte followed by the fields of the game state. Coordinates are packed
+//! into a single byte each (`row * N_COLS + col`). Multi-byte integers are little-endian.
+//!
+//! | Bytes    | Field                                          |
+//! |----------|------------------------------------------------|
+//! | 1        | Format version                                 |
+//! | 4        | PRNG state                                     |
+//! | 1        | Speed                                          |
+//! | 1        | Score                                          |
+//! | 1        | Status                                         |
+//! | 1        | Direction                                      |
+//! | 1        | Food coordinates                               |
+//! | 1        | Head coordinates                               |
+//! | 1        | Flags (see below)                              |
+//! | 1        | Number of times the snake has looped           |
+//! | 1        | Tail length (`n`)                              |
+//! | `n`      | Tail coordinates, from the end of the tail up  |
+//! | 1        | Number of walls (`w`)                          |
+//! | `w`      | Wall coordinates                               |
+//!
+//! Bit 0 of the flags is set if the snake wraps around, bit 1 if bouncing walls are on, bit 2 if a
+//! bonus round is in progress, bit 3 if a chase is in progress and bit 4 if the snake is
+//! invincible. With a bonus round, the walls are followed by:
+//!
+//! | Bytes    | Field                                          |
+//! |----------|------------------------------------------------|
+//! | 4        | Time left in the bonus round, in milliseconds  |
+//! | 1        | Number of bonus food (`b`)                     |
+//! | `b`      | Bonus food coordinates                         |
+//!
+//! With a chase, the last section (walls or bonus food) is followed by:
+//!
+//! | Bytes    | Field                                          |
+//! |----------|------------------------------------------------|
+//! | 1        | Hunter coordinates                             |
+//! | 1        | Steps left in the chase                        |
+//!
+//! A game's [rules](super::rules) are not part of its state, so a restored game plays by the
+//! standard rules until others are attached with [`Game::with_rules`]. Nor is the number of pieces
+//! of food eaten, which a restored game counts from how far its snake has grown.
+
+use heapless::FnvIndexSet;
+use heapless::spsc::Queue;
+
+use super::rules::StandardRules;
+use super::{
+    BonusRound, Chase, CoordSet, Coords, Direction, Game, GameStatus, Phase, Prng, Snake, N_COLS,
+    N_ROWS, TAIL_CAPACITY
+};
+
+/// Current version of the snapshot format.
+const VERSION: u8 = 1;
+/// Number of bytes preceding the tail coordinates.
+const HEADER_LEN: usize = 14;
+/// Bit in the flags byte that is set if the snake wraps around the edges of the grid.
+const FLAG_WRAPAROUND: u8 = 1;
+/// Bit in the flags byte that is set if running into a wall turns the snake along it.
+const FLAG_BOUNCING_WALLS: u8 = 2;
+/// Bit in the flags byte that is set if a bonus round is in progress.
+const FLAG_BONUS_ROUND: u8 = 4;
+/// Number of bytes preceding the bonus food coordinates, when there is a bonus round.
+const BONUS_HEADER_LEN: usize = 5;
+/// Bit in the flags byte that is set if a chase is in progress.
+const FLAG_CHASE: u8 = 8;
+/// Number of bytes describing a chase, when there is one.
+const CHASE_LEN: usize = 2;
+/// Bit in the flags byte that is set if crashes stop the snake rather than ending the game.
+const FLAG_INVINCIBLE: u8 = 16;
+/// Every bit that may be set in the flags byte.
+const ALL_FLAGS: u8 =
+    FLAG_WRAPAROUND | FLAG_BOUNCING_WALLS | FLAG_BONUS_ROUND | FLAG_CHASE | FLAG_INVINCIBLE;
+/// Maximum number of tail segments a snapshot can hold (one less than the capacity of the tail
+/// queue).
+const MAX_TAIL_LEN: usize = TAIL_CAPACITY - 1;
+/// Maximum length of a serialized game, in bytes. The tail, walls, bonus food and hunter can't
+/// occupy more tiles than there are on the grid.
+pub const MAX_SNAPSHOT_LEN: usize =
+    HEADER_LEN + 1 + BONUS_HEADER_LEN + CHASE_LEN + N_ROWS * N_COLS;
+
+/// The ways in which serializing or deserializing a game can fail.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SnapshotError {
+    /// The buffer is too small to hold the serialized game (or, when deserializing, the data ends
+    /// before the game state is complete).
+    BufferTooSmall,
+    /// The data was written by an unsupported version of the format.
+    UnsupportedVersion(u8),
+    /// A field holds a value outside its permitted range.
+    InvalidValue,
+    /// The data decodes, but describes a game state that could not have arisen from play (eg, a
+    /// snake that overlaps itself or whose segments are not connected).
+    InvalidState,
+}
+
+impl Coords {
+    fn to_byte(self) -> u8 {
+        (self.row as u8) * (N_COLS as u8) + (self.col as u8)
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, SnapshotError> {
+        if (byte as usize) >= N_ROWS * N_COLS {
+            return Err(SnapshotError::InvalidValue);
+        }
+        Ok(Coords {
+            row: (byte / N_COLS as u8) as i8,
+            col: (byte % N_COLS as u8) as i8
+        })
+    }
+}
+
+impl Direction {
+    fn to_byte(self) -> u8 {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, SnapshotError> {
+        match byte {
+            0 => Ok(Direction::Up),
+            1 => Ok(Direction::Down),
+            2 => Ok(Direction::Left),
+            3 => Ok(Direction::Right),
+            _ => Err(SnapshotError::InvalidValue)
+        }
+    }
+}
+
+impl GameStatus {
+    fn to_byte(self) -> u8 {
+        match self {
+            GameStatus::Won => 0,
+            GameStatus::Lost => 1,
+            GameStatus::Ongoing => 2
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, SnapshotError> {
+        match byte {
+            0 => Ok(GameStatus::Won),
+            1 => Ok(GameStatus::Lost),
+            2 => Ok(GameStatus::Ongoing),
+            _ => Err(SnapshotError::InvalidValue)
+        }
+    }
+}
+
+impl<R> Game<R> {
+    /// Serialize the game state into `buf`, returning the number of bytes written.
+    pub fn serialize(&self, buf: &mut [u8]) -> Result<usize, SnapshotError> {
+        let tail_len = self.snake.tail.len();
+        let walls_start = HEADER_LEN + tail_len + 1;
+        let bonus_start = walls_start + self.walls.len() + BONUS_HEADER_LEN;
+        let chase_start = match &self.bonus {
+            Some(bonus) => bonus_start + bonus.food.len(),
+            None => walls_start + self.walls.len()
+        };
+        let len = chase_start + if self.chase.is_some() { CHASE_LEN } else { 0 };
+        if buf.len() < len {
+            return Err(SnapshotError::BufferTooSmall);
+        }
+        buf[0] = VERSION;
+        buf[1..5].copy_from_slice(&self.rng.value.to_le_bytes());
+        buf[5] = self.speed;
+        buf[6] = self.score;
+        buf[7] = self.status.to_byte();
+        buf[8] = self.snake.direction.to_byte();
+        buf[9] = self.food_coords.to_byte();
+        buf[10] = self.snake.head.to_byte();
+        buf[11] = (if self.wraparound { FLAG_WRAPAROUND } else { 0 })
+            | (if self.bouncing_walls { FLAG_BOUNCING_WALLS } else { 0 })
+            | (if self.bonus.is_some() { FLAG_BONUS_ROUND } else { 0 })
+            | (if self.chase.is_some() { FLAG_CHASE } else { 0 })
+            | (if self.invincible { FLAG_INVINCIBLE } else { 0 });
+        buf[12] = self.loops;
+        buf[13] = tail_len as u8;
+        for (i, t) in self.snake.tail.iter().enumerate() {
+            buf[HEADER_LEN + i] = t.to_byte();
+        }
+        buf[walls_start - 1] = self.walls.len() as u8;
+        for (i, w) in self.walls.iter().enumerate() {
+            buf[walls_start + i] = w.to_byte();
+        }
+        if let Some(bonus) = &self.bonus {
+            let header = &mut buf[bonus_start - BONUS_HEADER_LEN..bonus_start];
+            header[..4].copy_from_slice(&bonus.remaining_ms.to_le_bytes());
+            header[4] = bonus.food.len() as u8;
+            for (i, f) in bonus.food.iter().enumerate() {
+                buf[bonus_start + i] = f.to_byte();
+            }
+        }
+        if let Some(chase) = &self.chase {
+            buf[chase_start] = chase.hunter.to_byte();
+            buf[chase_start + 1] = chase.steps_left;
+        }
+        Ok(len)
+    }
+}
+
+impl Game {
+    /// Restore a game from data written by [`Game::serialize`]. The decoded state is checked
+    /// against the game's invariants before being returned, so corrupt or malicious data cannot
+    /// produce a game that would later panic.
+    pub fn deserialize(buf: &[u8]) -> Result<Self, SnapshotError> {
+        if buf.len() < HEADER_LEN {
+            return Err(SnapshotError::BufferTooSmall);
+        }
+        if buf[0] != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(buf[0]));
+        }
+        let mut rng_bytes = [0u8; 4];
+        rng_bytes.copy_from_slice(&buf[1..5]);
+        let rng_value = u32::from_le_bytes(rng_bytes);
+        let speed = buf[5];
+        let score = buf[6];
+        let status = GameStatus::from_byte(buf[7])?;
+        let direction = Direction::from_byte(buf[8])?;
+        let food_coords = Coords::from_byte(buf[9])?;
+        let head = Coords::from_byte(buf[10])?;
+        let flags = buf[11];
+        let loops = buf[12];
+        let tail_len = buf[13] as usize;
+        if rng_value == 0 || speed == 0 || tail_len == 0 || tail_len > MAX_TAIL_LEN
+            || flags & !ALL_FLAGS != 0 {
+            // A zero xorshift state would only ever produce zeroes
+            return Err(SnapshotError::InvalidValue);
+        }
+        let walls_start = HEADER_LEN + tail_len + 1;
+        if buf.len() < walls_start {
+            return Err(SnapshotError::BufferTooSmall);
+        }
+        let walls_len = buf[walls_start - 1] as usize;
+        if buf.len() < walls_start + walls_len {
+            return Err(SnapshotError::BufferTooSmall);
+        }
+
+        let mut tail = Queue::new();
+        let mut coord_set: CoordSet = FnvIndexSet::new();
+        let wraparound = flags & FLAG_WRAPAROUND != 0;
+        let mut prev: Option<Coords> = None;
+        for &byte in &buf[HEADER_LEN..HEADER_LEN + tail_len] {
+            let coords = Coords::from_byte(byte)?;
+            if prev.is_some_and(|p| !p.is_adjacent(&coords, wraparound)) {
+                return Err(SnapshotError::InvalidState);
+            }
+            // `insert` returns false if the snake already occupies these coordinates
+            if !coord_set.insert(coords).map_err(|_| SnapshotError::InvalidState)? {
+                return Err(SnapshotError::InvalidState);
+            }
+            tail.enqueue(coords).map_err(|_| SnapshotError::InvalidState)?;
+            prev = Some(coords);
+        }
+        if prev.is_some_and(|p| !p.is_adjacent(&head, wraparound))
+            || !coord_set.insert(head).map_err(|_| SnapshotError::InvalidState)? {
+            return Err(SnapshotError::InvalidState);
+        }
+        let mut walls: CoordSet = FnvIndexSet::new();
+        for &byte in &buf[walls_start..walls_start + walls_len] {
+            let coords = Coords::from_byte(byte)?;
+            if coord_set.contains(&coords)
+                || !walls.insert(coords).map_err(|_| SnapshotError::InvalidState)? {
+                return Err(SnapshotError::InvalidState);
+            }
+        }
+        if coord_set.contains(&food_coords) || walls.contains(&food_coords) {
+            return Err(SnapshotError::InvalidState);
+        }
+        let mut chase_start = walls_start + walls_len;
+        let bonus = if flags & FLAG_BONUS_ROUND != 0 {
+            let bonus_start = walls_start + walls_len + BONUS_HEADER_LEN;
+            if buf.len() < bonus_start {
+                return Err(SnapshotError::BufferTooSmall);
+            }
+            let header = &buf[bonus_start - BONUS_HEADER_LEN..bonus_start];
+            let mut remaining_bytes = [0u8; 4];
+            remaining_bytes.copy_from_slice(&header[..4]);
+            let food_len = header[4] as usize;
+            if buf.len() < bonus_start + food_len {
+                return Err(SnapshotError::BufferTooSmall);
+            }
+            let mut food: CoordSet = FnvIndexSet::new();
+            for &byte in &buf[bonus_start..bonus_start + food_len] {
+                let coords = Coords::from_byte(byte)?;
+                if coord_set.contains(&coords) || walls.contains(&coords) || coords == food_coords
+                    || !food.insert(coords).map_err(|_| SnapshotError::InvalidState)? {
+                    return Err(SnapshotError::InvalidState);
+                }
+            }
+            chase_start = bonus_start + food_len;
+            Some(BonusRound { remaining_ms: u32::from_le_bytes(remaining_bytes), food })
+        } else {
+            None
+        };
+        let chase = if flags & FLAG_CHASE != 0 {
+            if buf.len() < chase_start + CHASE_LEN {
+                return Err(SnapshotError::BufferTooSmall);
+            }
+            let hunter = Coords::from_byte(buf[chase_start])?;
+            let steps_left = buf[chase_start + 1];
+            if steps_left == 0 {
+                return Err(SnapshotError::InvalidValue);
+            }
+            if coord_set.contains(&hunter) || walls.contains(&hunter) || hunter == food_coords
+                || bonus.as_ref().is_some_and(|b| b.food.contains(&hunter)) {
+                return Err(SnapshotError::InvalidState);
+            }
+            Some(Chase { hunter, steps_left })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rng: Prng::new(rng_value),
+            snake: Snake { head, tail, coord_set, direction },
+            food_coords,
+            walls,
+            speed,
+            status,
+            score,
+            wraparound,
+            bouncing_walls: flags & FLAG_BOUNCING_WALLS != 0,
+            invincible: flags & FLAG_INVINCIBLE != 0,
+            bonus,
+            chase,
+            loops,
+            pieces_eaten: tail_len as u32 - 1,
+            phase: Phase::Idle,
+            crash: None,
+            rules: StandardRules
+        })
+    }
+}
@@ -0,0 +1,82 @@
+//! Interpretation of the player's inputs, independent of where they come from.
+
+use crate::game::{Direction, Turn};
+
+/// How the player's inputs are interpreted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ControlScheme {
+    /// Each input turns the snake left or right relative to the direction it is heading in, and
+    /// inputs are applied one per step.
+    Relative,
+    /// Inputs choose the direction the snake should head in (a left input rotates the choice
+    /// counter-clockwise and a right input clockwise), and only the latest choice is applied at the
+    /// next step.
+    Absolute
+}
+
+/// Work out the turn to apply at the next game step, interpreting inputs according to `scheme`.
+/// `next_input` should return the oldest input not yet applied (or `Turn::None` if there are none),
+/// and is called as many times as the scheme needs. `heading` is the snake's current direction.
+pub fn next_turn(
+    scheme: ControlScheme,
+    heading: Direction,
+    mut next_input: impl FnMut() -> Turn
+) -> Turn {
+    match scheme {
+        ControlScheme::Relative => next_input(),
+        ControlScheme::Absolute => {
+            // Rotate the chosen direction by every input since the last step, so that only the
+            // final choice is applied
+            let mut target = None;
+            loop {
+                match next_input() {
+                    Turn::None => break,
+                    turn => target = Some(turn.target(target.unwrap_or(heading)))
+                }
+            }
+            target.map_or(Turn::None, Turn::To)
+        }
+    }
+}
+
+/// Watches a stream of inputs for a particular sequence of them, such as a cheat code. The
+/// sequence may turn up anywhere in the stream, including straight after a false start that
+/// overlaps it (eg `L, L, R` is found in `L, L, L, R`).
+#[derive(Debug, Clone)]
+pub struct SequenceMatcher<'a, T> {
+    /// The sequence to look for, which should not be empty.
+    sequence: &'a [T],
+    /// Number of inputs at the start of the sequence that the latest inputs match.
+    matched: usize
+}
+
+impl<'a, T: PartialEq> SequenceMatcher<'a, T> {
+    pub const fn new(sequence: &'a [T]) -> Self {
+        Self { sequence, matched: 0 }
+    }
+
+    /// Take the next input, returning whether it completes the sequence. Completing the sequence
+    /// starts the search again from scratch.
+    pub fn feed(&mut self, input: T) -> bool {
+        // The latest inputs are the first `matched` of the sequence followed by `input`, so the
+        // longest run of them that could start the sequence can be found from the sequence alone
+        let seen = self.matched + 1;
+        self.matched = (1..=seen.min(self.sequence.len()))
+            .rev()
+            .find(|&len| {
+                self.sequence[..len - 1] == self.sequence[seen - len..self.matched]
+                    && self.sequence[len - 1] == input
+            })
+            .unwrap_or(0);
+        if self.matched == self.sequence.len() {
+            self.matched = 0;
+            return true;
+        }
+        false
+    }
+
+    /// Forget any partial match.
+    pub fn reset(&mut self) {
+        self.matched = 0;
+    }
+}
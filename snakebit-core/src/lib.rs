@@ -0,0 +1,32 @@
+//! Core game logic for snakebit, independent of the micro:bit (or any other hardware).
+//!
+//! The firmware feeds the player's inputs into a [`game::Game`], steps it at the rate it asks for
+//! and displays the brightness matrices it produces. Any other front end (or a variant of the
+//! game with its own rules) can drive it the same way:
+//!
+//! ```
+//! use snakebit_core::game::{Game, GameError, GameStatus, Turn};
+//!
+//! let mut game = Game::new(12345)?;
+//! game.set_wraparound(false);
+//! // Head straight until the snake crashes into the edge of the grid
+//! while game.status == GameStatus::Ongoing {
+//!     game.step(Turn::None)?;
+//!     // A real front end would wait `game.step_len_ms()` here, then display
+//!     // `game.game_matrix(9, 5, 7, 3)`
+//! }
+//! assert_eq!(game.status, GameStatus::Lost);
+//! # Ok::<(), GameError>(())
+//! ```
+//!
+//! Variants with different scoring or outcomes implement [`game::rules::Rules`] and attach it with
+//! [`game::Game::with_rules`]; `examples/custom_rules.rs` in the firmware crate shows a complete
+//! variant built this way.
+
+#![no_std]
+
+pub mod game;
+pub mod input;
+pub mod replay;
+pub mod seed;
+pub mod share;
@@ -0,0 +1,137 @@
+//! Recordings of games, so that a game seen on the device can be played through again step by
+//! step, eg to reproduce a bug. A recording holds the game's state when it started (which includes
+//! its generator's seed) and the turn applied at each step since. As every step goes through the
+//! same stages in the same order (see [`Game::step`]), playing the same turns from the same state
+//! always ends up in the same place.
+//!
+//! Anything that changes a game other than through its steps, such as moving the food for testing
+//! or turning invincibility on part way through, isn't recorded, so a game changed that way won't
+//! play back the same.
+
+use core::fmt;
+use heapless::Vec;
+
+use crate::game::rules::Rules;
+use crate::game::snapshot::{SnapshotError, MAX_SNAPSHOT_LEN};
+use crate::game::{Direction, Game, GameError, Turn};
+
+/// A turn applied to a game, at the step it was applied at (counting from 0).
+#[derive(Debug, Copy, Clone)]
+pub struct RecordedTurn {
+    pub step: u32,
+    pub turn: Turn
+}
+
+/// Why a recording couldn't be made or played back.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReplayError {
+    /// The game's starting state couldn't be saved or restored.
+    Snapshot(SnapshotError),
+    /// The game failed part way through being played back.
+    Game(GameError)
+}
+
+/// A recording of a game, holding up to `N` turns. Steps without a turn take up no room, so a game
+/// can go on for many more than `N` steps before the recording fills up. Once it is full, later
+/// steps aren't recorded at all, so that what has been recorded still plays back faithfully.
+pub struct Recording<const N: usize> {
+    /// The game's state when recording started.
+    start: [u8; MAX_SNAPSHOT_LEN],
+    start_len: usize,
+    /// Every turn other than [`Turn::None`], in the order they were applied.
+    turns: Vec<RecordedTurn, N>,
+    /// Number of steps recorded.
+    steps: u32,
+    /// Whether a turn has been left out for lack of room.
+    full: bool
+}
+
+impl<const N: usize> Recording<N> {
+    /// Start recording `game` from its current state, which should be before its first step.
+    pub fn new<R>(game: &Game<R>) -> Result<Self, ReplayError> {
+        let mut start = [0u8; MAX_SNAPSHOT_LEN];
+        let start_len = game.serialize(&mut start).map_err(ReplayError::Snapshot)?;
+        Ok(Self { start, start_len, turns: Vec::new(), steps: 0, full: false })
+    }
+
+    /// Record `turn` as applied at the next step.
+    pub fn record(&mut self, turn: Turn) {
+        if self.full {
+            return;
+        }
+        if !matches!(turn, Turn::None)
+            && self.turns.push(RecordedTurn { step: self.steps, turn }).is_err() {
+            self.full = true;
+            return;
+        }
+        self.steps += 1;
+    }
+
+    /// The game's state when recording started, as written by [`Game::serialize`].
+    pub fn start(&self) -> &[u8] {
+        &self.start[..self.start_len]
+    }
+
+    /// The turns recorded, other than steps without a turn.
+    pub fn turns(&self) -> &[RecordedTurn] {
+        &self.turns
+    }
+
+    /// Number of steps recorded.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Whether the recording has filled up, so doesn't go on to the end of the game.
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// The turn applied at `step`.
+    pub fn turn_at(&self, step: u32) -> Turn {
+        match self.turns.binary_search_by_key(&step, |recorded| recorded.step) {
+            Ok(i) => self.turns[i].turn,
+            Err(_) => Turn::None
+        }
+    }
+
+    /// Play the recording through from the start, with `rules` (which aren't part of the recorded
+    /// state), returning the game as it was after the last step recorded.
+    pub fn play<S: Rules>(&self, rules: S) -> Result<Game<S>, ReplayError> {
+        let mut game = Game::deserialize(self.start())
+            .map_err(ReplayError::Snapshot)?
+            .with_rules(rules);
+        for step in 0..self.steps {
+            game.step(self.turn_at(step)).map_err(ReplayError::Game)?;
+        }
+        Ok(game)
+    }
+
+    /// The recorded steps as text, one character per step: `L` and `R` for relative turns, `u`,
+    /// `d`, `l` and `r` for absolute turns and `.` for no turn. This is the form the golden replay
+    /// tests record their inputs in.
+    pub fn inputs(&self) -> Inputs<'_, N> {
+        Inputs(self)
+    }
+}
+
+/// The steps of a [`Recording`] as text, for display. See [`Recording::inputs`].
+pub struct Inputs<'a, const N: usize>(&'a Recording<N>);
+
+impl<const N: usize> fmt::Display for Inputs<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in 0..self.0.steps {
+            let c = match self.0.turn_at(step) {
+                Turn::Left => 'L',
+                Turn::Right => 'R',
+                Turn::None => '.',
+                Turn::To(Direction::Up) => 'u',
+                Turn::To(Direction::Down) => 'd',
+                Turn::To(Direction::Left) => 'l',
+                Turn::To(Direction::Right) => 'r'
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+//! Seeds for new games, conditioned so that a poor entropy source still gives varied games.
+//!
+//! A [`SeedSource`] produces the seeds that new games are created with. [`MixedSeeds`] wraps
+//! another source and stirs extra readings (eg, sensor noise or timer values) into every seed it
+//! hands out, so games differ from one another even if the wrapped source is stuck or predictable.
+
+/// Anything that can produce seeds for new games.
+pub trait SeedSource {
+    /// Return the next seed. Seeds are passed straight to [`Game::new`](crate::game::Game::new)
+    /// and friends, so should vary from call to call.
+    fn next_u32(&mut self) -> u32;
+}
+
+/// A [`SeedSource`] that mixes every value from `S` into a pool along with any other entropy it is
+/// given, and hands out seeds drawn from the pool.
+#[derive(Debug, Clone)]
+pub struct MixedSeeds<S> {
+    source: S,
+    pool: u32,
+    /// Number of seeds handed out so far, mixed in with each one so that the pool can't get stuck.
+    count: u32
+}
+
+impl<S: SeedSource> MixedSeeds<S> {
+    /// Wrap `source`, starting with an empty pool.
+    pub fn new(source: S) -> Self {
+        Self { source, pool: 0, count: 0 }
+    }
+
+    /// Stir `entropy` into the pool. Every bit of `entropy` affects every bit of the pool, so
+    /// readings that only vary in their lowest bits are still worth mixing in.
+    pub fn mix(&mut self, entropy: u32) {
+        self.pool = fmix32(self.pool ^ entropy.wrapping_mul(0x9e37_79b9));
+    }
+}
+
+impl<S: SeedSource> SeedSource for MixedSeeds<S> {
+    /// Mix in the next value from the wrapped source and return a seed drawn from the pool. The
+    /// pool keeps changing even if the wrapped source always returns the same value, and a seed is
+    /// never zero, which would leave a game's generator stuck.
+    fn next_u32(&mut self) -> u32 {
+        let value = self.source.next_u32();
+        self.count = self.count.wrapping_add(1);
+        self.mix(value ^ self.count);
+        match fmix32(self.pool) {
+            0 => 1,
+            seed => seed
+        }
+    }
+}
+
+/// The finalizer from MurmurHash3: a bijection on `u32` in which each input bit affects every
+/// output bit.
+pub(crate) fn fmix32(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
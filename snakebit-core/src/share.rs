@@ -0,0 +1,79 @@
+//! Short codes for sharing a score between boards with no link between them: one board shows the
+//! code for a finished game, and the player types it in on another board, which checks it before
+//! recording the score.
+//!
+//! A code packs the score, the mode it was achieved in and part of the address of the board it was
+//! achieved on into [`CODE_LEN`] characters of Crockford's base 32, along with a check value that
+//! catches mistyped codes. The check value isn't secret, so it keeps out typos rather than
+//! determined cheats.
+
+use crate::seed::fmix32;
+
+/// Number of characters in a share code.
+pub const CODE_LEN: usize = 8;
+/// The characters a code is written with: digits and capital letters, leaving out I, L, O and U.
+pub const CODE_CHARS: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// Bits in the check value.
+const CHECK_BITS: u32 = 12;
+
+/// What a share code records about a game.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ShareCode {
+    pub score: u8,
+    /// The mode the game was played in, numbered however the front end likes, from 0 to 15.
+    pub mode: u8,
+    /// The lowest 16 bits of the address of the board the game was played on.
+    pub device: u16
+}
+
+impl ShareCode {
+    /// Write the code out. Only the lowest four bits of [`ShareCode::mode`] are kept.
+    pub fn encode(&self) -> [u8; CODE_LEN] {
+        let payload = self.payload();
+        let mut value = ((payload as u64) << CHECK_BITS) | check(payload) as u64;
+        let mut code = [0u8; CODE_LEN];
+        for c in code.iter_mut().rev() {
+            *c = CODE_CHARS[(value & 31) as usize];
+            value >>= 5;
+        }
+        code
+    }
+
+    /// Read a code typed in by the player, or return `None` if it isn't a valid code. Lower case
+    /// letters are accepted, as are I and L for 1 and O for 0, as they are easily confused.
+    pub fn decode(code: &[u8]) -> Option<Self> {
+        if code.len() != CODE_LEN {
+            return None;
+        }
+        let mut value = 0u64;
+        for &c in code {
+            let c = match c.to_ascii_uppercase() {
+                b'I' | b'L' => b'1',
+                b'O' => b'0',
+                c => c
+            };
+            let digit = CODE_CHARS.iter().position(|&a| a == c)?;
+            value = (value << 5) | digit as u64;
+        }
+        let payload = (value >> CHECK_BITS) as u32;
+        if check(payload) as u64 != value & ((1 << CHECK_BITS) - 1) {
+            return None;
+        }
+        Some(Self {
+            score: payload as u8,
+            mode: ((payload >> 8) & 0xf) as u8,
+            device: (payload >> 12) as u16
+        })
+    }
+
+    /// The code's contents, packed into the lowest 28 bits.
+    fn payload(&self) -> u32 {
+        self.score as u32 | ((self.mode as u32 & 0xf) << 8) | ((self.device as u32) << 12)
+    }
+}
+
+/// The check value for `payload`. Every bit of the payload affects every bit of the check value,
+/// so any single mistyped character is caught unless it happens to match by chance.
+fn check(payload: u32) -> u32 {
+    fmix32(payload ^ 0x5a17_c0de) & ((1 << CHECK_BITS) - 1)
+}
@@ -0,0 +1,41 @@
+//! Checks that sequences are found in a stream of inputs wherever they turn up.
+
+use snakebit_core::input::SequenceMatcher;
+
+/// Feed `inputs` to a matcher for `sequence`, returning the positions at which it was completed.
+fn matches(sequence: &str, inputs: &str) -> Vec<usize> {
+    let sequence: Vec<char> = sequence.chars().collect();
+    let mut matcher = SequenceMatcher::new(&sequence);
+    inputs.chars().enumerate().filter(|&(_, input)| matcher.feed(input)).map(|(i, _)| i).collect()
+}
+
+#[test]
+fn sequence_found_after_unrelated_inputs() {
+    assert_eq!(matches("LLRRB", "RBLLRRB"), vec![6]);
+    assert_eq!(matches("LLRRB", "LLRLLRRB"), vec![7]);
+}
+
+#[test]
+fn sequence_found_after_overlapping_false_start() {
+    assert_eq!(matches("LLR", "LLLR"), vec![3]);
+    assert_eq!(matches("LLR", "LLLLLR"), vec![5]);
+    assert_eq!(matches("LRLRB", "LRLRLRB"), vec![6]);
+    assert_eq!(matches("RRLLRL", "RRLLRRLLRL"), vec![9]);
+}
+
+#[test]
+fn completing_sequence_starts_again() {
+    assert_eq!(matches("LL", "LLLLL"), vec![1, 3]);
+    assert_eq!(matches("LRL", "LRLRL"), vec![2]);
+}
+
+#[test]
+fn reset_forgets_partial_match() {
+    let sequence = ['L', 'R'];
+    let mut matcher = SequenceMatcher::new(&sequence);
+    assert!(!matcher.feed('L'));
+    matcher.reset();
+    assert!(!matcher.feed('R'));
+    assert!(!matcher.feed('L'));
+    assert!(matcher.feed('R'));
+}
@@ -0,0 +1,501 @@
+//! Property checks on `Game`, run over many pseudo-random seeds and input sequences.
+
+use snakebit_core::game::{
+    blend_matrix, Direction, Game, GameStatus, Turn, N_COLS, N_ROWS, START_ROW
+};
+use snakebit_core::game::rules::{AdversarialRules, FullGrid, Rules, TruncatingRules};
+
+/// Number of games to play for each property.
+const CASES: u32 = 2000;
+/// Maximum number of steps to play in each game.
+const MAX_STEPS: usize = 1000;
+
+/// Generates pseudo-random inputs, independently of the game's own PRNG.
+struct Inputs {
+    state: u32
+}
+
+impl Inputs {
+    fn new(seed: u32) -> Self {
+        Self { state: seed.wrapping_mul(2654435761) | 1 }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    /// A random input, with no turn being the most likely.
+    fn next_turn(&mut self) -> Turn {
+        match self.next_u32() % 10 {
+            0 => Turn::Left,
+            1 => Turn::Right,
+            2 => Turn::To(Direction::Up),
+            3 => Turn::To(Direction::Down),
+            4 => Turn::To(Direction::Left),
+            5 => Turn::To(Direction::Right),
+            _ => Turn::None
+        }
+    }
+}
+
+/// Play a game for every case, with and without walls, wraparound and bouncing walls, calling
+/// `check` after every step.
+fn for_each_step(mut check: impl FnMut(&Game, u32)) {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = if case % 2 == 0 {
+            Game::new(case)
+        } else {
+            Game::with_generated_walls(case)
+        }
+        .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        if case % 3 == 0 {
+            game.set_wraparound(false);
+        }
+        if case % 5 == 0 {
+            game.set_bouncing_walls(true);
+        }
+        check(&game, case);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            game.step(inputs.next_turn())
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            check(&game, case);
+        }
+    }
+}
+
+#[test]
+fn invariants_hold_for_any_inputs() {
+    for_each_step(|game, case| {
+        if let Err(e) = game.check_invariants() {
+            panic!("case {}: {}", case, e);
+        }
+    });
+}
+
+#[test]
+fn snake_grows_by_one_per_point() {
+    for_each_step(|game, case| {
+        assert_eq!(game.snake_len(), 2 + game.score() as usize, "case {}", case);
+    });
+}
+
+#[test]
+fn segments_run_from_head_to_tail_end() {
+    for_each_step(|game, case| {
+        let segments: Vec<_> = game.segments().collect();
+        let mut tiles: Vec<_> = game.snake_tiles().collect();
+        tiles.reverse();
+        assert_eq!(segments, tiles, "case {}", case);
+        assert_eq!(segments.len(), game.snake_len(), "case {}", case);
+        assert_eq!(segments[0], game.head(), "case {}", case);
+        // Each segment is next to the one before it, around the edges of the grid if need be
+        for pair in segments.windows(2) {
+            let d_row = (pair[0].0 + N_ROWS - pair[1].0) % N_ROWS;
+            let d_col = (pair[0].1 + N_COLS - pair[1].1) % N_COLS;
+            let distance = d_row.min(N_ROWS - d_row) + d_col.min(N_COLS - d_col);
+            assert_eq!(distance, 1, "case {}", case);
+        }
+    });
+}
+
+#[test]
+fn games_end_or_continue_without_panicking() {
+    let mut finished = 0;
+    for_each_step(|game, _| {
+        if game.status != GameStatus::Ongoing {
+            finished += 1;
+        }
+    });
+    // Random inputs should quickly run the snake into itself (or a wall) in most games
+    assert!(finished > CASES / 2);
+}
+
+#[test]
+fn danger_ahead_means_carrying_on_would_lose() {
+    for_each_step(|game, case| {
+        if game.status != GameStatus::Ongoing {
+            return;
+        }
+        let mut buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+        let len = game.serialize(&mut buf).unwrap();
+        let mut next = Game::deserialize(&buf[..len]).unwrap();
+        next.step(Turn::None).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        let lost = next.status == GameStatus::Lost;
+        if game.danger_ahead() {
+            assert!(lost, "case {}: no crash despite danger ahead", case);
+        } else if game.hunter().is_none() {
+            assert!(!lost, "case {}: crashed with no danger ahead", case);
+        }
+    });
+}
+
+#[test]
+fn random_starts_are_clear_of_walls() {
+    for case in 1..=CASES {
+        let mut game = Game::with_generated_walls(case).unwrap();
+        if case % 2 == 0 {
+            game.set_wraparound(false);
+        }
+        game.randomise_start().unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        if let Err(e) = game.check_invariants() {
+            panic!("case {}: {}", case, e);
+        }
+        // Nothing is next to the head, so the snake can't crash on its first step
+        assert!(!game.danger_ahead(), "case {}", case);
+        game.step(Turn::None).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        assert_eq!(game.status, GameStatus::Ongoing, "case {}", case);
+    }
+}
+
+#[test]
+fn snapshots_round_trip() {
+    for_each_step(|game, case| {
+        let mut buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+        let len = game.serialize(&mut buf).unwrap();
+        let restored = Game::deserialize(&buf[..len])
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        let mut restored_buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+        let restored_len = restored.serialize(&mut restored_buf).unwrap();
+        assert_eq!(&buf[..len], &restored_buf[..restored_len], "case {}", case);
+    });
+}
+
+#[test]
+fn truncating_snakes_never_lose_without_walls() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::new(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e))
+            .with_rules(TruncatingRules);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            game.step(inputs.next_turn())
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            if let Err(e) = game.check_invariants() {
+                panic!("case {}: {}", case, e);
+            }
+            // Points are lost along with segments, so the score still matches the length
+            assert_eq!(game.snake_len(), 2 + game.score() as usize, "case {}", case);
+        }
+        assert_ne!(game.status, GameStatus::Lost, "case {}", case);
+    }
+}
+
+/// Find the tile shown with `brightness` in a game matrix.
+fn find_tile(matrix: &[[u8; N_COLS]; N_ROWS], brightness: u8) -> Option<(usize, usize)> {
+    (0..N_ROWS).flat_map(|row| (0..N_COLS).map(move |col| (row, col)))
+        .find(|&(row, col)| matrix[row][col] == brightness)
+}
+
+#[test]
+fn adversarial_food_is_never_next_to_the_head() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::new(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e))
+            .with_rules(AdversarialRules);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            let score = game.score();
+            game.step(inputs.next_turn())
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            if game.score() == score || game.status != GameStatus::Ongoing {
+                continue;
+            }
+            // Food that has just been placed should be at least two steps away (around the edges
+            // of the grid, if need be) while there is plenty of room
+            let matrix = game.game_matrix(3, 1, 2, 0);
+            let (head, food) = (find_tile(&matrix, 3).unwrap(), find_tile(&matrix, 2).unwrap());
+            let d_row = (head.0 + N_ROWS - food.0) % N_ROWS;
+            let d_col = (head.1 + N_COLS - food.1) % N_COLS;
+            let distance = d_row.min(N_ROWS - d_row) + d_col.min(N_COLS - d_col);
+            if game.snake_len() < N_ROWS * N_COLS / 2 {
+                assert!(distance >= 2, "case {}", case);
+            }
+        }
+    }
+}
+
+/// Rules with a bonus round after every piece of food, to play as many as possible.
+struct FrequentBonusRounds;
+
+impl Rules for FrequentBonusRounds {
+    fn speed(&self, food_eaten: u8) -> u8 {
+        1 + food_eaten
+    }
+
+    fn bonus_round_ms(&self) -> u32 {
+        10_000
+    }
+
+    // The hunter can catch the snake during a bonus round
+    fn chase_steps(&self) -> u8 {
+        0
+    }
+}
+
+#[test]
+fn bonus_rounds_are_consistent_and_forgiving() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::new(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e))
+            .with_rules(FrequentBonusRounds);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            let was_bonus_round = game.in_bonus_round();
+            game.step(inputs.next_turn())
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            if let Err(e) = game.check_invariants() {
+                panic!("case {}: {}", case, e);
+            }
+            // Without walls, the only way to lose is by running into itself
+            assert!(!(was_bonus_round && game.status == GameStatus::Lost), "case {}", case);
+
+            let mut buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+            let len = game.serialize(&mut buf).unwrap();
+            let restored = Game::deserialize(&buf[..len])
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            assert_eq!(restored.in_bonus_round(), game.in_bonus_round(), "case {}", case);
+        }
+    }
+}
+
+/// Rules with a chase every fifth piece of food.
+struct FrequentChases;
+
+impl Rules for FrequentChases {
+    fn level(&self, food_eaten: u8) -> u8 {
+        1 + food_eaten
+    }
+}
+
+#[test]
+fn hunters_move_one_tile_at_a_time_and_give_up() {
+    let steps = FrequentChases.chase_steps() as usize;
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::new(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e))
+            .with_rules(FrequentChases);
+        let mut chase_len = 0;
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            let hunter = game.hunter();
+            game.step(inputs.next_turn())
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            if let Err(e) = game.check_invariants() {
+                panic!("case {}: {}", case, e);
+            }
+            if let (Some((row, col)), Some((next_row, next_col))) = (hunter, game.hunter()) {
+                // With wraparound, a hunter's single step can only look like a long jump by
+                // going off one edge and on at the other
+                let d_row = row.abs_diff(next_row) % (N_ROWS - 1);
+                let d_col = col.abs_diff(next_col) % (N_COLS - 1);
+                assert!(d_row + d_col <= 1, "case {}: hunter jumped", case);
+            }
+            // A chase isn't counted down on the step that ends the game, so stays where it was
+            let chasing = game.hunter().is_some() && game.status == GameStatus::Ongoing;
+            chase_len = if chasing { chase_len + 1 } else { 0 };
+            assert!(chase_len <= steps, "case {}: chase went on too long", case);
+
+            let mut buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+            let len = game.serialize(&mut buf).unwrap();
+            let restored = Game::deserialize(&buf[..len])
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            assert_eq!(restored.hunter(), game.hunter(), "case {}", case);
+        }
+    }
+}
+
+/// Endless rules without chases, whose hunter could get in the way of the snake's path.
+struct SteadyMarathon;
+
+impl Rules for SteadyMarathon {
+    fn full_grid(&self) -> FullGrid {
+        FullGrid::Loop
+    }
+
+    fn chase_steps(&self) -> u8 {
+        0
+    }
+}
+
+/// The direction that keeps a snake on a cycle through every tile of the (wrapping) grid: along
+/// each row from left to right, dropping down a row one column before where it came in.
+fn grid_cycle_direction(row: usize, col: usize) -> Direction {
+    // The snake starts heading right across the start row, entering it at column 1
+    let entry = (1 + N_COLS + START_ROW - row) % N_COLS;
+    if col == (entry + N_COLS - 1) % N_COLS {
+        Direction::Down
+    } else {
+        Direction::Right
+    }
+}
+
+#[test]
+fn marathon_snakes_loop_instead_of_winning() {
+    for case in 1..=CASES / 20 {
+        let mut game = Game::new(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e))
+            .with_rules(SteadyMarathon);
+        let mut eaten = 0;
+        while game.loops() < 3 {
+            let len = game.snake_len();
+            let (row, col) = game.head();
+            game.step(Turn::To(grid_cycle_direction(row, col)))
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            if let Err(e) = game.check_invariants() {
+                panic!("case {}: {}", case, e);
+            }
+            assert_eq!(game.status, GameStatus::Ongoing, "case {}", case);
+            if game.snake_len() != len {
+                eaten += 1;
+            }
+            assert_eq!(game.score(), eaten, "case {}", case);
+            assert_eq!(game.pieces_eaten(), eaten as u32, "case {}", case);
+        }
+        assert_eq!(game.snake_len(), 2, "case {}", case);
+
+        let mut buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+        let len = game.serialize(&mut buf).unwrap();
+        let restored = Game::deserialize(&buf[..len])
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        assert_eq!(restored.loops(), 3, "case {}", case);
+    }
+}
+
+#[test]
+fn invincible_snakes_never_lose() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::with_generated_walls(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e))
+            .with_rules(FrequentChases);
+        game.set_wraparound(case % 2 == 0);
+        game.set_invincible(true);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            game.step(inputs.next_turn())
+                .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            if let Err(e) = game.check_invariants() {
+                panic!("case {}: {}", case, e);
+            }
+            assert!(!game.danger_ahead(), "case {}", case);
+        }
+        assert_ne!(game.status, GameStatus::Lost, "case {}", case);
+
+        let mut buf = [0u8; snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN];
+        let len = game.serialize(&mut buf).unwrap();
+        let restored = Game::deserialize(&buf[..len])
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        assert!(restored.is_invincible(), "case {}", case);
+    }
+}
+
+#[test]
+fn food_moved_ahead_is_eaten() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::with_generated_walls(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        game.set_wraparound(case % 2 == 0);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            let (row, col) = game.head();
+            assert!(!game.move_food(row, col).unwrap(), "case {}", case);
+            assert!(!game.move_food(N_ROWS, col).unwrap(), "case {}", case);
+            let moved = match game.tile_ahead() {
+                Some((row, col)) => game.move_food(row, col)
+                    .unwrap_or_else(|e| panic!("case {}: {:?}", case, e)),
+                None => false
+            };
+            if let Err(e) = game.check_invariants() {
+                panic!("case {}: {}", case, e);
+            }
+            let score = game.score();
+            if moved {
+                game.step(Turn::None).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+                assert!(
+                    game.score() > score || game.status == GameStatus::Won,
+                    "case {}",
+                    case
+                );
+            } else {
+                game.step(inputs.next_turn()).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            }
+        }
+    }
+}
+
+#[test]
+fn steps_blend_between_their_matrices() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::new(case).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            let from = game.game_matrix(9, 4, 6, 2);
+            game.step(inputs.next_turn()).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+            let to = game.game_matrix(9, 4, 6, 2);
+            let total = inputs.next_u32() % 500 + 1;
+            assert_eq!(blend_matrix(&from, &to, 0, total), from, "case {}", case);
+            assert_eq!(blend_matrix(&from, &to, total, total), to, "case {}", case);
+            let blended = blend_matrix(&from, &to, inputs.next_u32() % total, total);
+            for ((row, from_row), to_row) in blended.iter().zip(from.iter()).zip(to.iter()) {
+                for ((&value, &from_value), &to_value) in
+                    row.iter().zip(from_row.iter()).zip(to_row.iter())
+                {
+                    let (low, high) = (from_value.min(to_value), from_value.max(to_value));
+                    assert!(low <= value && value <= high, "case {}", case);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn lost_games_know_where_the_snake_crashed() {
+    for case in 1..=CASES {
+        let mut inputs = Inputs::new(case);
+        let mut game = Game::with_generated_walls(case)
+            .unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        game.set_wraparound(case % 2 == 0);
+        for _ in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            assert_eq!(game.crash_site(), None, "case {}", case);
+            game.step(inputs.next_turn()).unwrap_or_else(|e| panic!("case {}: {:?}", case, e));
+        }
+        if game.status != GameStatus::Lost {
+            continue;
+        }
+        match game.crash_site() {
+            // Only running off the edge crashes off the grid
+            None => assert_eq!(game.tile_ahead(), None, "case {}", case),
+            site => assert!(site == game.tile_ahead() || site == game.hunter(), "case {}", case)
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Golden replay tests: recorded games (a seed and a sequence of inputs) are replayed through
+//! `Game`, and a checksum of the final state is compared against the value recorded alongside
+//! them, so that changes to movement, food placement or scoring can't silently change how games
+//! play out.
+//!
+//! Each line of `fixtures/replays.txt` is a recording, in the form
+//! `<name> <seed> <plain|walls> <inputs> <checksum>`. Inputs are one character per step: `L` and `R`
+//! for relative turns, `u`, `d`, `l` and `r` for absolute turns and `.` for no turn. The checksum is
+//! a 32-bit FNV-1a hash of the final game's snapshot, in hex. If a change to the game is meant to
+//! change how it plays (or changes the snapshot format), update the checksums to the values reported
+//! by the failing test.
+
+use snakebit_core::game::rules::StandardRules;
+use snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN;
+use snakebit_core::game::{Direction, Game, GameStatus, Turn};
+use snakebit_core::replay::Recording;
+
+const REPLAYS: &str = include_str!("fixtures/replays.txt");
+
+struct Replay<'a> {
+    name: &'a str,
+    seed: u32,
+    walls: bool,
+    inputs: &'a str,
+    checksum: u32
+}
+
+impl<'a> Replay<'a> {
+    fn parse(line: &'a str) -> Self {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(fields.len(), 5, "malformed replay: {}", line);
+        Self {
+            name: fields[0],
+            seed: fields[1].parse().expect("invalid seed"),
+            walls: match fields[2] {
+                "plain" => false,
+                "walls" => true,
+                other => panic!("{}: unknown layout {}", fields[0], other)
+            },
+            inputs: fields[3],
+            checksum: u32::from_str_radix(fields[4], 16).expect("invalid checksum")
+        }
+    }
+
+    /// The game before any inputs are played.
+    fn start(&self) -> Game {
+        if self.walls {
+            Game::with_generated_walls(self.seed)
+        } else {
+            Game::new(self.seed)
+        }
+        .unwrap_or_else(|e| panic!("{}: {:?}", self.name, e))
+    }
+
+    /// The recorded inputs, as turns.
+    fn turns(&self) -> impl Iterator<Item = Turn> + '_ {
+        self.inputs.chars().map(move |input| match input {
+            'L' => Turn::Left,
+            'R' => Turn::Right,
+            'u' => Turn::To(Direction::Up),
+            'd' => Turn::To(Direction::Down),
+            'l' => Turn::To(Direction::Left),
+            'r' => Turn::To(Direction::Right),
+            '.' => Turn::None,
+            other => panic!("{}: unknown input {:?}", self.name, other)
+        })
+    }
+
+    /// Play the recorded inputs, stopping early if the game ends, calling `each_turn` with each
+    /// turn before it is played.
+    fn play_with(&self, mut each_turn: impl FnMut(Turn)) -> Game {
+        let mut game = self.start();
+        for turn in self.turns() {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            each_turn(turn);
+            game.step(turn).unwrap_or_else(|e| panic!("{}: {:?}", self.name, e));
+        }
+        game
+    }
+
+    /// Play the recorded inputs, stopping early if the game ends.
+    fn play(&self) -> Game {
+        self.play_with(|_| ())
+    }
+}
+
+/// 32-bit FNV-1a hash of the game's snapshot.
+fn checksum(game: &Game) -> u32 {
+    let mut buf = [0u8; MAX_SNAPSHOT_LEN];
+    let len = game.serialize(&mut buf).unwrap();
+    buf[..len].iter().fold(0x811c9dc5, |hash, &b| (hash ^ b as u32).wrapping_mul(0x01000193))
+}
+
+fn replays() -> impl Iterator<Item = Replay<'static>> {
+    REPLAYS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(Replay::parse)
+}
+
+#[test]
+#[cfg_attr(
+    any(feature = "grid8x8", feature = "grid9x9"),
+    ignore = "recorded on the 5x5 grid, so they play out differently on a bigger one"
+)]
+fn replays_match_golden_checksums() {
+    let mut mismatches = Vec::new();
+    for replay in replays() {
+        let actual = checksum(&replay.play());
+        if actual != replay.checksum {
+            mismatches.push(format!(
+                "{}: expected {:08x}, got {:08x}",
+                replay.name, replay.checksum, actual
+            ));
+        }
+    }
+    assert!(mismatches.is_empty(), "replays changed:\n{}", mismatches.join("\n"));
+}
+
+#[test]
+fn replay_names_are_unique() {
+    let mut names: Vec<&str> = replays().map(|r| r.name).collect();
+    let count = names.len();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), count);
+}
+
+#[test]
+fn recordings_play_back_the_same() {
+    for replay in replays() {
+        let mut recording = Recording::<1024>::new(&replay.start()).unwrap();
+        let game = replay.play_with(|turn| recording.record(turn));
+        assert!(!recording.is_full(), "{}", replay.name);
+        assert_eq!(
+            recording.inputs().to_string(),
+            replay.inputs[..recording.steps() as usize],
+            "{}",
+            replay.name
+        );
+        let played = recording.play(StandardRules)
+            .unwrap_or_else(|e| panic!("{}: {:?}", replay.name, e));
+        assert_eq!(checksum(&played), checksum(&game), "{}", replay.name);
+    }
+}
+
+#[test]
+fn full_recordings_stop_before_the_first_turn_left_out() {
+    let game = Game::new(1).unwrap();
+    let mut recording = Recording::<2>::new(&game).unwrap();
+    for turn in [Turn::Left, Turn::None, Turn::Right, Turn::None, Turn::Left, Turn::None].iter() {
+        recording.record(*turn);
+    }
+    assert!(recording.is_full());
+    assert_eq!(recording.steps(), 4);
+    assert_eq!(recording.inputs().to_string(), "L.R.");
+}
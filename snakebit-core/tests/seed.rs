@@ -0,0 +1,40 @@
+//! Checks that mixed seeds vary even when the underlying entropy source doesn't.
+
+use snakebit_core::seed::{MixedSeeds, SeedSource};
+
+/// A broken source that always returns the same value.
+struct Stuck(u32);
+
+impl SeedSource for Stuck {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+#[test]
+fn stuck_sources_still_give_distinct_seeds() {
+    for value in [0, 1, u32::MAX] {
+        let mut seeds = MixedSeeds::new(Stuck(value));
+        let mut seen: Vec<u32> = (0..1000).map(|_| seeds.next_u32()).collect();
+        assert!(!seen.contains(&0), "zero seed from {}", value);
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), 1000, "repeated seed from {}", value);
+    }
+}
+
+#[test]
+fn mixed_entropy_changes_the_seeds() {
+    let first_seed = |entropy: u32| {
+        let mut seeds = MixedSeeds::new(Stuck(0));
+        seeds.mix(entropy);
+        seeds.next_u32()
+    };
+    // The same readings give the same seeds, so games can still be reproduced in tests
+    assert_eq!(first_seed(25), first_seed(25));
+    // Readings differing in a single bit (eg, adjacent temperatures) give unrelated seeds
+    for bit in 0..32 {
+        let (a, b) = (first_seed(0), first_seed(1 << bit));
+        assert!((a ^ b).count_ones() >= 8, "bit {}: {:08x} vs {:08x}", bit, a, b);
+    }
+}
@@ -0,0 +1,49 @@
+//! Checks that share codes survive being written out and typed back in, and that typos are caught.
+
+use snakebit_core::share::{ShareCode, CODE_CHARS, CODE_LEN};
+
+/// A spread of codes, covering the extremes of each field.
+fn sample_codes() -> impl Iterator<Item = ShareCode> {
+    (0..=255u8).step_by(17).flat_map(|score| {
+        [0u8, 3, 15].iter().flat_map(move |&mode| {
+            [0u16, 0x1234, u16::MAX].iter().map(move |&device| ShareCode { score, mode, device })
+        })
+    })
+}
+
+#[test]
+fn codes_round_trip() {
+    for code in sample_codes() {
+        let text = code.encode();
+        assert_eq!(ShareCode::decode(&text), Some(code), "{}", String::from_utf8_lossy(&text));
+        // Codes may be typed in lower case
+        let lower = text.to_ascii_lowercase();
+        assert_eq!(ShareCode::decode(&lower), Some(code));
+    }
+}
+
+#[test]
+fn mistyped_codes_are_rejected() {
+    let mut typos = 0;
+    let mut missed = 0;
+    for code in sample_codes() {
+        let text = code.encode();
+        for i in 0..CODE_LEN {
+            for replacement in CODE_CHARS {
+                if *replacement == text[i] {
+                    continue;
+                }
+                let mut typo = text;
+                typo[i] = *replacement;
+                typos += 1;
+                if ShareCode::decode(&typo).is_some() {
+                    missed += 1;
+                }
+            }
+        }
+        assert_eq!(ShareCode::decode(&text[..CODE_LEN - 1]), None);
+        assert_eq!(ShareCode::decode(b"UUUUUUUU"), None);
+    }
+    // The check value has 12 bits, so about one typo in 4096 slips through
+    assert!(missed * 1000 < typos, "{} of {} typos accepted", missed, typos);
+}
@@ -0,0 +1,53 @@
+//! Checks that crafted snapshots are only accepted if they describe a game that could be played.
+
+use snakebit_core::game::snapshot::{SnapshotError, MAX_SNAPSHOT_LEN};
+use snakebit_core::game::{Game, N_COLS};
+
+/// A snapshot of a three-segment snake whose body runs off the right edge of the grid and back on
+/// at the left, with the given flags byte.
+fn snake_across_edge(flags: u8) -> Vec<u8> {
+    let coords = |row: usize, col: usize| (row * N_COLS + col) as u8;
+    // Start from a real snapshot, to get the version and a valid PRNG state, speed and so on
+    let mut buf = [0u8; MAX_SNAPSHOT_LEN];
+    let len = Game::new(12345).unwrap().serialize(&mut buf).unwrap();
+    let mut snapshot = buf[..len].to_vec();
+    snapshot[9] = coords(0, 0);
+    snapshot[10] = coords(2, 0);
+    snapshot[11] = flags;
+    snapshot.truncate(13);
+    snapshot.extend_from_slice(&[2, coords(2, N_COLS - 2), coords(2, N_COLS - 1), 0]);
+    snapshot
+}
+
+#[test]
+fn snake_may_cross_edge_with_wraparound() {
+    let game = Game::deserialize(&snake_across_edge(1)).unwrap();
+    assert_eq!(game.head(), (2, 0));
+    assert_eq!(game.snake_len(), 3);
+}
+
+#[test]
+fn snake_may_not_cross_edge_without_wraparound() {
+    assert_eq!(
+        Game::deserialize(&snake_across_edge(0)).err(),
+        Some(SnapshotError::InvalidState)
+    );
+}
+
+#[test]
+fn settings_survive_restore() {
+    // A game handed off to another board is sent as a snapshot, and must carry on under the same
+    // settings there
+    for case in 0..8 {
+        let mut game = Game::new(12345).unwrap();
+        game.set_wraparound(case & 1 != 0);
+        game.set_bouncing_walls(case & 2 != 0);
+        game.set_invincible(case & 4 != 0);
+        let mut buf = [0u8; MAX_SNAPSHOT_LEN];
+        let len = game.serialize(&mut buf).unwrap();
+        let restored = Game::deserialize(&buf[..len]).unwrap();
+        assert_eq!(restored.wraps_around(), game.wraps_around(), "case {}", case);
+        assert_eq!(restored.has_bouncing_walls(), game.has_bouncing_walls(), "case {}", case);
+        assert_eq!(restored.is_invincible(), game.is_invincible(), "case {}", case);
+    }
+}
@@ -0,0 +1,65 @@
+//! What is shown by holding A at startup: which optional features this firmware was built with,
+//! and (with `persist`) how worn its flash is.
+
+use microbit::display::nonblocking::BitImage;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::TIMER0;
+#[cfg(feature = "persist")]
+use rtt_target::rprintln;
+#[cfg(feature = "persist")]
+use snakebit_core::game::count_matrix;
+
+use crate::display::flash_image;
+use crate::hardware::Capabilities;
+use crate::icons;
+#[cfg(feature = "persist")]
+use crate::storage::Storage;
+
+/// Number of times the nRF52833's flash is rated to be erased (its endurance, in the product
+/// specification).
+#[cfg(feature = "persist")]
+const RATED_ERASES: u32 = 10_000;
+
+/// Show an icon for each optional subsystem that was compiled into this firmware (and whose
+/// hardware is present, where that can be checked), one after the other, or a cross if there are
+/// none. The external displays share an icon, as they can't be told from each other (or checked
+/// for) at startup. `persist` has none here, as the flash wear shown next starts with the same
+/// chip icon, and nor does `controller`, as a controller build never gets this far. The debugging
+/// aids aren't shown either.
+pub(crate) fn show_features(timer: &mut Timer<TIMER0, Periodic>, capabilities: &Capabilities) {
+    let features = [
+        (cfg!(feature = "radio"), icons::RADIO),
+        (cfg!(feature = "audio") && capabilities.speaker, icons::SOUND),
+        (cfg!(feature = "fonts"), icons::LETTER),
+        (cfg!(feature = "tilt") && capabilities.accelerometer, icons::TILT),
+        // The magnetometer is on the same chip as the accelerometer
+        (cfg!(feature = "compass") && capabilities.accelerometer, icons::COMPASS),
+        (cfg!(feature = "joystick") && capabilities.joystick, icons::JOYSTICK),
+        (cfg!(feature = "pads"), icons::PADS),
+        (cfg!(feature = "max7219"), icons::SCREEN),
+        (cfg!(feature = "ssd1306"), icons::SCREEN),
+        (cfg!(feature = "neopixel"), icons::SCREEN),
+    ];
+    let mut any_enabled = false;
+    for (enabled, icon) in features.iter() {
+        if *enabled {
+            any_enabled = true;
+            flash_image(timer, &BitImage::new(icon), 1000);
+        }
+    }
+    if !any_enabled {
+        flash_image(timer, &BitImage::new(&icons::CROSS), 1000);
+    }
+}
+
+/// Show how worn the storage page of flash is: a chip icon followed by one lit LED for every 4% of
+/// its rated endurance used up, rounded up so that any wear shows. The exact number of erases is
+/// also printed over RTT.
+#[cfg(feature = "persist")]
+pub(crate) fn show_flash_wear(timer: &mut Timer<TIMER0, Periodic>, storage: &Storage) {
+    let erases = storage.erase_count();
+    rprintln!("Storage page erased {} times (rated for {})", erases, RATED_ERASES);
+    let leds = (erases as u64 * 25 + RATED_ERASES as u64 - 1) / RATED_ERASES as u64;
+    flash_image(timer, &BitImage::new(&icons::FLASH), 1000);
+    flash_image(timer, &BitImage::new(&count_matrix(leds as usize)), 1500);
+}
@@ -0,0 +1,142 @@
+//! What the player's gestures do during play. Each gesture (a press of a button, holding one down,
+//! pressing both together or tapping the logo) is mapped to an action, or to nothing, by an
+//! [`ActionMap`], which the player can change over serial (see [`crate::serial`]). The map only
+//! applies while the game is running: the pause screen and the menus opened from it always treat
+//! the buttons the same way.
+//!
+//! Anything else that presses a button on the player's behalf, such as steering over serial, is
+//! mapped along with the button.
+
+/// Something the player can make happen during play.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Action {
+    /// Turn the snake left (or, with absolute controls, rotate its heading counter-clockwise).
+    TurnLeft,
+    /// Turn the snake right (or rotate its heading clockwise).
+    TurnRight,
+    /// Pause the game, opening the pause screen (which is also the game's menu).
+    Pause,
+    /// Double the snake's speed for the rest of the game, or put it back to normal.
+    Boost
+}
+
+/// Something the player can do with the buttons or the logo.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Gesture {
+    /// A short press of A.
+    PressA,
+    /// A short press of B.
+    PressB,
+    /// Holding A down for a long press.
+    HoldA,
+    /// Holding B down for a long press.
+    HoldB,
+    /// Pressing both buttons together.
+    Chord,
+    /// Tapping the logo.
+    LogoTap
+}
+
+/// Every gesture, in the order their actions are stored.
+const GESTURES: [Gesture; 6] = [
+    Gesture::PressA,
+    Gesture::PressB,
+    Gesture::HoldA,
+    Gesture::HoldB,
+    Gesture::Chord,
+    Gesture::LogoTap
+];
+
+/// Length of an [`ActionMap`] when serialized.
+#[cfg(feature = "persist")]
+pub(crate) const ACTION_MAP_LEN: usize = 8;
+
+impl Gesture {
+    fn index(self) -> usize {
+        GESTURES.iter().position(|&gesture| gesture == self).unwrap_or(0)
+    }
+
+    /// Whether the gesture can turn the snake. Turns are queued to be applied one per step, which
+    /// only short presses are, so the other gestures can only do things that happen straight away.
+    fn can_turn(self) -> bool {
+        matches!(self, Gesture::PressA | Gesture::PressB)
+    }
+}
+
+/// The action each gesture makes during play, if any.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct ActionMap {
+    actions: [Option<Action>; GESTURES.len()]
+}
+
+impl ActionMap {
+    /// The usual controls: A and B turn, and holding A, pressing both buttons or tapping the logo
+    /// pauses the game.
+    pub(crate) const DEFAULT: Self = Self {
+        actions: [
+            Some(Action::TurnLeft),
+            Some(Action::TurnRight),
+            Some(Action::Pause),
+            None,
+            Some(Action::Pause),
+            Some(Action::Pause)
+        ]
+    };
+
+    /// The action `gesture` makes, if any.
+    pub(crate) fn action(&self, gesture: Gesture) -> Option<Action> {
+        self.actions[gesture.index()]
+    }
+
+    /// Have `gesture` make `action` (or nothing, if `None`). Returns false, changing nothing, if
+    /// the gesture can't make the action.
+    pub(crate) fn set(&mut self, gesture: Gesture, action: Option<Action>) -> bool {
+        let turn = matches!(action, Some(Action::TurnLeft | Action::TurnRight));
+        if turn && !gesture.can_turn() {
+            return false;
+        }
+        self.actions[gesture.index()] = action;
+        true
+    }
+
+    #[cfg(feature = "persist")]
+    pub(crate) fn to_bytes(&self) -> [u8; ACTION_MAP_LEN] {
+        let mut bytes = [0u8; ACTION_MAP_LEN];
+        for (byte, action) in bytes.iter_mut().zip(self.actions.iter()) {
+            *byte = match action {
+                None => 0,
+                Some(Action::TurnLeft) => 1,
+                Some(Action::TurnRight) => 2,
+                Some(Action::Pause) => 3,
+                Some(Action::Boost) => 4
+            };
+        }
+        bytes
+    }
+
+    /// Read a map written by [`ActionMap::to_bytes`], or return `None` if it isn't valid.
+    #[cfg(feature = "persist")]
+    pub(crate) fn from_bytes(bytes: &[u8; ACTION_MAP_LEN]) -> Option<Self> {
+        let mut map = Self { actions: [None; GESTURES.len()] };
+        for (&byte, &gesture) in bytes.iter().zip(GESTURES.iter()) {
+            let action = match byte {
+                0 => None,
+                1 => Some(Action::TurnLeft),
+                2 => Some(Action::TurnRight),
+                3 => Some(Action::Pause),
+                4 => Some(Action::Boost),
+                _ => return None
+            };
+            if !map.set(gesture, action) {
+                return None;
+            }
+        }
+        Some(map)
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
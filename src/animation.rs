@@ -0,0 +1,165 @@
+//! Short animations, made of frames played on the display one after another.
+//!
+//! An animation is any iterator of [`Frame`]s, so effects can be built from iterator adapters and
+//! chained together, and are only worked out as they are played.
+
+use microbit::display::nonblocking::GreyscaleImage;
+use snakebit_core::game::blend_matrix;
+
+use crate::display::{display_image, LED_COLS, LED_ROWS};
+use crate::icons;
+
+/// Time each frame of the fireworks is shown for, in milliseconds.
+const FIREWORK_FRAME_MS: u32 = 70;
+/// Where each burst of the fireworks starts, as `(row, col)` pairs, in order.
+const BURSTS: [(usize, usize); 4] = [(2, 2), (1, 3), (3, 1), (2, 2)];
+/// Number of frames in each burst: long enough for its ring to reach the far corners of the LEDs
+/// and fade out.
+const BURST_FRAMES: usize = 7;
+/// Brightness of a burst's ring at its edge, and then of the trail behind it, going inwards.
+const RING_BRIGHTNESS: [u8; 4] = [9, 5, 2, 0];
+
+/// Time each frame of the title screen is shown for, in milliseconds.
+const TITLE_FRAME_MS: u32 = 120;
+/// The path the snake on the title screen follows, round the edge of the LEDs, as `(row, col)`
+/// pairs. It goes round and round, so the last tile is next to the first.
+const TITLE_PATH: [(usize, usize); 16] = [
+    (0, 0), (0, 1), (0, 2), (0, 3), (0, 4),
+    (1, 4), (2, 4), (3, 4), (4, 4),
+    (4, 3), (4, 2), (4, 1), (4, 0),
+    (3, 0), (2, 0), (1, 0)
+];
+/// Brightness of the title screen's snake, from its head to the end of its tail.
+const TITLE_SNAKE: [u8; 5] = [9, 6, 4, 2, 1];
+
+/// Time each frame of the new high score animation is shown for, in milliseconds.
+const NEW_HIGH_FRAME_MS: u32 = 60;
+/// Number of times light sweeps up the trophy when a high score is beaten.
+const NEW_HIGH_SWEEPS: usize = 3;
+/// Brightness of the trophy where the light isn't sweeping past.
+const TROPHY_BRIGHTNESS: u8 = 3;
+
+/// Time each frame of a fade is shown for, in milliseconds.
+const FADE_FRAME_MS: u32 = 40;
+/// Time the trophy takes to fade in when a high score is beaten with flashing reduced, in
+/// milliseconds.
+const TROPHY_FADE_MS: u32 = 800;
+/// Time the trophy is then shown for, in milliseconds.
+const TROPHY_HOLD_MS: u32 = 1200;
+
+/// Brightness of each LED in a frame.
+pub(crate) type Matrix = [[u8; LED_COLS]; LED_ROWS];
+
+/// One frame of an animation.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Frame {
+    pub(crate) matrix: Matrix,
+    /// How long the frame is shown for, in milliseconds.
+    pub(crate) ms: u32
+}
+
+/// Play `frames` in order, calling `wait` with how long each is shown for. If `wait` returns false,
+/// the animation stops there and this returns false too.
+pub(crate) fn play(
+    frames: impl IntoIterator<Item = Frame>,
+    mut wait: impl FnMut(u32) -> bool
+) -> bool {
+    for frame in frames {
+        display_image(&GreyscaleImage::new(&frame.matrix));
+        if !wait(frame.ms) {
+            return false;
+        }
+    }
+    true
+}
+
+/// `first` then `second`, `times` times over, showing each for `ms` milliseconds.
+pub(crate) fn blink(
+    first: Matrix,
+    second: Matrix,
+    times: usize,
+    ms: u32
+) -> impl Iterator<Item = Frame> {
+    (0..times * 2).map(move |i| Frame { matrix: if i % 2 == 0 { first } else { second }, ms })
+}
+
+/// A steady fade from `first` to `second` over `ms` milliseconds, then `second` held for `hold_ms`:
+/// a gentler way of drawing attention to something than [`blink`], for reduced flashing.
+pub(crate) fn fade(
+    first: Matrix,
+    second: Matrix,
+    ms: u32,
+    hold_ms: u32
+) -> impl Iterator<Item = Frame> {
+    let frames = (ms / FADE_FRAME_MS).max(1);
+    (1..=frames)
+        .map(move |frame| Frame {
+            matrix: blend_matrix(&first, &second, frame, frames),
+            ms: FADE_FRAME_MS
+        })
+        .chain(core::iter::once(Frame { matrix: second, ms: hold_ms }))
+}
+
+/// The title screen: a snake going once round the edge of the LEDs, ending where it started, so
+/// that it can be played over and over.
+pub(crate) fn title() -> impl Iterator<Item = Frame> {
+    (0..TITLE_PATH.len()).map(|head| {
+        let mut matrix = [[0; LED_COLS]; LED_ROWS];
+        for (behind, &brightness) in TITLE_SNAKE.iter().enumerate() {
+            let (row, col) = TITLE_PATH[(head + TITLE_PATH.len() - behind) % TITLE_PATH.len()];
+            matrix[row][col] = brightness;
+        }
+        Frame { matrix, ms: TITLE_FRAME_MS }
+    })
+}
+
+/// Fireworks, for winning: bursts of light, one after another, each spreading out in a ring from a
+/// point on the LEDs.
+pub(crate) fn fireworks() -> impl Iterator<Item = Frame> {
+    BURSTS.iter().flat_map(|&(row, col)| {
+        (0..BURST_FRAMES).map(move |frame| Frame {
+            matrix: burst(row, col, frame),
+            ms: FIREWORK_FRAME_MS
+        })
+    })
+}
+
+/// A new high score: light sweeping up the trophy a few times, then fireworks.
+pub(crate) fn new_high() -> impl Iterator<Item = Frame> {
+    // The sweep starts below the LEDs and ends above them, so the whole trophy is dim in between
+    let sweeps = (0..NEW_HIGH_SWEEPS).flat_map(|_| (0..=LED_ROWS + 1).rev()).map(|band| {
+        let mut matrix = [[0; LED_COLS]; LED_ROWS];
+        for (row, (cells, icon_row)) in matrix.iter_mut().zip(icons::TROPHY.iter()).enumerate() {
+            let brightness = if row + 1 == band { 9 } else { TROPHY_BRIGHTNESS };
+            for (cell, &lit) in cells.iter_mut().zip(icon_row.iter()) {
+                *cell = lit * brightness;
+            }
+        }
+        Frame { matrix, ms: NEW_HIGH_FRAME_MS }
+    });
+    sweeps.chain(fireworks())
+}
+
+/// A new high score, with flashing reduced: the trophy fading in and staying lit for a moment.
+pub(crate) fn calm_new_high() -> impl Iterator<Item = Frame> {
+    let mut trophy = icons::TROPHY;
+    for value in trophy.iter_mut().flatten() {
+        *value *= 9;
+    }
+    fade([[0; LED_COLS]; LED_ROWS], trophy, TROPHY_FADE_MS, TROPHY_HOLD_MS)
+}
+
+/// A burst from `(row, col)`, `frame` frames after it started: a ring `frame` tiles out from where
+/// it started (counting diagonal steps as one), fading towards the middle behind it.
+fn burst(row: usize, col: usize, frame: usize) -> Matrix {
+    let mut matrix = [[0; LED_COLS]; LED_ROWS];
+    for (r, cells) in matrix.iter_mut().enumerate() {
+        for (c, cell) in cells.iter_mut().enumerate() {
+            let distance = r.abs_diff(row).max(c.abs_diff(col));
+            if distance <= frame {
+                *cell = RING_BRIGHTNESS[(frame - distance).min(RING_BRIGHTNESS.len() - 1)];
+            }
+        }
+    }
+    matrix
+}
@@ -0,0 +1,91 @@
+//! A watchdog-style check that no single operation in the main loop blocks for long. The display
+//! is refreshed from an interrupt, so it keeps going while the main loop is busy, but anything that
+//! stalls the CPU (such as erasing flash) freezes it, and anything that holds up the main loop makes
+//! input sluggish.
+//!
+//! Operations are timed with the Cortex-M cycle counter. The check is a debug assertion, so release
+//! builds only pay for reading the counter.
+//!
+//! Writing to the serial port isn't timed. Writes block until every byte has gone out, which at
+//! 115200 baud is about 87us a byte, so any reply longer than a line (such as the leaderboard)
+//! would blow the budget however little work went into it.
+//!
+//! The same counter is used to check that the game loop as a whole keeps up with the frame timer.
+//! The timer doesn't queue up ticks that aren't waited for in time, so a loop that can't keep up
+//! would otherwise just slow the game down without anyone noticing.
+
+use cortex_m::peripheral::{DCB, DWT};
+
+/// Longest that any single operation in the main loop may take, in microseconds.
+const MAX_OPERATION_US: u32 = 5000;
+/// CPU clock frequency, in MHz.
+const CPU_MHZ: u32 = 64;
+/// Length of each check that the game loop is keeping up, in milliseconds. Short enough that the
+/// cycle counter can't wrap around more than once.
+const FRAME_CHECK_MS: u32 = 10_000;
+/// Percentage of the expected frames that must be counted for the game loop to be keeping up.
+const MIN_FRAMES_PERCENT: u32 = 95;
+
+/// Start the cycle counter used to time operations.
+pub(crate) fn init_budget(dcb: &mut DCB, dwt: &mut DWT) {
+    dcb.enable_trace();
+    dwt.enable_cycle_counter();
+}
+
+/// Milliseconds since the cycle counter read `start`. Only correct for intervals of up to a minute
+/// or so, after which the counter wraps around.
+pub(crate) fn ms_since(start: u32) -> u32 {
+    DWT::cycle_count().wrapping_sub(start) / (CPU_MHZ * 1000)
+}
+
+/// Microseconds since the cycle counter read `start`. Only correct for intervals of up to a minute
+/// or so, after which the counter wraps around.
+pub(crate) fn us_since(start: u32) -> u32 {
+    DWT::cycle_count().wrapping_sub(start) / CPU_MHZ
+}
+
+/// Run `operation`, asserting (in debug builds) that it finishes within the budget. `name` is used
+/// to identify the operation if it doesn't.
+pub(crate) fn within_budget<T>(name: &str, operation: impl FnOnce() -> T) -> T {
+    let start = DWT::cycle_count();
+    let result = operation();
+    let elapsed_us = DWT::cycle_count().wrapping_sub(start) / CPU_MHZ;
+    debug_assert!(elapsed_us <= MAX_OPERATION_US, "{} took {}us", name, elapsed_us);
+    result
+}
+
+/// Counts the frames the game loop waits for, to check that it isn't dropping timer ticks.
+pub(crate) struct FrameCheck {
+    /// Length of a frame, in milliseconds.
+    frame_ms: u32,
+    /// Cycle count at the start of the check.
+    start: u32,
+    /// Frames counted since the start of the check.
+    frames: u32
+}
+
+impl FrameCheck {
+    /// Start checking frames of `frame_ms` milliseconds each.
+    pub(crate) fn new(frame_ms: u32) -> Self {
+        Self { frame_ms, start: DWT::cycle_count(), frames: 0 }
+    }
+
+    /// Start the check again, eg after the game loop has been held up on purpose.
+    pub(crate) fn restart(&mut self) {
+        *self = Self::new(self.frame_ms);
+    }
+
+    /// Count a frame. Once the check has run for its full length, return whether enough frames
+    /// were counted in that time, and start a new check.
+    pub(crate) fn frame(&mut self) -> Option<bool> {
+        self.frames += 1;
+        let elapsed_ms = ms_since(self.start);
+        if elapsed_ms < FRAME_CHECK_MS {
+            return None;
+        }
+        let expected = elapsed_ms / self.frame_ms;
+        let kept_up = self.frames * 100 >= expected * MIN_FRAMES_PERCENT;
+        self.restart();
+        Some(kept_up)
+    }
+}
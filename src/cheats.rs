@@ -0,0 +1,86 @@
+//! Cheat codes: secret sequences of presses, made on the title or pause screen while touching the
+//! logo, which unlock hidden modes. While the logo is touched, presses do nothing but enter codes.
+
+use heapless::Vec;
+use snakebit_core::input::SequenceMatcher;
+
+use crate::control::InputEvent;
+
+/// Maximum number of cheat codes.
+const MAX_CHEATS: usize = 3;
+
+/// B, B, A, A, B, A.
+const DEV_MENU_CODE: [InputEvent; 6] = [
+    InputEvent::TurnRight,
+    InputEvent::TurnRight,
+    InputEvent::TurnLeft,
+    InputEvent::TurnLeft,
+    InputEvent::TurnRight,
+    InputEvent::TurnLeft
+];
+/// A, A, B, B, then both together.
+const INVINCIBLE_CODE: [InputEvent; 5] = [
+    InputEvent::TurnLeft,
+    InputEvent::TurnLeft,
+    InputEvent::TurnRight,
+    InputEvent::TurnRight,
+    InputEvent::Both
+];
+/// A, B, A, B.
+#[cfg(feature = "compass")]
+const COMPASS_CODE: [InputEvent; 4] =
+    [InputEvent::TurnLeft, InputEvent::TurnRight, InputEvent::TurnLeft, InputEvent::TurnRight];
+
+/// What a cheat code unlocks.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Cheat {
+    /// Open the developer menu.
+    DevMenu,
+    /// Turn invincibility on or off.
+    Invincible,
+    /// Turn compass steering on or off.
+    #[cfg(feature = "compass")]
+    Compass
+}
+
+/// Watches for every cheat code at once.
+pub(crate) struct CheatCodes {
+    matchers: Vec<(Cheat, SequenceMatcher<'static, InputEvent>), MAX_CHEATS>
+}
+
+impl CheatCodes {
+    pub(crate) fn new() -> Self {
+        let mut matchers = Vec::new();
+        let _ = matchers.push((Cheat::DevMenu, SequenceMatcher::new(&DEV_MENU_CODE)));
+        let _ = matchers.push((Cheat::Invincible, SequenceMatcher::new(&INVINCIBLE_CODE)));
+        #[cfg(feature = "compass")]
+        let _ = matchers.push((Cheat::Compass, SequenceMatcher::new(&COMPASS_CODE)));
+        Self { matchers }
+    }
+
+    /// Take the next event, returning the cheat whose code it completes, if any. Completing a code
+    /// starts every search again. Released events are ignored, so that a code is the same whether
+    /// or not its presses were parts of chords.
+    pub(crate) fn feed(&mut self, event: InputEvent) -> Option<Cheat> {
+        if event == InputEvent::Released {
+            return None;
+        }
+        let mut completed = None;
+        for (cheat, matcher) in self.matchers.iter_mut() {
+            if matcher.feed(event) && completed.is_none() {
+                completed = Some(*cheat);
+            }
+        }
+        if completed.is_some() {
+            self.reset();
+        }
+        completed
+    }
+
+    /// Forget any partly entered codes.
+    pub(crate) fn reset(&mut self) {
+        for (_, matcher) in self.matchers.iter_mut() {
+            matcher.reset();
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! Wall-clock time. The micro:bit has no battery-backed clock, so the time has to be set (eg, over
+//! serial) each time the board is powered on, after which it is kept by the RTC.
+//!
+//! The RTC is driven by the low frequency clock, which on the micro:bit comes from an internal RC
+//! oscillator rather than a crystal, so expect the time to drift by a few seconds a day.
+
+use core::cell::RefCell;
+use core::fmt;
+use cortex_m::interrupt::{free, CriticalSection, Mutex};
+use microbit::hal::rtc::{Rtc, RtcInterrupt};
+use microbit::pac::{self, interrupt, RTC0};
+
+use crate::display::{LED_COLS, LED_ROWS};
+
+/// RTC prescaler, giving a tick rate of 32768 / (4095 + 1) = 8Hz.
+const PRESCALER: u32 = 4095;
+const TICKS_PER_SECOND: u64 = 8;
+/// Number of bits in the RTC's counter.
+const COUNTER_BITS: u32 = 24;
+const SECONDS_PER_DAY: u32 = 86400;
+
+static RTC: Mutex<RefCell<Option<Rtc<RTC0>>>> = Mutex::new(RefCell::new(None));
+/// Number of times the RTC's counter has overflowed.
+static OVERFLOWS: Mutex<RefCell<u32>> = Mutex::new(RefCell::new(0));
+/// The time that was last set, in seconds since the Unix epoch, and the RTC tick count at the time
+/// it was set.
+static SET_AT: Mutex<RefCell<Option<(u32, u64)>>> = Mutex::new(RefCell::new(None));
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Date {
+    pub(crate) year: u16,
+    /// Month of the year, from 1 to 12.
+    pub(crate) month: u8,
+    /// Day of the month, from 1.
+    pub(crate) day: u8
+}
+
+impl Date {
+    /// Number of days since 1 January 1970. See
+    /// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    pub(crate) fn day_number(&self) -> u32 {
+        let month = self.month as i32;
+        let year = self.year as i32 - if month <= 2 { 1 } else { 0 };
+        let era = year / 400;
+        let year_of_era = year - era * 400;
+        let month_from_march = if month > 2 { month - 3 } else { month + 9 };
+        let day_of_year = (153 * month_from_march + 2) / 5 + self.day as i32 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        (era * 146097 + day_of_era - 719468) as u32
+    }
+
+    /// The inverse of [`Date::day_number`]. See
+    /// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    fn from_day_number(day_number: u32) -> Self {
+        let days = day_number as i32 + 719468;
+        let era = days / 146097;
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+        let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8
+        }
+    }
+
+    fn days_in_month(year: u16, month: u8) -> u8 {
+        match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct DateTime {
+    pub(crate) date: Date,
+    pub(crate) hour: u8,
+    pub(crate) minute: u8,
+    pub(crate) second: u8
+}
+
+impl DateTime {
+    /// Parse a date and time in the form `YYYY-MM-DD HH:MM:SS`. Returns `None` if the input is not
+    /// in that form or does not describe a valid date and time (no earlier than 1970).
+    pub(crate) fn parse(input: &[u8]) -> Option<Self> {
+        if input.len() != 19 || input[4] != b'-' || input[7] != b'-' || input[10] != b' '
+            || input[13] != b':' || input[16] != b':' {
+            return None;
+        }
+        let year = parse_digits(&input[0..4])? as u16;
+        let month = parse_digits(&input[5..7])? as u8;
+        let day = parse_digits(&input[8..10])? as u8;
+        let hour = parse_digits(&input[11..13])? as u8;
+        let minute = parse_digits(&input[14..16])? as u8;
+        let second = parse_digits(&input[17..19])? as u8;
+        if year < 1970 || !(1..=12).contains(&month) || day == 0
+            || day > Date::days_in_month(year, month) || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        Some(Self {
+            date: Date { year, month, day },
+            hour,
+            minute,
+            second
+        })
+    }
+
+    pub(crate) fn from_unix_seconds(seconds: u32) -> Self {
+        let time_of_day = seconds % SECONDS_PER_DAY;
+        Self {
+            date: Date::from_day_number(seconds / SECONDS_PER_DAY),
+            hour: (time_of_day / 3600) as u8,
+            minute: (time_of_day / 60 % 60) as u8,
+            second: (time_of_day % 60) as u8
+        }
+    }
+
+    /// A binary clock face showing the time, as brightnesses for the display. The digits of the
+    /// hour and minute each get a column, going from left to right and lit from the bottom up
+    /// (the bottom LED being worth 1, the one above it 2, and so on). The middle column has a
+    /// single LED that blinks each second.
+    pub(crate) fn clock_face(&self) -> [[u8; LED_COLS]; LED_ROWS] {
+        let mut face = [[0u8; LED_COLS]; LED_ROWS];
+        let digits = [
+            (0, self.hour / 10),
+            (1, self.hour % 10),
+            (3, self.minute / 10),
+            (4, self.minute % 10)
+        ];
+        for &(col, digit) in digits.iter() {
+            for bit in 0..4 {
+                if digit & (1 << bit) != 0 {
+                    face[LED_ROWS - 1 - bit][col] = 9;
+                }
+            }
+        }
+        if self.second % 2 == 0 {
+            face[LED_ROWS / 2][2] = 5;
+        }
+        face
+    }
+
+    pub(crate) fn unix_seconds(&self) -> u32 {
+        self.date.day_number() * SECONDS_PER_DAY
+            + self.hour as u32 * 3600
+            + self.minute as u32 * 60
+            + self.second as u32
+    }
+}
+
+/// Formats as `YYYY-MM-DD HH:MM:SS`, the same form accepted by `DateTime::parse`.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.date.year, self.date.month, self.date.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Parse a string of ASCII digits as a decimal number.
+fn parse_digits(digits: &[u8]) -> Option<u32> {
+    digits.iter().try_fold(0u32, |acc, &d| {
+        if d.is_ascii_digit() {
+            Some(acc * 10 + (d - b'0') as u32)
+        } else {
+            None
+        }
+    })
+}
+
+pub(crate) fn init_clock(board_rtc: RTC0) {
+    let mut rtc = Rtc::new(board_rtc, PRESCALER).unwrap();
+    rtc.enable_event(RtcInterrupt::Overflow);
+    rtc.enable_interrupt(RtcInterrupt::Overflow, None);
+    rtc.enable_counter();
+    free(move |cs| {
+        *RTC.borrow(cs).borrow_mut() = Some(rtc);
+    });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::RTC0)
+    }
+}
+
+/// Count any pending overflow of the RTC's counter.
+fn handle_overflow(cs: &CriticalSection, rtc: &Rtc<RTC0>) {
+    if rtc.is_event_triggered(RtcInterrupt::Overflow) {
+        rtc.reset_event(RtcInterrupt::Overflow);
+        *OVERFLOWS.borrow(cs).borrow_mut() += 1;
+    }
+}
+
+/// Number of RTC ticks since the clock was initialised.
+fn ticks(cs: &CriticalSection) -> u64 {
+    match RTC.borrow(cs).borrow().as_ref() {
+        Some(rtc) => {
+            handle_overflow(cs, rtc);
+            let counter = rtc.get_counter();
+            // If the counter overflowed between the check above and being read, it will be close to
+            // zero, and the overflow needs counting
+            if counter < (1 << (COUNTER_BITS - 1)) {
+                handle_overflow(cs, rtc);
+            }
+            let overflows = *OVERFLOWS.borrow(cs).borrow() as u64;
+            (overflows << COUNTER_BITS) | counter as u64
+        },
+        None => 0
+    }
+}
+
+/// The current date and time, if it has been set.
+pub(crate) fn now() -> Option<DateTime> {
+    free(|cs| {
+        let (set_seconds, set_ticks) = (*SET_AT.borrow(cs).borrow())?;
+        let elapsed = (ticks(cs) - set_ticks) / TICKS_PER_SECOND;
+        Some(DateTime::from_unix_seconds(set_seconds + elapsed as u32))
+    })
+}
+
+/// Today's date, if it has been set.
+pub(crate) fn today() -> Option<Date> {
+    now().map(|now| now.date)
+}
+
+pub(crate) fn set_now(now: DateTime) {
+    free(|cs| {
+        *SET_AT.borrow(cs).borrow_mut() = Some((now.unix_seconds(), ticks(cs)));
+    })
+}
+
+#[interrupt]
+fn RTC0() {
+    free(|cs| {
+        if let Some(rtc) = RTC.borrow(cs).borrow().as_ref() {
+            handle_overflow(cs, rtc);
+        }
+    })
+}
@@ -0,0 +1,116 @@
+//! Steering by compass, an easter egg: the snake always heads for magnetic north, so the player
+//! steers it by turning the board around on the table.
+//!
+//! The magnetometer picks up the magnetism of the board itself and anything near it as well as the
+//! Earth's field, which shifts every reading by the same amount. That shift is found as the board
+//! is turned, from the middle of the range of readings seen along each axis, so the snake carries
+//! straight on until the board has been turned most of the way around once.
+
+use snakebit_core::game::Direction;
+
+use crate::control::{InputEvent, InputSource};
+use crate::display::upright;
+use crate::hardware::Accelerometer;
+
+/// Interval between magnetometer readings, in milliseconds. Each reading is started at one poll and
+/// picked up at a later one, once the magnetometer has finished it.
+const COMPASS_SAMPLE_MS: u32 = 100;
+/// Range of readings (in nanotesla) that must be seen along each horizontal axis before the
+/// compass counts as calibrated. Turning the board all the way around gives a range of twice the
+/// horizontal part of the Earth's field, which is at least 20,000nT almost everywhere.
+const MIN_RANGE_NT: i32 = 30_000;
+/// How much stronger (in eighths) the field along one axis must be than along the other to change
+/// direction, so that the snake doesn't flick back and forth when north is near a diagonal.
+const HYSTERESIS_EIGHTHS: i32 = 10;
+
+/// The lowest and highest readings seen along an axis.
+#[derive(Debug, Copy, Clone)]
+struct Range {
+    min: i32,
+    max: i32
+}
+
+impl Range {
+    fn include(&mut self, reading: i32) {
+        self.min = self.min.min(reading);
+        self.max = self.max.max(reading);
+    }
+
+    fn centre(&self) -> i32 {
+        self.min + (self.max - self.min) / 2
+    }
+
+    fn is_wide(&self) -> bool {
+        self.max - self.min >= MIN_RANGE_NT
+    }
+}
+
+/// Steering towards magnetic north, in place of the buttons.
+pub(crate) struct CompassSteering {
+    /// Time since the magnetometer was last read.
+    since_sample_ms: u32,
+    /// The readings seen so far along the X and Y axes, or `None` before the first reading.
+    ranges: Option<(Range, Range)>,
+    /// Which way north was at the last reading, if the compass has been calibrated.
+    direction: Option<Direction>,
+    /// Whether the direction hasn't been gathered since the last poll.
+    unread: bool
+}
+
+impl CompassSteering {
+    pub(crate) fn new() -> Self {
+        Self { since_sample_ms: COMPASS_SAMPLE_MS, ranges: None, direction: None, unread: false }
+    }
+
+    /// Count `elapsed_ms` milliseconds towards the next reading, reading the magnetometer if it is
+    /// due and has a reading ready. This should be called every frame.
+    pub(crate) fn poll(&mut self, accelerometer: &mut Accelerometer, elapsed_ms: u32) {
+        self.since_sample_ms += elapsed_ms;
+        self.unread = true;
+        if self.since_sample_ms < COMPASS_SAMPLE_MS {
+            return;
+        }
+        let (x, y, _) = match accelerometer.magnetic_field() {
+            Ok(field) => field.xyz_nt(),
+            // Still measuring, so try again next frame
+            Err(_) => return
+        };
+        self.since_sample_ms = 0;
+        let (x_range, y_range) = self.ranges.get_or_insert((
+            Range { min: x, max: x },
+            Range { min: y, max: y }
+        ));
+        x_range.include(x);
+        y_range.include(y);
+        if !x_range.is_wide() || !y_range.is_wide() {
+            return;
+        }
+        let (x, y) = (x - x_range.centre(), y - y_range.centre());
+        let current_is_x = matches!(self.direction, Some(Direction::Left | Direction::Right));
+        // Stay on the current axis unless the other is clearly stronger
+        let x_wins = if current_is_x {
+            x.abs() * HYSTERESIS_EIGHTHS >= y.abs() * 8
+        } else {
+            x.abs() * 8 > y.abs() * HYSTERESIS_EIGHTHS
+        };
+        self.direction = Some(if x_wins {
+            if x > 0 { Direction::Right } else { Direction::Left }
+        } else if y > 0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        });
+    }
+}
+
+impl InputSource for CompassSteering {
+    /// Head for north, once per poll, so that the snake keeps heading that way as long as north
+    /// stays the same way round. North is found relative to the board, so is turned to match the
+    /// way it is held.
+    fn next_event(&mut self) -> Option<InputEvent> {
+        if !core::mem::take(&mut self.unread) {
+            return None;
+        }
+        self.direction.map(|direction| InputEvent::Head(upright(direction)))
+    }
+}
@@ -0,0 +1,297 @@
+//! Player-configurable settings.
+
+use snakebit_core::game::rules::SpeedSchedule;
+use snakebit_core::input::ControlScheme;
+
+use crate::actions::ActionMap;
+#[cfg(feature = "persist")]
+use crate::actions::ACTION_MAP_LEN;
+use crate::display::BRIGHTNESS_LEVELS;
+#[cfg(feature = "persist")]
+use crate::display::ROTATIONS;
+#[cfg(feature = "persist")]
+use crate::storage::{Record, Storage};
+
+/// Length of [`GameConfig`] when serialized: the settings themselves, then the action map.
+#[cfg(feature = "persist")]
+const CONFIG_LEN: usize = SETTINGS_LEN + ACTION_MAP_LEN;
+/// Length of the settings apart from the action map, when serialized.
+#[cfg(feature = "persist")]
+const SETTINGS_LEN: usize = 8;
+/// Bit set in the flags of a saved [`GameConfig`] if the snake wraps around.
+#[cfg(feature = "persist")]
+const WRAPAROUND_BIT: u16 = 1;
+/// Bit set in the flags of a saved [`GameConfig`] if bouncing walls are on.
+#[cfg(feature = "persist")]
+const BOUNCING_WALLS_BIT: u16 = 2;
+/// Bit set in the flags of a saved [`GameConfig`] if bonus rounds are on.
+#[cfg(feature = "persist")]
+const BONUS_ROUNDS_BIT: u16 = 4;
+/// Bit set in the flags of a saved [`GameConfig`] if the high contrast display is on.
+#[cfg(feature = "persist")]
+const HIGH_CONTRAST_BIT: u16 = 8;
+/// Bit set in the flags of a saved [`GameConfig`] if level beeps are on.
+#[cfg(feature = "persist")]
+const LEVEL_BEEPS_BIT: u16 = 16;
+/// Bit set in the flags of a saved [`GameConfig`] if the buttons are swapped.
+#[cfg(feature = "persist")]
+const SWAP_BUTTONS_BIT: u16 = 32;
+/// Bit set in the flags of a saved [`GameConfig`] if the snake starts at random.
+#[cfg(feature = "persist")]
+const RANDOM_START_BIT: u16 = 64;
+/// Bit set in the flags of a saved [`GameConfig`] if holding a button keeps turning.
+#[cfg(feature = "persist")]
+const HOLD_TO_TURN_BIT: u16 = 128;
+/// Bit set in the flags of a saved [`GameConfig`] if buttons turn as soon as pressed.
+#[cfg(feature = "persist")]
+const TURN_ON_PRESS_BIT: u16 = 256;
+/// Bit set in the flags of a saved [`GameConfig`] if sound is on.
+#[cfg(feature = "persist")]
+const SOUND_BIT: u16 = 512;
+/// Bit set in the flags of a saved [`GameConfig`] if reduced flashing is on.
+#[cfg(feature = "persist")]
+const REDUCED_FLASHING_BIT: u16 = 1024;
+/// Bit set in the flags of a saved [`GameConfig`] if food placed out of sight leaves a trail.
+#[cfg(feature = "persist")]
+const FOOD_TRAILS_BIT: u16 = 2048;
+/// Every bit that may be set in the flags of a saved [`GameConfig`].
+#[cfg(feature = "persist")]
+const ALL_BITS: u16 = 4095;
+
+/// What kind of game to play.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum GameMode {
+    /// No walls, with food placed at random.
+    Normal,
+    /// The daily puzzle (when the date is known; otherwise a normal game).
+    Daily,
+    /// The wall layout drawn in the wall editor.
+    Custom,
+    /// A faster game with no walls, where each piece of food is worth two points.
+    Blitz,
+    /// An endless game with no walls, where filling the grid trims the snake back to carry on.
+    Marathon,
+    /// No walls, with each piece of food placed as far from the snake's head as it can get.
+    Hard,
+    /// No walls, with the snake cut short rather than crashing when it runs into itself.
+    Penalty
+}
+
+#[cfg(any(feature = "persist", feature = "radio"))]
+impl GameMode {
+    /// The byte the mode is saved or sent as.
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            GameMode::Normal => 0,
+            GameMode::Daily => 1,
+            GameMode::Custom => 2,
+            GameMode::Blitz => 3,
+            GameMode::Marathon => 4,
+            GameMode::Hard => 5,
+            GameMode::Penalty => 6
+        }
+    }
+
+    /// The mode saved or sent as `byte`, or `None` if it isn't one.
+    pub(crate) fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(GameMode::Normal),
+            1 => Some(GameMode::Daily),
+            2 => Some(GameMode::Custom),
+            3 => Some(GameMode::Blitz),
+            4 => Some(GameMode::Marathon),
+            5 => Some(GameMode::Hard),
+            6 => Some(GameMode::Penalty),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct GameConfig {
+    pub(crate) control_scheme: ControlScheme,
+    pub(crate) mode: GameMode,
+    /// Whether the snake is steered by tilting the board rather than with the buttons. Only has
+    /// an effect if the firmware was built with the `tilt` feature and the accelerometer works.
+    pub(crate) tilt_steering: bool,
+    /// Whether the snake wraps around the edges of the grid, rather than crashing into them.
+    pub(crate) wraparound: bool,
+    /// Whether running into a wall turns the snake along it, rather than crashing.
+    pub(crate) bouncing_walls: bool,
+    /// How the snake speeds up as it eats.
+    pub(crate) speed_schedule: SpeedSchedule,
+    /// Whether a bonus round is played each time the snake speeds up.
+    pub(crate) bonus_rounds: bool,
+    /// Whether the game is drawn with only two brightness levels, with the food blinking.
+    pub(crate) high_contrast: bool,
+    /// Whether the speaker beeps out the new speed each time the snake speeds up. Only has an
+    /// effect if the firmware was built with the `audio` feature.
+    pub(crate) level_beeps: bool,
+    /// Whether the A and B buttons are swapped, for left-handed players or for holding the board
+    /// upside down.
+    pub(crate) swap_buttons: bool,
+    /// Whether the snake starts each game at a random position, heading in a random direction.
+    pub(crate) random_start: bool,
+    /// Whether holding a button down turns the snake again at every step, for tight spirals, rather
+    /// than pausing the game.
+    pub(crate) hold_to_turn: bool,
+    /// Whether a press of a button turns the snake as soon as the button is pressed, rather than
+    /// when it is let go. Holding a button down doesn't pause the game while this is on.
+    pub(crate) turn_on_press: bool,
+    /// What each gesture does during play.
+    pub(crate) actions: ActionMap,
+    /// Whether the speaker makes any sound at all. Only has an effect if the firmware was built
+    /// with the `audio` feature.
+    pub(crate) sound: bool,
+    /// How bright the display is, from 1 (dimmest) to [`BRIGHTNESS_LEVELS`] (full brightness).
+    pub(crate) brightness: u8,
+    /// How many quarter turns clockwise the board is held from upright (with the USB socket at the
+    /// top), from 0 to 3. The display and tilting are turned to match.
+    pub(crate) rotation: u8,
+    /// Whether flashing effects are replaced with steady or slowly fading ones, for players
+    /// sensitive to flashing lights.
+    pub(crate) reduced_flashing: bool,
+    /// Whether food placed where the board's own LEDs can't show it leaves a fading trail leading
+    /// to it. Only has an effect on grids bigger than the LEDs.
+    pub(crate) food_trails: bool
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            control_scheme: ControlScheme::Relative,
+            mode: GameMode::Normal,
+            tilt_steering: false,
+            wraparound: true,
+            bouncing_walls: false,
+            speed_schedule: SpeedSchedule::default(),
+            bonus_rounds: false,
+            high_contrast: false,
+            level_beeps: false,
+            swap_buttons: false,
+            random_start: false,
+            hold_to_turn: false,
+            turn_on_press: false,
+            actions: ActionMap::DEFAULT,
+            sound: true,
+            brightness: BRIGHTNESS_LEVELS,
+            rotation: 0,
+            reduced_flashing: false,
+            food_trails: false
+        }
+    }
+}
+
+/// Settings changed for the current session only, which take precedence over the saved settings
+/// until the board is powered off. Each is `None` if it hasn't been changed.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct SessionOverrides {
+    pub(crate) wraparound: Option<bool>,
+    pub(crate) sound: Option<bool>,
+    /// Whether crashes stop the snake rather than ending the game. This is a cheat, so it is never
+    /// saved, and is off rather than `None` until it has been found.
+    pub(crate) invincible: bool,
+    /// Whether the snake is steered by compass. This is an easter egg, so it is never saved, and
+    /// is off rather than `None` until it has been found.
+    #[cfg(feature = "compass")]
+    pub(crate) compass_steering: bool
+}
+
+impl SessionOverrides {
+    /// The settings in effect: the saved settings, with any overrides applied.
+    pub(crate) fn apply(&self, config: GameConfig) -> GameConfig {
+        GameConfig {
+            wraparound: self.wraparound.unwrap_or(config.wraparound),
+            sound: self.sound.unwrap_or(config.sound),
+            ..config
+        }
+    }
+}
+
+#[cfg(feature = "persist")]
+impl GameConfig {
+    /// Load settings from flash, or return the default settings if none have been saved (or the
+    /// saved settings are invalid).
+    pub(crate) fn load(storage: &mut Storage) -> Self {
+        let mut bytes = [0u8; CONFIG_LEN];
+        if storage.read(Record::Config, &mut bytes) {
+            Self::from_bytes(&bytes).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    pub(crate) fn save(&self, storage: &mut Storage) {
+        storage.write(Record::Config, &self.to_bytes());
+    }
+
+    fn to_bytes(&self) -> [u8; CONFIG_LEN] {
+        let flags = if self.wraparound { WRAPAROUND_BIT } else { 0 }
+            | if self.bouncing_walls { BOUNCING_WALLS_BIT } else { 0 }
+            | if self.bonus_rounds { BONUS_ROUNDS_BIT } else { 0 }
+            | if self.high_contrast { HIGH_CONTRAST_BIT } else { 0 }
+            | if self.level_beeps { LEVEL_BEEPS_BIT } else { 0 }
+            | if self.swap_buttons { SWAP_BUTTONS_BIT } else { 0 }
+            | if self.random_start { RANDOM_START_BIT } else { 0 }
+            | if self.hold_to_turn { HOLD_TO_TURN_BIT } else { 0 }
+            | if self.turn_on_press { TURN_ON_PRESS_BIT } else { 0 }
+            | if self.sound { SOUND_BIT } else { 0 }
+            | if self.reduced_flashing { REDUCED_FLASHING_BIT } else { 0 }
+            | if self.food_trails { FOOD_TRAILS_BIT } else { 0 };
+        let mut bytes = [0u8; CONFIG_LEN];
+        // Tilting always picks a direction, like absolute controls
+        bytes[0] = match (self.control_scheme, self.tilt_steering) {
+            (_, true) => 2,
+            (ControlScheme::Relative, false) => 0,
+            (ControlScheme::Absolute, false) => 1
+        };
+        bytes[1] = self.mode.to_byte();
+        bytes[2] = self.speed_schedule.food_per_speed_up;
+        bytes[3] = self.speed_schedule.max_speed;
+        bytes[4] = self.brightness;
+        bytes[5] = self.rotation;
+        bytes[6..SETTINGS_LEN].copy_from_slice(&flags.to_le_bytes());
+        bytes[SETTINGS_LEN..].copy_from_slice(&self.actions.to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; CONFIG_LEN]) -> Option<Self> {
+        let (control_scheme, tilt_steering) = match bytes[0] {
+            0 => (ControlScheme::Relative, false),
+            1 => (ControlScheme::Absolute, false),
+            2 => (ControlScheme::Absolute, true),
+            _ => return None
+        };
+        let flags = u16::from_le_bytes([bytes[6], bytes[7]]);
+        if bytes[2] == 0 || bytes[3] == 0 || !(1..=BRIGHTNESS_LEVELS).contains(&bytes[4])
+            || bytes[5] >= ROTATIONS || flags & !ALL_BITS != 0 {
+            return None;
+        }
+        let flag = |bit| flags & bit != 0;
+        let mut actions = [0u8; ACTION_MAP_LEN];
+        actions.copy_from_slice(&bytes[SETTINGS_LEN..]);
+        Some(Self {
+            control_scheme,
+            mode: GameMode::from_byte(bytes[1])?,
+            tilt_steering,
+            wraparound: flag(WRAPAROUND_BIT),
+            bouncing_walls: flag(BOUNCING_WALLS_BIT),
+            speed_schedule: SpeedSchedule { food_per_speed_up: bytes[2], max_speed: bytes[3] },
+            bonus_rounds: flag(BONUS_ROUNDS_BIT),
+            high_contrast: flag(HIGH_CONTRAST_BIT),
+            level_beeps: flag(LEVEL_BEEPS_BIT),
+            swap_buttons: flag(SWAP_BUTTONS_BIT),
+            random_start: flag(RANDOM_START_BIT),
+            hold_to_turn: flag(HOLD_TO_TURN_BIT),
+            turn_on_press: flag(TURN_ON_PRESS_BIT),
+            // Gestures that can't be read back do what they usually do, rather than losing every
+            // other setting with them
+            actions: ActionMap::from_bytes(&actions).unwrap_or_default(),
+            sound: flag(SOUND_BIT),
+            brightness: bytes[4],
+            rotation: bytes[5],
+            reduced_flashing: flag(REDUCED_FLASHING_BIT),
+            food_trails: flag(FOOD_TRAILS_BIT)
+        })
+    }
+}
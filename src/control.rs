@@ -1,31 +1,181 @@
 // https://github.com/nrf-rs/microbit/blob/main/examples/gpio-hal-printbuttons/src/main.rs
 
 use core::cell::RefCell;
-use cortex_m::interrupt::{free, Mutex};
+use cortex_m::interrupt::{free, CriticalSection, Mutex};
+use cortex_m::peripheral::DWT;
+use heapless::spsc::Queue;
 use microbit::board::Buttons;
-use microbit::hal::gpiote::Gpiote;
+use microbit::hal::gpio::{Disconnected, Floating, Input, Level, Pin};
+use microbit::hal::gpio::p1::P1_04;
+use microbit::hal::gpiote::{Gpiote, GpiotePort};
+use microbit::hal::prelude::*;
 use microbit::pac::{self, GPIOTE, interrupt};
-use crate::game::Turn;
+use snakebit_core::game::{Direction, Turn};
+use snakebit_core::input::{self, ControlScheme};
+
+use crate::actions::{Action, ActionMap, Gesture};
+use crate::budget::ms_since;
+#[cfg(feature = "tilt")]
+use crate::hardware::Accelerometer;
+#[cfg(feature = "tilt")]
+use crate::tilt::tilt_direction;
+
+/// Capacity of the input event queue (which can hold one fewer event than this).
+const EVENT_QUEUE_LEN: usize = 8;
+/// How long a button must be held down for, in milliseconds, for it to count as a long press
+/// rather than a short one.
+const LONG_PRESS_MS: u32 = 600;
+/// How long a button must be held down for, in milliseconds, before it starts turning the snake at
+/// every step, when hold-to-turn is on. Shorter than a long press, which it takes the place of.
+const HOLD_REPEAT_MS: u32 = 300;
+/// How long after a change on a button's pin, in milliseconds, further changes on it are ignored
+/// as the contacts bouncing. Much shorter than the quickest deliberate press.
+const DEBOUNCE_MS: u32 = 20;
+/// Likewise for the touch pads, which a finger makes and breaks contact with less cleanly than a
+/// button's contacts.
+#[cfg(feature = "pads")]
+const PAD_DEBOUNCE_MS: u32 = 100;
+/// The events made by touching the pads on pins 0, 1 and 2 of the edge connector. Touching the
+/// first pauses or resumes the game, like pressing both buttons together.
+#[cfg(feature = "pads")]
+const PAD_EVENTS: [InputEvent; 3] = [InputEvent::Both, InputEvent::Mute, InputEvent::Boost];
+/// Interval between accelerometer readings when steering by tilt, in milliseconds. Each reading
+/// is an I2C transfer, so isn't made every frame.
+#[cfg(feature = "tilt")]
+const TILT_SAMPLE_MS: u32 = 100;
+/// Most polling loop iterations to wait for the touch logo to charge, so that a fault can't hang
+/// the game.
+const LOGO_MAX_CHARGE_COUNT: u32 = 5000;
+/// Percentage of its untouched charging time beyond which the touch logo counts as touched.
+const LOGO_TOUCH_PERCENT: u32 = 150;
+/// Number of readings averaged to find the touch logo's untouched charging time.
+const LOGO_CALIBRATION_READINGS: u32 = 8;
 
 static GPIO: Mutex<RefCell<Option<Gpiote>>> = Mutex::new(RefCell::new(None));
-static TURN: Mutex<RefCell<Turn>> = Mutex::new(RefCell::new(Turn::None));
+/// The A and B buttons, kept so we can tell which of them changed when the PORT event fires.
+static BUTTONS: Mutex<RefCell<Option<[Pin<Input<Floating>>; 2]>>> = Mutex::new(RefCell::new(None));
+/// Whether the A and B buttons (as wired, before any swapping) were held down at their last
+/// accepted changes.
+static HELD: Mutex<RefCell<[bool; 2]>> = Mutex::new(RefCell::new([false; 2]));
+/// The touch pads, likewise.
+#[cfg(feature = "pads")]
+static PADS: Mutex<RefCell<Option<[Pin<Input<Floating>>; 3]>>> = Mutex::new(RefCell::new(None));
+/// Whether each touch pad was touched at its last accepted change.
+#[cfg(feature = "pads")]
+static PAD_HELD: Mutex<RefCell<[bool; 3]>> = Mutex::new(RefCell::new([false; 3]));
+/// Input events that have not yet been handled, oldest first.
+static EVENTS: Mutex<RefCell<Queue<QueuedEvent, EVENT_QUEUE_LEN>>> =
+    Mutex::new(RefCell::new(Queue::new()));
+/// Cycle counts at which the A and B buttons were pressed, for presses that haven't yet been
+/// turned into a short or long press (or been part of a chord).
+static PRESSED_AT: Mutex<RefCell<[Option<u32>; 2]>> = Mutex::new(RefCell::new([None; 2]));
+/// Cycle counts of the last changes accepted on the A and B buttons' pins, for debouncing.
+static LAST_EDGE: Mutex<RefCell<[Option<u32>; 2]>> = Mutex::new(RefCell::new([None; 2]));
+/// Whether the A and B buttons have been held down long enough to turn the snake at every step,
+/// and are still held.
+static REPEATING: Mutex<RefCell<[bool; 2]>> = Mutex::new(RefCell::new([false; 2]));
+/// Cycle counts of the last changes accepted on the touch pads' pins, for debouncing.
+#[cfg(feature = "pads")]
+static PAD_LAST_EDGE: Mutex<RefCell<[Option<u32>; 3]>> = Mutex::new(RefCell::new([None; 3]));
+/// Whether the buttons are swapped, so that the physical A button acts as B and vice versa.
+static SWAPPED: Mutex<RefCell<bool>> = Mutex::new(RefCell::new(false));
+/// Whether a short press turns the snake as soon as the button is pressed, rather than when it is
+/// let go.
+static TURN_ON_PRESS: Mutex<RefCell<bool>> = Mutex::new(RefCell::new(false));
+/// Whether the A and B buttons made their turns when they were pressed, so shouldn't make them
+/// again when let go.
+static TURNED_ON_PRESS: Mutex<RefCell<[bool; 2]>> = Mutex::new(RefCell::new([false; 2]));
+/// What each gesture does during play.
+static ACTIONS: Mutex<RefCell<ActionMap>> = Mutex::new(RefCell::new(ActionMap::DEFAULT));
+/// Cycle count at which the last turn taken by [`next_press`] was made, if it hasn't been taken
+/// by [`take_turn_latency_ms`] yet.
+#[cfg(feature = "latency")]
+static TURN_MADE_AT: Mutex<RefCell<Option<u32>>> = Mutex::new(RefCell::new(None));
 
-pub(crate) fn init_buttons(board_gpiote: GPIOTE, board_buttons: Buttons) {
-    let gpiote = Gpiote::new(board_gpiote);
+/// Something the player did with the buttons.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum InputEvent {
+    /// A short press of A.
+    TurnLeft,
+    /// A short press of B.
+    TurnRight,
+    /// Both buttons pressed together: one pressed while the other is held down.
+    Both,
+    /// A button let go without turning, as it was part of a chord or was held down for a long
+    /// press.
+    Released,
+    /// A choice of direction for the snake to head in, from a source that steers directly rather
+    /// than by turning.
+    Head(Direction),
+    /// A touch of the mute pad.
+    #[cfg(feature = "pads")]
+    Mute,
+    /// A touch of the boost pad.
+    #[cfg(feature = "pads")]
+    Boost
+}
+
+/// An input event waiting in the queue.
+#[derive(Debug, Copy, Clone)]
+struct QueuedEvent {
+    event: InputEvent,
+    /// Cycle count at which the player made the event: when the button was pressed, for button
+    /// presses, or when the event was gathered, for other sources.
+    #[cfg(feature = "latency")]
+    at: u32
+}
+
+/// Anything other than the buttons that input events can come from. Button presses are queued
+/// as they happen, by the GPIOTE interrupt; other sources are polled for their events once a
+/// frame with [`gather_input`], which queues them alongside the button presses. Whatever reads
+/// the queue doesn't need to know where each event came from.
+pub(crate) trait InputSource {
+    /// Take the oldest event from this source that hasn't been gathered yet, if any.
+    fn next_event(&mut self) -> Option<InputEvent>;
+}
 
-    let channel0 = gpiote.channel0();
-    channel0
-        .input_pin(&board_buttons.button_a.degrade())
-        .hi_to_lo()
-        .enable_interrupt();
-    channel0.reset_events();
+/// Queue every event waiting at `source`, after any already queued.
+pub(crate) fn gather_input(source: &mut impl InputSource) {
+    while let Some(event) = source.next_event() {
+        free(|cs| push_event(cs, event, DWT::cycle_count()));
+    }
+}
 
-    let channel1 = gpiote.channel1();
-    channel1
-        .input_pin(&board_buttons.button_b.degrade())
-        .hi_to_lo()
-        .enable_interrupt();
-    channel1.reset_events();
+/// One of the two buttons on the front of the board.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Button {
+    A,
+    B
+}
+
+/// Set up the buttons (and, with the `pads` feature, the touch pads on pins 0, 1 and 2 of the edge
+/// connector) to queue input events as they are pressed. The pads are touched together with GND,
+/// which pulls them low against the board's own pull-up resistors.
+///
+/// Every input shares the GPIOTE PORT event rather than taking a channel of its own, so adding
+/// inputs doesn't use up channels. Each pin is sensed for the level it isn't at, so the event fires
+/// whenever any of them changes, and the pins are read to find out which. Anything held down at
+/// this point only counts once it has been let go.
+pub(crate) fn init_buttons(
+    board_gpiote: GPIOTE,
+    board_buttons: Buttons,
+    #[cfg(feature = "pads")] pads: [Pin<Disconnected>; 3]
+) {
+    let gpiote = Gpiote::new(board_gpiote);
+    let buttons = [board_buttons.button_a.degrade(), board_buttons.button_b.degrade()];
+    #[cfg(feature = "pads")]
+    let pads = pads.map(|pad| pad.into_floating_input());
+
+    let port = gpiote.port();
+    let held = [sense_next_change(&port, &buttons[0]), sense_next_change(&port, &buttons[1])];
+    #[cfg(feature = "pads")]
+    let pad_held = [
+        sense_next_change(&port, &pads[0]),
+        sense_next_change(&port, &pads[1]),
+        sense_next_change(&port, &pads[2])
+    ];
+    port.enable_interrupt();
+    port.reset_events();
 
     free(move |cs| {
         /* Enable external GPIO interrupts */
@@ -34,40 +184,561 @@ pub(crate) fn init_buttons(board_gpiote: GPIOTE, board_buttons: Buttons) {
         }
         pac::NVIC::unpend(pac::Interrupt::GPIOTE);
         *GPIO.borrow(cs).borrow_mut() = Some(gpiote);
+        *BUTTONS.borrow(cs).borrow_mut() = Some(buttons);
+        *HELD.borrow(cs).borrow_mut() = held;
+        #[cfg(feature = "pads")]
+        {
+            *PADS.borrow(cs).borrow_mut() = Some(pads);
+            *PAD_HELD.borrow(cs).borrow_mut() = pad_held;
+        }
     });
 
 }
 
-pub fn get_turn(reset: bool) -> Turn {
+/// Swap the buttons (or swap them back), for left-handed players or for holding the board upside
+/// down. Everything the buttons do is swapped, including chords and long presses.
+pub(crate) fn set_buttons_swapped(swapped: bool) {
+    free(|cs| *SWAPPED.borrow(cs).borrow_mut() = swapped);
+}
+
+/// Have short presses make their turns as soon as the button is pressed (if `on_press`) or when it
+/// is let go, as they usually do. Turning on press is quicker, but a press can't then be told apart
+/// from the start of a long press, so long presses should be ignored while it is on. Only presses
+/// made after this is called are affected.
+pub(crate) fn set_turn_on_press(on_press: bool) {
+    free(|cs| *TURN_ON_PRESS.borrow(cs).borrow_mut() = on_press);
+}
+
+/// Change what each gesture does during play. This takes effect straight away, including for
+/// presses already queued.
+pub(crate) fn set_action_map(map: ActionMap) {
+    free(|cs| *ACTIONS.borrow(cs).borrow_mut() = map);
+}
+
+/// Take the oldest input event that has not yet been handled, if any.
+pub(crate) fn next_event() -> Option<InputEvent> {
+    free(|cs| EVENTS.borrow(cs).borrow_mut().dequeue().map(|queued| queued.event))
+}
+
+/// Take the oldest turn that has not yet been handled, if any. A short button press is only seen
+/// once the button is let go, so that it can be told apart from a long press, and turns the snake
+/// as the action map (see [`set_action_map`]) says it should. [`InputEvent::Released`] events and
+/// presses that do nothing on the way are skipped, but a chord or a press that makes some other
+/// action stops the search and is left for [`take_action`] (or [`take_chord`]).
+pub fn next_press() -> Turn {
+    free(|cs| {
+        let mut events = EVENTS.borrow(cs).borrow_mut();
+        let actions = *ACTIONS.borrow(cs).borrow();
+        loop {
+            let turn = match events.peek().map(|queued| queued.event) {
+                Some(event @ (InputEvent::TurnLeft | InputEvent::TurnRight)) => {
+                    match press_gesture(event).and_then(|gesture| actions.action(gesture)) {
+                        Some(Action::TurnLeft) => Turn::Left,
+                        Some(Action::TurnRight) => Turn::Right,
+                        Some(Action::Pause | Action::Boost) => return Turn::None,
+                        None => {
+                            events.dequeue();
+                            continue;
+                        }
+                    }
+                },
+                Some(InputEvent::Head(direction)) => Turn::To(direction),
+                Some(InputEvent::Released) => {
+                    events.dequeue();
+                    continue;
+                },
+                // Taps on the pads are taken with `take_event`, so any left over are stale
+                #[cfg(feature = "pads")]
+                Some(InputEvent::Mute | InputEvent::Boost) => {
+                    events.dequeue();
+                    continue;
+                },
+                Some(InputEvent::Both) | None => return Turn::None
+            };
+            #[cfg_attr(not(feature = "latency"), allow(unused_variables))]
+            let queued = events.dequeue();
+            #[cfg(feature = "latency")]
+            if let Some(queued) = queued {
+                *TURN_MADE_AT.borrow(cs).borrow_mut() = Some(queued.at);
+            }
+            return turn;
+        }
+    })
+}
+
+/// Take the next turn to apply to the game, interpreting input events according to `scheme`.
+/// This should be called once per game step. With relative controls, several presses between two
+/// steps are applied over the following steps rather than all but the last being lost.
+pub fn next_turn(scheme: ControlScheme, heading: Direction) -> Turn {
+    input::next_turn(scheme, heading, next_press)
+}
+
+/// Steering by tilting the board, in place of the buttons. Tilts too slight to count (see
+/// [`tilt_direction`]) leave the snake heading the way it is.
+#[cfg(feature = "tilt")]
+pub(crate) struct TiltSteering {
+    /// Time since the accelerometer was last read.
+    since_sample_ms: u32,
+    /// Which way the board was tilted when it was last read.
+    direction: Option<Direction>,
+    /// Whether the direction hasn't been gathered since the last poll.
+    unread: bool
+}
+
+#[cfg(feature = "tilt")]
+impl TiltSteering {
+    pub(crate) fn new() -> Self {
+        // Read the accelerometer on the first poll
+        Self { since_sample_ms: TILT_SAMPLE_MS, direction: None, unread: false }
+    }
+
+    /// Count `elapsed_ms` milliseconds towards the next reading, reading the accelerometer if it
+    /// is due. This should be called every frame.
+    pub(crate) fn poll(&mut self, accelerometer: &mut Accelerometer, elapsed_ms: u32) {
+        self.since_sample_ms += elapsed_ms;
+        if self.since_sample_ms >= TILT_SAMPLE_MS {
+            self.since_sample_ms = 0;
+            self.direction = tilt_direction(accelerometer);
+        }
+        self.unread = true;
+    }
+}
+
+#[cfg(feature = "tilt")]
+impl InputSource for TiltSteering {
+    /// Head towards whichever way the board was last tilted, once per poll, so that the snake
+    /// keeps heading that way for as long as the board stays tilted.
+    fn next_event(&mut self) -> Option<InputEvent> {
+        if !core::mem::take(&mut self.unread) {
+            return None;
+        }
+        self.direction.map(InputEvent::Head)
+    }
+}
+
+/// The capacitive touch logo on the front of the board. It is sensed by discharging it and timing
+/// how long it takes to charge again through its pull-up resistor: a finger adds capacitance, so
+/// it charges more slowly. Nothing should touch the logo while it is being calibrated at startup.
+pub(crate) struct TouchLogo {
+    /// The logo's pin, which is missing while a reading is being taken, and for good if the logo
+    /// wasn't found.
+    pin: Option<Pin<Input<Floating>>>,
+    /// Charging time when untouched, in polling loop iterations.
+    baseline: u32,
+    /// Whether the logo was touched at the last poll.
+    touched: bool,
+    /// Whether the current touch has been used for something else, so shouldn't count as a tap.
+    tap_cancelled: bool
+}
+
+impl TouchLogo {
+    pub(crate) fn new(pin: P1_04<Disconnected>) -> Self {
+        let pin = pin.into_floating_input().degrade();
+        let mut logo = Self { pin: Some(pin), baseline: 0, touched: false, tap_cancelled: false };
+        let total: u32 = (0..LOGO_CALIBRATION_READINGS).map(|_| logo.charge_time()).sum();
+        logo.baseline = total / LOGO_CALIBRATION_READINGS;
+        if !logo.is_present() {
+            // Without the pad the pin never charges (or is already high), so nothing is read
+            logo.pin = None;
+        }
+        logo
+    }
+
+    /// Whether the logo charged up, but not instantly, while it was being calibrated. If it didn't,
+    /// the board has no logo (or it is damaged), and it never counts as touched.
+    pub(crate) fn is_present(&self) -> bool {
+        self.pin.is_some() && (1..LOGO_MAX_CHARGE_COUNT).contains(&self.baseline)
+    }
+
+    /// Discharge the logo and count how long it takes to charge again.
+    fn charge_time(&mut self) -> u32 {
+        let pin = match self.pin.take() {
+            Some(pin) => pin,
+            None => return 0
+        };
+        let pin = pin.into_push_pull_output(Level::Low);
+        // About a microsecond is plenty to discharge it
+        cortex_m::asm::delay(64);
+        let pin = pin.into_floating_input();
+        let mut count = 0;
+        while pin.is_low().unwrap() && count < LOGO_MAX_CHARGE_COUNT {
+            count += 1;
+        }
+        self.pin = Some(pin);
+        count
+    }
+
+    /// Take a reading, returning whether the logo has just been tapped: touched and then let go.
+    pub(crate) fn poll(&mut self) -> bool {
+        if self.pin.is_none() {
+            return false;
+        }
+        let touched = self.charge_time() * 100 > self.baseline * LOGO_TOUCH_PERCENT;
+        let tapped = self.touched && !touched && !self.tap_cancelled;
+        if !touched {
+            self.tap_cancelled = false;
+        }
+        self.touched = touched;
+        tapped
+    }
+
+    /// Whether the logo was being touched at the last reading.
+    pub(crate) fn is_touched(&self) -> bool {
+        self.touched
+    }
+
+    /// Stop the current touch from counting as a tap when it ends, as it has been used for
+    /// something else.
+    pub(crate) fn cancel_tap(&mut self) {
+        self.tap_cancelled = self.touched;
+    }
+}
+
+/// Return whether both buttons have been pressed together since this function was last called.
+/// Any events from before the chord are discarded along with it, as they were most likely made on
+/// the way to pressing both buttons.
+pub fn take_chord() -> bool {
+    free(|cs| {
+        let mut events = EVENTS.borrow(cs).borrow_mut();
+        if !events.iter().any(|queued| queued.event == InputEvent::Both) {
+            return false;
+        }
+        while events.dequeue().map(|queued| queued.event) != Some(InputEvent::Both) {}
+        true
+    })
+}
+
+/// Return the button that has been held down long enough to count as a long press, if either
+/// has. This should be called every frame, so that a long press is seen while the button is still
+/// held rather than once it is let go. Letting go of the button afterwards does nothing.
+pub(crate) fn take_long_press() -> Option<Button> {
+    free(long_press)
+}
+
+/// Take the oldest action made during play that isn't a turn (which are taken by [`next_turn`]),
+/// if any, as the action map (see [`set_action_map`]) says each gesture should make. Long presses
+/// are only looked for if `long_presses` is true. Like [`take_long_press`], this should be called
+/// every frame, and until it returns `None`. The logo isn't watched here, so its taps should be
+/// looked up in the action map by whatever polls it.
+///
+/// A chord is always taken, even if it makes no action, along with any events before it (see
+/// [`take_chord`]).
+pub(crate) fn take_action(long_presses: bool) -> Option<Action> {
+    free(|cs| {
+        let actions = *ACTIONS.borrow(cs).borrow();
+        if long_presses {
+            let gesture = match long_press(cs) {
+                Some(Button::A) => Some(Gesture::HoldA),
+                Some(Button::B) => Some(Gesture::HoldB),
+                None => None
+            };
+            if let Some(action) = gesture.and_then(|gesture| actions.action(gesture)) {
+                return Some(action);
+            }
+        }
+        let mut events = EVENTS.borrow(cs).borrow_mut();
+        loop {
+            let position = events.iter().position(|queued| match queued.event {
+                InputEvent::Both => true,
+                event => matches!(
+                    press_gesture(event).and_then(|gesture| actions.action(gesture)),
+                    Some(Action::Pause | Action::Boost)
+                )
+            })?;
+            let chord = events.iter().nth(position).map(|queued| queued.event)
+                == Some(InputEvent::Both);
+            // Taken from wherever it is in the queue, leaving the events around it in order
+            // (apart from those before a chord, which go with it)
+            let mut taken = None;
+            for i in 0..events.len() {
+                if let Some(queued) = events.dequeue() {
+                    if i == position {
+                        taken = Some(queued.event);
+                    } else if i > position || !chord {
+                        let _ = events.enqueue(queued);
+                    }
+                }
+            }
+            let gesture = match taken {
+                Some(InputEvent::Both) => Some(Gesture::Chord),
+                Some(event) => press_gesture(event),
+                None => None
+            };
+            if let Some(action) = gesture.and_then(|gesture| actions.action(gesture)) {
+                return Some(action);
+            }
+        }
+    })
+}
+
+/// Queue a turn for each button that has been held down long enough to keep turning the snake,
+/// for hold-to-turn. This should be called once per game step, before the step's turn is taken.
+/// Once a button has started turning like this, it makes no short or long press of its own, and
+/// letting go of it makes a [`InputEvent::Released`].
+pub(crate) fn repeat_held_turns() {
     free(|cs| {
-        let turn = *TURN.borrow(cs).borrow();
-        if reset {
-            *TURN.borrow(cs).borrow_mut() = Turn::None
+        let mut pressed_at = PRESSED_AT.borrow(cs).borrow_mut();
+        let mut repeating = REPEATING.borrow(cs).borrow_mut();
+        let turns = [InputEvent::TurnLeft, InputEvent::TurnRight];
+        for ((start, repeating), &turn) in
+            pressed_at.iter_mut().zip(repeating.iter_mut()).zip(turns.iter())
+        {
+            if matches!(*start, Some(at) if ms_since(at) >= HOLD_REPEAT_MS) {
+                *start = None;
+                *repeating = true;
+            }
+            if *repeating {
+                push_event(cs, turn, DWT::cycle_count());
+            }
         }
-        turn
     })
 }
 
+/// Catch up on any change to the buttons (or pads) that was ignored as bounce but has since
+/// settled, such as a button let go within [`DEBOUNCE_MS`] of being pressed. Its pin won't change
+/// again until it is next pressed, so otherwise the button would read as held down until then, and
+/// might make a long press or keep turning. This should be called every frame.
+pub(crate) fn settle_buttons() {
+    free(|cs| {
+        // A pin whose level differs from the last change accepted on it, once the window in which
+        // changes are ignored is over
+        let unsettled = |pin: &Pin<Input<Floating>>, held: bool, last: Option<u32>, window_ms| {
+            pin.is_low().unwrap() != held && !matches!(last, Some(at) if ms_since(at) < window_ms)
+        };
+        let buttons = BUTTONS.borrow(cs).borrow();
+        let held = HELD.borrow(cs).borrow();
+        let last_edge = LAST_EDGE.borrow(cs).borrow();
+        #[cfg_attr(not(feature = "pads"), allow(unused_mut))]
+        let mut pending = buttons.iter().flatten().zip(held.iter()).zip(last_edge.iter())
+            .any(|((pin, &held), &last)| unsettled(pin, held, last, DEBOUNCE_MS));
+        #[cfg(feature = "pads")]
+        {
+            let pads = PADS.borrow(cs).borrow();
+            let held = PAD_HELD.borrow(cs).borrow();
+            let last_edge = PAD_LAST_EDGE.borrow(cs).borrow();
+            pending |= pads.iter().flatten().zip(held.iter()).zip(last_edge.iter())
+                .any(|((pin, &held), &last)| unsettled(pin, held, last, PAD_DEBOUNCE_MS));
+        }
+        if pending {
+            // The interrupt handler reads every pin afresh, so takes the change as if just made
+            pac::NVIC::pend(pac::Interrupt::GPIOTE);
+        }
+    })
+}
+
+/// Milliseconds between the player making the last turn taken by [`next_turn`] and it being
+/// taken, if there has been a turn since this was last called. For a button press, this includes
+/// the time the button was held down for, as the turn isn't made until it is let go.
+#[cfg(feature = "latency")]
+pub(crate) fn take_turn_latency_ms() -> Option<u32> {
+    free(|cs| TURN_MADE_AT.borrow(cs).borrow_mut().take().map(ms_since))
+}
+
+/// Discard any turns that have not yet been applied, up to the first chord (if any).
+#[cfg(feature = "tilt")]
+pub(crate) fn discard_turns() {
+    while !matches!(next_press(), Turn::None) {}
+}
+
+/// Take the oldest `wanted` event that has not yet been handled, returning whether there was one.
+/// The events around it are left queued in order. This is for events that only matter at certain
+/// points, such as touches of the pads, which shouldn't be left to hold up the turns behind them.
+#[cfg(feature = "pads")]
+pub(crate) fn take_event(wanted: InputEvent) -> bool {
+    free(|cs| {
+        let mut events = EVENTS.borrow(cs).borrow_mut();
+        let mut found = false;
+        for _ in 0..events.len() {
+            if let Some(queued) = events.dequeue() {
+                if !found && queued.event == wanted {
+                    found = true;
+                } else {
+                    let _ = events.enqueue(queued);
+                }
+            }
+        }
+        found
+    })
+}
+
+/// Discard any input events that have not yet been handled.
+pub fn clear_events() {
+    free(|cs| {
+        let mut events = EVENTS.borrow(cs).borrow_mut();
+        while events.dequeue().is_some() {}
+    })
+}
+
+/// The gesture a press event is, if it is one.
+fn press_gesture(event: InputEvent) -> Option<Gesture> {
+    match event {
+        InputEvent::TurnLeft => Some(Gesture::PressA),
+        InputEvent::TurnRight => Some(Gesture::PressB),
+        _ => None
+    }
+}
+
+/// Take the button that has been held down long enough to count as a long press, if either has.
+/// See [`take_long_press`].
+fn long_press(cs: &CriticalSection) -> Option<Button> {
+    let mut pressed_at = PRESSED_AT.borrow(cs).borrow_mut();
+    let button = match *pressed_at {
+        [Some(start), _] if ms_since(start) >= LONG_PRESS_MS => Button::A,
+        [_, Some(start)] if ms_since(start) >= LONG_PRESS_MS => Button::B,
+        _ => return None
+    };
+    pressed_at[button as usize] = None;
+    Some(button)
+}
+
+/// Queue `event` to be handled, as made at cycle count `at`. If the queue is full the player is
+/// pressing faster than the game can keep up with, so the event is just dropped.
+#[cfg_attr(not(feature = "latency"), allow(unused_variables))]
+fn push_event(cs: &CriticalSection, event: InputEvent, at: u32) {
+    let queued = QueuedEvent {
+        event,
+        #[cfg(feature = "latency")]
+        at
+    };
+    let _ = EVENTS.borrow(cs).borrow_mut().enqueue(queued);
+}
+
+/// Whether a change on `button`'s pin should be acted on, rather than ignored as bounce from the
+/// last one that was. The time of each accepted change is recorded.
+fn accept_edge(cs: &CriticalSection, button: Button) -> bool {
+    debounce(&mut LAST_EDGE.borrow(cs).borrow_mut()[button as usize], DEBOUNCE_MS)
+}
+
+/// Whether a change is far enough from the last one accepted, at cycle count `last`, to be acted
+/// on rather than ignored as bounce. If it is, its time is recorded in `last`.
+fn debounce(last: &mut Option<u32>, debounce_ms: u32) -> bool {
+    if matches!(*last, Some(at) if ms_since(at) < debounce_ms) {
+        return false;
+    }
+    *last = Some(DWT::cycle_count());
+    true
+}
+
+/// Return whether `pin` is low, sensing it for the opposite level so that its next change fires the
+/// PORT event. The event only fires when the first sensed pin reaches the level it is sensed for,
+/// so leaving a pin sensed for the level it is at (such as a button that is still held down) would
+/// hide changes on every other pin.
+fn sense_next_change(port: &GpiotePort<'_>, pin: &Pin<Input<Floating>>) -> bool {
+    let low = pin.is_low().unwrap();
+    let sense = port.input_pin(pin);
+    if low {
+        sense.high();
+    } else {
+        sense.low();
+    }
+    low
+}
+
+/// Queue an event for each pad that has been touched since the last interrupt.
+#[cfg(feature = "pads")]
+fn pads_touched(cs: &CriticalSection, port: &GpiotePort<'_>) {
+    let pads = PADS.borrow(cs).borrow();
+    let mut held = PAD_HELD.borrow(cs).borrow_mut();
+    let mut last_edge = PAD_LAST_EDGE.borrow(cs).borrow_mut();
+    let pads = pads.iter().flatten().zip(held.iter_mut()).zip(last_edge.iter_mut());
+    for (((pad, held), last), &event) in pads.zip(PAD_EVENTS.iter()) {
+        let touched = sense_next_change(port, pad);
+        if touched != *held && debounce(last, PAD_DEBOUNCE_MS) {
+            *held = touched;
+            if touched {
+                push_event(cs, event, DWT::cycle_count());
+            }
+        }
+    }
+}
+
+/// Handle a button being pressed (if `held`) or let go, where `pressed_at` is when it was pressed,
+/// `repeating` is whether it has been turning at every step, `turned` is whether it turned when it
+/// was pressed and `turn` is the event a short press of it makes.
+fn button_changed(
+    cs: &CriticalSection,
+    held: bool,
+    pressed_at: &mut Option<u32>,
+    repeating: &mut bool,
+    turned: &mut bool,
+    turn: InputEvent
+) {
+    *repeating = false;
+    if held {
+        let now = DWT::cycle_count();
+        *pressed_at = Some(now);
+        *turned = *TURN_ON_PRESS.borrow(cs).borrow();
+        if *turned {
+            push_event(cs, turn, now);
+        }
+        return;
+    }
+    // Long presses are usually taken while the button is still held, but may not have been if
+    // nothing was checking for them
+    match pressed_at.take() {
+        Some(start) if ms_since(start) < LONG_PRESS_MS && !core::mem::take(turned) => {
+            push_event(cs, turn, start)
+        },
+        _ => push_event(cs, InputEvent::Released, DWT::cycle_count())
+    }
+}
+
 #[interrupt]
 fn GPIOTE() {
     // Enter a critical section here to satisfy the Mutex.
     free(|cs| {
         if let Some(gpiote) = GPIO.borrow(cs).borrow().as_ref() {
-            let a_pressed = gpiote.channel0().is_event_triggered();
-            let b_pressed = gpiote.channel1().is_event_triggered();
-
-            let turn = match (a_pressed, b_pressed) {
-                (false, false) => Turn::None,
-                (true, false) => Turn::Left,
-                (false, true) => Turn::Right,
-                (true, true) => Turn::None,
+            let port = gpiote.port();
+            // Cleared before the pins are read, so that a change after they are read fires the
+            // event again
+            port.reset_events();
+            #[cfg(feature = "pads")]
+            pads_touched(cs, &port);
+            let (a_held, b_held) = match BUTTONS.borrow(cs).borrow().as_ref() {
+                Some([a, b]) => (sense_next_change(&port, a), sense_next_change(&port, b)),
+                None => (false, false)
             };
+            let mut held = HELD.borrow(cs).borrow_mut();
+            let a_changed = a_held != held[0] && accept_edge(cs, Button::A);
+            let b_changed = b_held != held[1] && accept_edge(cs, Button::B);
+            if a_changed {
+                held[0] = a_held;
+            }
+            if b_changed {
+                held[1] = b_held;
+            }
+            // From here on, A and B are the buttons as the player sees them
+            let (a_changed, b_changed, a_held, b_held) = if *SWAPPED.borrow(cs).borrow() {
+                (b_changed, a_changed, b_held, a_held)
+            } else {
+                (a_changed, b_changed, a_held, b_held)
+            };
+            let a_pressed = a_changed && a_held;
+            let b_pressed = b_changed && b_held;
 
-            // Clear events
-            gpiote.channel0().reset_events();
-            gpiote.channel1().reset_events();
-
-            *TURN.borrow(cs).borrow_mut() = turn;
+            let mut pressed_at = PRESSED_AT.borrow(cs).borrow_mut();
+            let mut repeating = REPEATING.borrow(cs).borrow_mut();
+            let mut turned = TURNED_ON_PRESS.borrow(cs).borrow_mut();
+            if (a_pressed || b_pressed) && a_held && b_held {
+                // Pressing one button while the other is held down is a chord, not a turn
+                // (though the first button may already have turned, with turning on press)
+                push_event(cs, InputEvent::Both, DWT::cycle_count());
+                *pressed_at = [None; 2];
+                *repeating = [false; 2];
+                *turned = [false; 2];
+                return;
+            }
+            let [a_pressed_at, b_pressed_at] = &mut *pressed_at;
+            let [a_repeating, b_repeating] = &mut *repeating;
+            let [a_turned, b_turned] = &mut *turned;
+            if a_changed {
+                let turn = InputEvent::TurnLeft;
+                button_changed(cs, a_held, a_pressed_at, a_repeating, a_turned, turn);
+            }
+            if b_changed {
+                let turn = InputEvent::TurnRight;
+                button_changed(cs, b_held, b_pressed_at, b_repeating, b_turned, turn);
+            }
         }
     });
 }
\ No newline at end of file
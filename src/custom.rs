@@ -0,0 +1,133 @@
+//! Custom wall layouts, drawn by the player in the wall editor.
+
+#[cfg(feature = "tilt")]
+use microbit::display::nonblocking::GreyscaleImage;
+#[cfg(feature = "tilt")]
+use microbit::hal::timer::{Periodic, Timer};
+#[cfg(feature = "tilt")]
+use microbit::pac::TIMER0;
+#[cfg(feature = "tilt")]
+use snakebit_core::game::Direction;
+use snakebit_core::game::{Game, GameError, N_COLS, N_ROWS, START_ROW};
+
+#[cfg(feature = "tilt")]
+use crate::budget::within_budget;
+#[cfg(feature = "tilt")]
+use crate::control::{next_event, InputEvent};
+#[cfg(feature = "tilt")]
+use crate::display::{display_image, viewport};
+#[cfg(feature = "tilt")]
+use crate::hardware::Accelerometer;
+#[cfg(feature = "tilt")]
+use crate::serial::SerialPort;
+#[cfg(feature = "persist")]
+use crate::storage::{Record, Storage};
+#[cfg(feature = "tilt")]
+use crate::tilt::tilt_direction;
+#[cfg(feature = "tilt")]
+use crate::{wait_frame, FRAME_MS};
+
+/// Length of [`CustomWalls`] when serialized: a bit for each cell, rounded up to whole words, as
+/// flash is written a word at a time.
+#[cfg(feature = "persist")]
+pub(crate) const CUSTOM_WALLS_LEN: usize = (N_ROWS * N_COLS).div_ceil(32) * 4;
+/// Interval between moves of the editor's cursor while the board is held tilted, in milliseconds.
+#[cfg(feature = "tilt")]
+const CURSOR_MOVE_MS: u32 = 300;
+/// How long the editor's cursor spends on (and then off) when blinking, in milliseconds.
+#[cfg(feature = "tilt")]
+const CURSOR_BLINK_MS: u32 = 200;
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct CustomWalls {
+    /// Which cells are walls. Cells in the snake's starting row can never be walls.
+    layout: [[bool; N_COLS]; N_ROWS]
+}
+
+impl CustomWalls {
+    /// Create a game played on this layout.
+    pub(crate) fn game(&self, seed: u32) -> Result<Game, GameError> {
+        Game::with_walls(seed, &self.layout)
+    }
+
+    /// Let the player edit the layout. Tilting the board moves the cursor, A toggles a wall under
+    /// the cursor and B finishes. Where the grid is bigger than the LEDs, they show the part of it
+    /// around the cursor.
+    #[cfg(feature = "tilt")]
+    pub(crate) fn edit(
+        &mut self,
+        timer: &mut Timer<TIMER0, Periodic>,
+        serial: &mut SerialPort,
+        accelerometer: &mut Accelerometer
+    ) {
+        let (mut row, mut col) = (0, 0);
+        let mut elapsed_ms = 0;
+        let mut since_move_ms = 0;
+        timer.start(FRAME_MS * 1000);
+        loop {
+            let mut matrix = [[0u8; N_COLS]; N_ROWS];
+            for (matrix_row, layout_row) in matrix.iter_mut().zip(self.layout.iter()) {
+                for (cell, &wall) in matrix_row.iter_mut().zip(layout_row.iter()) {
+                    *cell = if wall { 5 } else { 0 };
+                }
+            }
+            // Show the starting row dimly, as walls can't be placed there
+            matrix[START_ROW] = [1; N_COLS];
+            if (elapsed_ms / CURSOR_BLINK_MS) % 2 == 0 {
+                matrix[row][col] = 9;
+            }
+            display_image(&GreyscaleImage::new(&viewport(&matrix, row, col)));
+            wait_frame(timer, serial);
+            elapsed_ms += FRAME_MS;
+            since_move_ms += FRAME_MS;
+
+            // Pressing both buttons together has no meaning here
+            match next_event() {
+                Some(InputEvent::TurnLeft) if row != START_ROW => {
+                    self.layout[row][col] = !self.layout[row][col]
+                },
+                Some(InputEvent::TurnRight) => return,
+                _ => ()
+            }
+
+            if since_move_ms >= CURSOR_MOVE_MS {
+                if let Some(direction) = within_budget("tilt", || tilt_direction(accelerometer)) {
+                    since_move_ms = 0;
+                    // The cursor wraps around the edges of the grid, like the snake
+                    match direction {
+                        Direction::Up => row = (row + N_ROWS - 1) % N_ROWS,
+                        Direction::Down => row = (row + 1) % N_ROWS,
+                        Direction::Left => col = (col + N_COLS - 1) % N_COLS,
+                        Direction::Right => col = (col + 1) % N_COLS
+                    }
+                    // Show the cursor straight away after moving it
+                    elapsed_ms = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "persist")]
+impl CustomWalls {
+    /// Load the custom layout from flash, or return an empty layout if none has been saved.
+    pub(crate) fn load(storage: &mut Storage) -> Self {
+        let mut bytes = [0u8; CUSTOM_WALLS_LEN];
+        let mut walls = Self::default();
+        if storage.read(Record::CustomWalls, &mut bytes) {
+            // One bit per cell, row by row, starting from the lowest bit of the first byte
+            for (i, cell) in walls.layout.iter_mut().flatten().enumerate() {
+                *cell = bytes[i / 8] & (1 << (i % 8)) != 0;
+            }
+        }
+        walls
+    }
+
+    pub(crate) fn save(&self, storage: &mut Storage) {
+        let mut bytes = [0u8; CUSTOM_WALLS_LEN];
+        for (i, &cell) in self.layout.iter().flatten().enumerate() {
+            bytes[i / 8] |= (cell as u8) << (i % 8);
+        }
+        storage.write(Record::CustomWalls, &bytes);
+    }
+}
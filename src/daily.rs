@@ -0,0 +1,67 @@
+//! The daily puzzle: a wall layout and food sequence generated from the date, so that everyone
+//! playing on the same day gets the same puzzle.
+
+use snakebit_core::game::{Game, GameError};
+
+use crate::clock::Date;
+#[cfg(feature = "persist")]
+use crate::storage::{Record, Storage};
+
+/// Length of [`DailyBest`] when serialized.
+#[cfg(feature = "persist")]
+const DAILY_BEST_LEN: usize = 8;
+
+/// Create the puzzle for the given date.
+pub(crate) fn daily_game(date: Date) -> Result<Game, GameError> {
+    // Scramble the day number (with the multiplier from Knuth's multiplicative hash) so that the
+    // puzzles for consecutive days aren't similar. The PRNG must not be seeded with zero.
+    let seed = date.day_number().wrapping_mul(2654435761);
+    Game::with_generated_walls(seed.max(1))
+}
+
+/// The best score achieved on the daily puzzle, for the most recent day it was played.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct DailyBest {
+    /// The day the score was achieved, as returned by `Date::day_number`.
+    day_number: u32,
+    score: u8
+}
+
+impl DailyBest {
+    /// Record a score achieved on the daily puzzle for `date`, returning the best score for that
+    /// day.
+    pub(crate) fn record(&mut self, date: Date, score: u8) -> u8 {
+        let day_number = date.day_number();
+        if day_number != self.day_number {
+            self.day_number = day_number;
+            self.score = 0;
+        }
+        self.score = self.score.max(score);
+        self.score
+    }
+}
+
+#[cfg(feature = "persist")]
+impl DailyBest {
+    /// Load the daily best score from flash, or return an empty record if none has been saved.
+    pub(crate) fn load(storage: &mut Storage) -> Self {
+        let mut bytes = [0u8; DAILY_BEST_LEN];
+        if storage.read(Record::DailyBest, &mut bytes) {
+            let mut word = [0u8; 4];
+            word.copy_from_slice(&bytes[0..4]);
+            Self {
+                day_number: u32::from_le_bytes(word),
+                score: bytes[4]
+            }
+        } else {
+            Self::default()
+        }
+    }
+
+    pub(crate) fn save(&self, storage: &mut Storage) {
+        let mut bytes = [0u8; DAILY_BEST_LEN];
+        bytes[0..4].copy_from_slice(&self.day_number.to_le_bytes());
+        bytes[4] = self.score;
+        storage.write(Record::DailyBest, &bytes);
+    }
+}
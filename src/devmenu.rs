@@ -0,0 +1,134 @@
+//! A hidden menu of settings for testing on hardware, unlocked with a cheat code (see
+//! [`crate::cheats`]). None of its settings are saved.
+//!
+//! In the menu, A moves on to the next item and B changes it. Settings are shown brightly while on
+//! and dimly while off. Choosing one of the forced outcomes at the end of the menu leaves it, and
+//! the outcome happens at the game's next step. Pressing both buttons together leaves the menu.
+//!
+//! The same outcomes can be forced over serial, which can also move the food (see
+//! [`crate::serial`]).
+
+use microbit::display::nonblocking::GreyscaleImage;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::TIMER0;
+
+use crate::control::{clear_events, next_event, InputEvent};
+use crate::display::display_image;
+use crate::icons::{self, Icon};
+use crate::serial::SerialPort;
+use crate::{wait_frame, FRAME_MS};
+
+/// Settings for testing, which all start off.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct DevSettings {
+    /// Log the state of the game over RTT after every step.
+    pub(crate) verbose: bool,
+    /// Check the game's invariants after every step, as debug builds always do.
+    pub(crate) check_invariants: bool,
+    /// Log every frame shown during play over RTT, as rows of brightness digits.
+    pub(crate) capture_frames: bool,
+    /// Record the turns applied at each step of the next games, and log the recording over RTT
+    /// when a game ends or a marker is logged, so that the game can be played back.
+    pub(crate) record_inputs: bool,
+    /// What happens at the next step, in place of what would have happened.
+    pub(crate) next_outcome: Option<ForcedOutcome>
+}
+
+/// An outcome that can be forced at the next step, to try out what follows it without playing up
+/// to it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum ForcedOutcome {
+    /// The snake eats food, which is moved into its path if it can be.
+    Eat,
+    /// The snake crashes, losing the game. The game ends as a crash would end it (including
+    /// recording it).
+    Collision,
+    /// The game is won, likewise.
+    Win
+}
+
+/// The items in the menu, in order.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Item {
+    Verbose,
+    CheckInvariants,
+    CaptureFrames,
+    RecordInputs,
+    Force(ForcedOutcome)
+}
+
+const ITEMS: [Item; 7] = [
+    Item::Verbose,
+    Item::CheckInvariants,
+    Item::CaptureFrames,
+    Item::RecordInputs,
+    Item::Force(ForcedOutcome::Eat),
+    Item::Force(ForcedOutcome::Collision),
+    Item::Force(ForcedOutcome::Win)
+];
+
+impl Item {
+    fn icon(self) -> Icon {
+        match self {
+            Item::Verbose => icons::LOG,
+            Item::CheckInvariants => icons::TICK,
+            Item::CaptureFrames => icons::FRAME,
+            Item::RecordInputs => icons::RECORD,
+            Item::Force(ForcedOutcome::Eat) => icons::FOOD,
+            Item::Force(ForcedOutcome::Collision) => icons::CROSS,
+            Item::Force(ForcedOutcome::Win) => icons::STAR
+        }
+    }
+
+    /// Whether the item is a setting that is on. Actions always count as on, so are shown brightly.
+    fn is_on(self, settings: &DevSettings) -> bool {
+        match self {
+            Item::Verbose => settings.verbose,
+            Item::CheckInvariants => settings.check_invariants,
+            Item::CaptureFrames => settings.capture_frames,
+            Item::RecordInputs => settings.record_inputs,
+            Item::Force(_) => true
+        }
+    }
+}
+
+/// Show the menu until the player leaves it. Returns whether they chose an outcome to force, in
+/// which case the game should resume straight away.
+pub(crate) fn dev_menu(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    settings: &mut DevSettings
+) -> bool {
+    let mut index = 0;
+    clear_events();
+    timer.start(FRAME_MS * 1000);
+    loop {
+        let item = ITEMS[index];
+        let brightness = if item.is_on(settings) { 9 } else { 2 };
+        let mut matrix = item.icon();
+        for cell in matrix.iter_mut().flatten() {
+            *cell *= brightness;
+        }
+        display_image(&GreyscaleImage::new(&matrix));
+        wait_frame(timer, serial);
+        match next_event() {
+            Some(InputEvent::TurnLeft) => index = (index + 1) % ITEMS.len(),
+            Some(InputEvent::TurnRight) => match item {
+                Item::Verbose => settings.verbose = !settings.verbose,
+                Item::CheckInvariants => settings.check_invariants = !settings.check_invariants,
+                Item::CaptureFrames => settings.capture_frames = !settings.capture_frames,
+                Item::RecordInputs => settings.record_inputs = !settings.record_inputs,
+                Item::Force(outcome) => {
+                    settings.next_outcome = Some(outcome);
+                    clear_events();
+                    return true;
+                }
+            },
+            Some(InputEvent::Both) => {
+                clear_events();
+                return false;
+            },
+            _ => ()
+        }
+    }
+}
@@ -1,12 +1,129 @@
 use core::cell::RefCell;
+#[cfg(feature = "fonts")]
+use core::fmt::Write;
 use cortex_m::interrupt::{free, Mutex};
-use microbit::display::nonblocking::Display;
+#[cfg(feature = "fonts")]
+use heapless::String;
+use microbit::display::nonblocking::{Display, GreyscaleImage};
 use microbit::gpio::DisplayPins;
+use microbit::hal::prelude::*;
+use microbit::hal::timer::{Periodic, Timer};
 use microbit::pac;
-use microbit::pac::{interrupt, TIMER1};
+use microbit::pac::{interrupt, TIMER0, TIMER1};
+use snakebit_core::game::{blend_matrix, count_matrix, Direction, N_COLS, N_ROWS};
 use tiny_led_matrix::Render;
 
+#[cfg(feature = "fonts")]
+use crate::font::scroll_text;
+use crate::FRAME_MS;
+
+/// Number of rows of LEDs on the board.
+pub(crate) const LED_ROWS: usize = 5;
+/// Number of columns of LEDs on the board.
+pub(crate) const LED_COLS: usize = 5;
+/// Brightness of every lit LED in high contrast mode.
+const FULL_BRIGHTNESS: u8 = 9;
+/// How much brighter things are drawn while boosted.
+const BOOST: u8 = 3;
+/// How long a count is shown for as lit LEDs, in milliseconds.
+const COUNT_MS: u32 = 2000;
+/// Number of brightness levels the display can be set to. The highest is full brightness.
+pub(crate) const BRIGHTNESS_LEVELS: u8 = 3;
+/// Number of ways round the board can be held, a quarter turn apart.
+pub(crate) const ROTATIONS: u8 = 4;
+/// How long a [`Transition`] from one screen to the next takes, in milliseconds.
+const TRANSITION_MS: u32 = 200;
+
 static DISPLAY: Mutex<RefCell<Option<Display<TIMER1>>>> = Mutex::new(RefCell::new(None));
+static BRIGHTNESS: Mutex<RefCell<u8>> = Mutex::new(RefCell::new(BRIGHTNESS_LEVELS));
+/// How many quarter turns clockwise the board is held from upright (see [`set_rotation`]).
+static ROTATION: Mutex<RefCell<u8>> = Mutex::new(RefCell::new(0));
+/// The brightness of each LED in the image last displayed (before dimming), for transitions to
+/// start from.
+static SHOWN: Mutex<RefCell<[[u8; LED_COLS]; LED_ROWS]>> =
+    Mutex::new(RefCell::new([[0; LED_COLS]; LED_ROWS]));
+
+// The LEDs show part of the grid at a time (see [`viewport`]), so the grid can't be any smaller
+const _: () = assert!(N_ROWS >= LED_ROWS && N_COLS >= LED_COLS);
+
+/// How one screen gives way to the next, rather than the next just appearing.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Transition {
+    /// Every LED fades from its old brightness to its new one.
+    Fade,
+    /// The new screen sweeps across the old one from left to right, a column at a time.
+    Wipe
+}
+
+/// Builds a frame of the game out of layers, such as the walls, the snake, the food and effects
+/// over them, each drawn over the ones before it. A layer only covers the tiles it lights, so each
+/// can be drawn on its own without knowing what is under it.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Compositor {
+    frame: [[u8; N_COLS]; N_ROWS]
+}
+
+impl Compositor {
+    /// Start with every tile off.
+    pub(crate) fn new() -> Self {
+        Self { frame: [[0; N_COLS]; N_ROWS] }
+    }
+
+    /// Draw `layer` over the frame so far.
+    pub(crate) fn layer(mut self, layer: &[[u8; N_COLS]; N_ROWS]) -> Self {
+        for (row, layer_row) in self.frame.iter_mut().zip(layer.iter()) {
+            for (value, &layer_value) in row.iter_mut().zip(layer_row.iter()) {
+                if layer_value > 0 {
+                    *value = layer_value;
+                }
+            }
+        }
+        self
+    }
+
+    /// Draw a single tile over the frame so far, eg to flash it.
+    pub(crate) fn tile(mut self, row: usize, col: usize, brightness: u8) -> Self {
+        self.frame[row][col] = brightness;
+        self
+    }
+
+    /// The finished frame.
+    pub(crate) fn frame(self) -> [[u8; N_COLS]; N_ROWS] {
+        self.frame
+    }
+}
+
+/// An image with every lit LED dimmed to the display's brightness level, though never so far that
+/// it goes out.
+struct Dimmed<'a, R> {
+    image: &'a R,
+    level: u8
+}
+
+impl<R: Render> Render for Dimmed<'_, R> {
+    fn brightness_at(&self, x: usize, y: usize) -> u8 {
+        let brightness = self.image.brightness_at(x, y) as u32;
+        let (level, levels) = (self.level as u32, BRIGHTNESS_LEVELS as u32);
+        ((brightness * level + levels - 1) / levels) as u8
+    }
+}
+
+/// An image turned by a number of quarter turns anticlockwise, so that it looks upright on a board
+/// turned that far clockwise.
+struct Turned<'a, R> {
+    image: &'a R,
+    quarter_turns: u8
+}
+
+impl<R: Render> Render for Turned<'_, R> {
+    fn brightness_at(&self, x: usize, y: usize) -> u8 {
+        let (mut x, mut y) = (x, y);
+        for _ in 0..self.quarter_turns {
+            (x, y) = (LED_COLS - 1 - y, x);
+        }
+        self.image.brightness_at(x, y)
+    }
+}
 
 pub(crate) fn init_display(board_timer: TIMER1, board_display: DisplayPins) {
     let display = Display::new(board_timer, board_display);
@@ -19,17 +136,164 @@ pub(crate) fn init_display(board_timer: TIMER1, board_display: DisplayPins) {
     }
 }
 
-/// Display an image.
+/// Set how bright everything is drawn, from 1 (dimmest) to [`BRIGHTNESS_LEVELS`] (full
+/// brightness). Only images displayed after this is called are affected.
+pub(crate) fn set_brightness(level: u8) {
+    free(|cs| *BRIGHTNESS.borrow(cs).borrow_mut() = level.clamp(1, BRIGHTNESS_LEVELS));
+}
+
+/// Set how many quarter turns clockwise (from 0 to 3) the board is held from upright, with the USB
+/// socket at the top. Everything displayed afterwards is turned to look upright that way round,
+/// and [`upright`] turns directions read from the board's sensors to match.
+pub(crate) fn set_rotation(quarter_turns: u8) {
+    free(|cs| *ROTATION.borrow(cs).borrow_mut() = quarter_turns % ROTATIONS);
+}
+
+/// The direction on the display that `direction`, read from the board with the USB socket taken
+/// as up (by tilting, say), points in as the board is held (see [`set_rotation`]).
+pub(crate) fn upright(direction: Direction) -> Direction {
+    let quarter_turns = free(|cs| *ROTATION.borrow(cs).borrow());
+    (0..quarter_turns).fold(direction, |direction, _| direction.right())
+}
+
+/// Display an image, dimmed to the brightness level (see [`set_brightness`]) and turned to the way
+/// the board is held (see [`set_rotation`]).
 pub(crate) fn display_image(image: &impl Render) {
     free(|cs| {
+        let mut shown = SHOWN.borrow(cs).borrow_mut();
+        for (y, row) in shown.iter_mut().enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
+                *value = image.brightness_at(x, y);
+            }
+        }
+        let level = *BRIGHTNESS.borrow(cs).borrow();
+        let quarter_turns = *ROTATION.borrow(cs).borrow();
         if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
-            display.show(image);
+            display.show(&Dimmed { image: &Turned { image, quarter_turns }, level });
         }
     })
 }
 
+/// Display an image for the given number of milliseconds, then clear the display and pause briefly
+/// so that consecutive images are distinguishable.
+pub(crate) fn flash_image(timer: &mut Timer<TIMER0, Periodic>, image: &impl Render, duration_ms: u32) {
+    display_image(image);
+    timer.delay_ms(duration_ms);
+    clear_display();
+    timer.delay_ms(200u32);
+}
+
+/// Show `count` as that many lit LEDs for a couple of seconds, wiped across whatever was shown
+/// before, calling `wait` with the number of milliseconds to wait for. With the `fonts` feature,
+/// counts too large to show that way are scrolled across the display as text instead, after
+/// `label`, once what was shown before has faded out. Returns false if `wait` did, to stop early.
+#[cfg_attr(not(feature = "fonts"), allow(unused_variables))]
+pub(crate) fn show_count(label: &str, count: usize, mut wait: impl FnMut(u32) -> bool) -> bool {
+    #[cfg(feature = "fonts")]
+    if count > LED_ROWS * LED_COLS {
+        let mut text: String<16> = String::new();
+        let _ = write!(text, "{} {}", label, count);
+        return transition(&[[0; LED_COLS]; LED_ROWS], Transition::Fade, &mut wait)
+            && scroll_text(text.as_bytes(), wait);
+    }
+    let mut matrix: [[u8; LED_COLS]; LED_ROWS] = count_matrix(count);
+    for value in matrix.iter_mut().flatten() {
+        *value *= FULL_BRIGHTNESS;
+    }
+    transition(&matrix, Transition::Wipe, &mut wait) && wait(COUNT_MS)
+}
+
+/// `brightness`, raised (up to full brightness) if `boosted`, to draw attention to something for a
+/// moment.
+pub(crate) fn boost(brightness: u8, boosted: bool) -> u8 {
+    if boosted {
+        (brightness + BOOST).min(FULL_BRIGHTNESS)
+    } else {
+        brightness
+    }
+}
+
+/// The part of `frame`, a frame of the game, that fits on the LEDs: the tiles around the one at
+/// `row` and `col` (the snake's head, say), as near centred on it as the edges of the grid allow.
+/// When the grid is no bigger than the LEDs, this is the whole frame.
+pub(crate) fn viewport(
+    frame: &[[u8; N_COLS]; N_ROWS],
+    row: usize,
+    col: usize
+) -> [[u8; LED_COLS]; LED_ROWS] {
+    let (top, left) = view_origin(row, col);
+    let mut shown = [[0; LED_COLS]; LED_ROWS];
+    for (shown_row, frame_row) in shown.iter_mut().zip(frame[top..].iter()) {
+        shown_row.copy_from_slice(&frame_row[left..left + LED_COLS]);
+    }
+    shown
+}
+
+/// Whether the tile at `row` and `col` is shown on the LEDs while they show the part of the grid
+/// around the tile at `focus` (see [`viewport`]).
+pub(crate) fn in_view(row: usize, col: usize, focus: (usize, usize)) -> bool {
+    let (top, left) = view_origin(focus.0, focus.1);
+    (top..top + LED_ROWS).contains(&row) && (left..left + LED_COLS).contains(&col)
+}
+
+/// The top left tile of the part of the grid shown on the LEDs around the tile at `row` and `col`.
+fn view_origin(row: usize, col: usize) -> (usize, usize) {
+    let top = row.saturating_sub(LED_ROWS / 2).min(N_ROWS - LED_ROWS);
+    let left = col.saturating_sub(LED_COLS / 2).min(N_COLS - LED_COLS);
+    (top, left)
+}
+
+/// Reduce `matrix` to just two brightness levels, off and full, for players who can't tell the
+/// levels in between apart on the red LEDs. LEDs where `blinking` is true (the food, say) are lit
+/// only if `blink_on`, so that they still stand out; all other lit LEDs are shown at full
+/// brightness.
+pub(crate) fn high_contrast(
+    matrix: &mut [[u8; N_COLS]; N_ROWS],
+    blinking: &[[bool; N_COLS]; N_ROWS],
+    blink_on: bool
+) {
+    for (value, &blinks) in matrix.iter_mut().flatten().zip(blinking.iter().flatten()) {
+        *value = match *value {
+            0 => 0,
+            _ if blinks && !blink_on => 0,
+            _ => FULL_BRIGHTNESS
+        };
+    }
+}
+
+/// Change from whatever is displayed to `to` by way of `transition`, calling `wait` with the number
+/// of milliseconds to wait for between each frame of it. Returns false if `wait` did, leaving the
+/// transition part way through.
+pub(crate) fn transition(
+    to: &[[u8; LED_COLS]; LED_ROWS],
+    transition: Transition,
+    mut wait: impl FnMut(u32) -> bool
+) -> bool {
+    let from = free(|cs| *SHOWN.borrow(cs).borrow());
+    let frames = TRANSITION_MS / FRAME_MS;
+    for frame in 1..=frames {
+        let matrix = match transition {
+            Transition::Fade => blend_matrix(&from, to, frame, frames),
+            Transition::Wipe => {
+                let edge = frame as usize * LED_COLS / frames as usize;
+                let mut matrix = from;
+                for (row, to_row) in matrix.iter_mut().zip(to.iter()) {
+                    row[..edge].copy_from_slice(&to_row[..edge]);
+                }
+                matrix
+            }
+        };
+        display_image(&GreyscaleImage::new(&matrix));
+        if !wait(FRAME_MS) {
+            return false;
+        }
+    }
+    true
+}
+
 pub(crate) fn clear_display() {
     free(|cs| {
+        *SHOWN.borrow(cs).borrow_mut() = [[0; LED_COLS]; LED_ROWS];
         if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
             display.clear();
         }
@@ -0,0 +1,36 @@
+//! Seeds for new games. The hardware RNG should be enough on its own, but its output is mixed with
+//! readings from the temperature sensor and the free-running cycle counter taken at boot, so that
+//! food placement still varies from game to game if the RNG peripheral misbehaves.
+
+use cortex_m::peripheral::DWT;
+use microbit::hal::{Rng, Temp};
+use microbit::pac::TEMP;
+use snakebit_core::seed::{MixedSeeds, SeedSource};
+
+/// Number of temperature readings mixed in at boot. Each one takes around 36us, and the time it
+/// takes (as seen by the cycle counter) varies as well as the reading itself.
+const TEMP_READINGS: usize = 4;
+
+/// The hardware RNG peripheral, as a source of seeds.
+pub(crate) struct HardwareRng(Rng);
+
+impl SeedSource for HardwareRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.random_u32()
+    }
+}
+
+/// Where new games get their seeds from.
+pub(crate) type Seeds = MixedSeeds<HardwareRng>;
+
+/// Seeds from the hardware RNG, conditioned with readings from the temperature sensor and the
+/// cycle counter (which must already be running).
+pub(crate) fn boot_seeds(rng: Rng, temp: TEMP) -> Seeds {
+    let mut seeds = MixedSeeds::new(HardwareRng(rng));
+    let mut temp = Temp::new(temp);
+    for _ in 0..TEMP_READINGS {
+        seeds.mix(temp.measure().to_bits() as u32);
+        seeds.mix(DWT::cycle_count());
+    }
+    seeds
+}
@@ -0,0 +1,64 @@
+//! A screen for entering a short piece of text one character at a time: A cycles through the
+//! characters allowed at the current position, B moves on to the next position (going back to
+//! the first after the last) and holding A confirms the text. Pressing both buttons together
+//! cancels.
+
+use microbit::display::nonblocking::BitImage;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::TIMER0;
+use snakebit_core::game::count_matrix;
+
+use crate::control::{clear_events, next_event, take_long_press, Button, InputEvent};
+use crate::display::display_image;
+use crate::font::glyph;
+use crate::serial::SerialPort;
+use crate::{wait_frame, FRAME_MS};
+
+/// How long the position is shown for after moving to it, in milliseconds.
+const POSITION_MS: u32 = 400;
+
+/// Let the player enter `N` characters, each one of `chars`, which must all have glyphs. Returns
+/// the text entered, or `None` if the player cancelled.
+pub(crate) fn enter_text<const N: usize>(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    chars: &[u8]
+) -> Option<[u8; N]> {
+    // The index into `chars` chosen at each position
+    let mut chosen = [0usize; N];
+    let mut pos = 0;
+    // Time since moving to the current position, so that it starts off showing the position
+    let mut since_move_ms = 0;
+    clear_events();
+    timer.start(FRAME_MS * 1000);
+    loop {
+        if since_move_ms < POSITION_MS {
+            display_image(&BitImage::new(&count_matrix(pos + 1)));
+        } else {
+            let icon = glyph(chars[chosen[pos]]).unwrap_or([[0; 5]; 5]);
+            display_image(&BitImage::new(&icon));
+        }
+        wait_frame(timer, serial);
+        since_move_ms += FRAME_MS;
+        if take_long_press() == Some(Button::A) {
+            let mut text = [0u8; N];
+            for (c, &i) in text.iter_mut().zip(chosen.iter()) {
+                *c = chars[i];
+            }
+            return Some(text);
+        }
+        match next_event() {
+            Some(InputEvent::TurnLeft) => {
+                chosen[pos] = (chosen[pos] + 1) % chars.len();
+                // Show the new character straight away, even if the position was still showing
+                since_move_ms = POSITION_MS;
+            },
+            Some(InputEvent::TurnRight) => {
+                pos = (pos + 1) % N;
+                since_move_ms = 0;
+            },
+            Some(InputEvent::Both) => return None,
+            _ => ()
+        }
+    }
+}
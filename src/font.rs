@@ -0,0 +1,153 @@
+//! A 5x5 font covering the digits and capital letters, for showing one character at a time or
+//! scrolling text across the display.
+
+use microbit::display::nonblocking::BitImage;
+
+use crate::display::display_image;
+use crate::icons::Icon;
+
+/// Time between each step of scrolling text one column to the left, in milliseconds.
+const SCROLL_COLUMN_MS: u32 = 100;
+/// Width of a space (or any other character without a glyph), in columns.
+const SPACE_WIDTH: usize = 2;
+
+/// Glyphs for `0`-`9` then `A`-`Z`, one row per byte from top to bottom, with the leftmost column
+/// in bit 4. Zero is slashed to tell it apart from O.
+const GLYPHS: [[u8; 5]; 36] = [
+    // 0
+    [0b01110, 0b10011, 0b10101, 0b11001, 0b01110],
+    // 1
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b01110],
+    // 2
+    [0b11100, 0b00010, 0b01100, 0b10000, 0b11110],
+    // 3
+    [0b11110, 0b00010, 0b00100, 0b10010, 0b01100],
+    // 4
+    [0b00110, 0b01010, 0b10010, 0b11111, 0b00010],
+    // 5
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b11110],
+    // 6
+    [0b00010, 0b00100, 0b01110, 0b10001, 0b01110],
+    // 7
+    [0b11111, 0b00010, 0b00100, 0b01000, 0b10000],
+    // 8
+    [0b01110, 0b10001, 0b01110, 0b10001, 0b01110],
+    // 9
+    [0b01110, 0b10001, 0b01110, 0b00100, 0b01000],
+    // A
+    [0b01100, 0b10010, 0b11110, 0b10010, 0b10010],
+    // B
+    [0b11100, 0b10010, 0b11100, 0b10010, 0b11100],
+    // C
+    [0b01110, 0b10000, 0b10000, 0b10000, 0b01110],
+    // D
+    [0b11100, 0b10010, 0b10010, 0b10010, 0b11100],
+    // E
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b11110],
+    // F
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b10000],
+    // G
+    [0b01110, 0b10000, 0b10011, 0b10001, 0b01110],
+    // H
+    [0b10010, 0b10010, 0b11110, 0b10010, 0b10010],
+    // I
+    [0b11100, 0b01000, 0b01000, 0b01000, 0b11100],
+    // J
+    [0b11111, 0b00010, 0b00010, 0b10010, 0b01100],
+    // K
+    [0b10010, 0b10100, 0b11000, 0b10100, 0b10010],
+    // L
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b11110],
+    // M
+    [0b10001, 0b11011, 0b10101, 0b10001, 0b10001],
+    // N
+    [0b10001, 0b11001, 0b10101, 0b10011, 0b10001],
+    // O
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b01110],
+    // P
+    [0b11100, 0b10010, 0b11100, 0b10000, 0b10000],
+    // Q
+    [0b01100, 0b10010, 0b10010, 0b01100, 0b00110],
+    // R
+    [0b11100, 0b10010, 0b11100, 0b10010, 0b10001],
+    // S
+    [0b01110, 0b10000, 0b01100, 0b00010, 0b11100],
+    // T
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100],
+    // U
+    [0b10010, 0b10010, 0b10010, 0b10010, 0b01100],
+    // V
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+    // W
+    [0b10001, 0b10001, 0b10101, 0b11011, 0b10001],
+    // X
+    [0b10010, 0b10010, 0b01100, 0b10010, 0b10010],
+    // Y
+    [0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+    // Z
+    [0b11110, 0b00100, 0b01000, 0b10000, 0b11110],
+];
+
+/// The glyph for a digit or letter (in either case), as an icon, or `None` for any other
+/// character.
+pub(crate) fn glyph(c: u8) -> Option<Icon> {
+    let index = match c.to_ascii_uppercase() {
+        c @ b'0'..=b'9' => c - b'0',
+        c @ b'A'..=b'Z' => c - b'A' + 10,
+        _ => return None
+    };
+    let mut icon = [[0u8; 5]; 5];
+    for (icon_row, bits) in icon.iter_mut().zip(GLYPHS[index as usize].iter()) {
+        for (col, cell) in icon_row.iter_mut().enumerate() {
+            *cell = (bits >> (4 - col)) & 1;
+        }
+    }
+    Some(icon)
+}
+
+/// Scroll `text` across the display from right to left until it has scrolled off, calling `wait`
+/// with the number of milliseconds to wait for between each step. If `wait` returns false,
+/// scrolling stops there and this returns false too.
+pub(crate) fn scroll_text(text: &[u8], mut wait: impl FnMut(u32) -> bool) -> bool {
+    let mut shown = [[0u8; 5]; 5];
+    // Blank columns at the end scroll the last character off
+    let columns = text
+        .iter()
+        .flat_map(|&c| glyph_columns(c))
+        .chain(core::iter::repeat([0; 5]).take(5));
+    for column in columns {
+        for (row, &cell) in shown.iter_mut().zip(column.iter()) {
+            row.rotate_left(1);
+            row[4] = cell;
+        }
+        display_image(&BitImage::new(&shown));
+        if !wait(SCROLL_COLUMN_MS) {
+            return false;
+        }
+    }
+    true
+}
+
+/// The columns of the glyph for `c`, from left to right, followed by a blank column to separate it
+/// from the next character. Blank columns on the glyph's right are left out, so that narrow
+/// characters take up less room.
+fn glyph_columns(c: u8) -> impl Iterator<Item = [u8; 5]> {
+    let icon = glyph(c);
+    let width = match icon {
+        Some(icon) => (0..5)
+            .rev()
+            .find(|&col| icon.iter().any(|row| row[col] != 0))
+            .map_or(0, |col| col + 1),
+        None => SPACE_WIDTH
+    };
+    let icon = icon.unwrap_or([[0; 5]; 5]);
+    (0..=width).map(move |col| {
+        let mut column = [0u8; 5];
+        if col < width {
+            for (cell, row) in column.iter_mut().zip(icon.iter()) {
+                *cell = row[col];
+            }
+        }
+        column
+    })
+}
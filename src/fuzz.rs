@@ -0,0 +1,87 @@
+//! A debug mode that plays a large number of games with pseudo-random inputs as fast as possible,
+//! checking the game's invariants after every step and reporting any failures over RTT. Panics
+//! (eg, from a heapless collection filling up) are also reported over RTT, by the panic handler.
+
+use rtt_target::rprintln;
+use snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN;
+use snakebit_core::game::{Direction, Game, GameError, GameStatus, Turn};
+
+/// Number of games to play.
+const FUZZ_GAMES: u32 = 1_000_000;
+/// Maximum number of steps to play in each game, in case random inputs keep a game going forever.
+const MAX_STEPS: u32 = 1000;
+/// Number of games between progress reports.
+const REPORT_INTERVAL: u32 = 10_000;
+
+/// Generates pseudo-random turns, using a xorshift PRNG.
+struct Inputs(u32);
+
+impl Inputs {
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    fn next_turn(&mut self) -> Turn {
+        match self.next_u32() % 7 {
+            0 => Turn::Left,
+            1 => Turn::Right,
+            2 => Turn::To(Direction::Up),
+            3 => Turn::To(Direction::Down),
+            4 => Turn::To(Direction::Left),
+            5 => Turn::To(Direction::Right),
+            _ => Turn::None
+        }
+    }
+}
+
+/// Play `FUZZ_GAMES` games, alternating between games with and without walls, then report how many
+/// steps were played and how many invariant failures were found.
+pub(crate) fn run_fuzz(seed: u32) {
+    rprintln!("Fuzzing {} games from seed {:#010x}", FUZZ_GAMES, seed);
+    // The PRNG must not be seeded with zero
+    let mut inputs = Inputs(seed.max(1));
+    let mut steps: u64 = 0;
+    let mut failures: u32 = 0;
+    for i in 0..FUZZ_GAMES {
+        let game_seed = inputs.next_u32().max(1);
+        let created = if i % 2 == 0 {
+            Game::new(game_seed)
+        } else {
+            Game::with_generated_walls(game_seed)
+        };
+        let mut game = match created {
+            Ok(game) => game,
+            Err(e) => {
+                failures += 1;
+                rprintln!("Game {} (seed {:#010x}) failed to start: {:?}", i, game_seed, e);
+                continue;
+            }
+        };
+        for step in 0..MAX_STEPS {
+            if game.status != GameStatus::Ongoing {
+                break;
+            }
+            let result = game.step(inputs.next_turn())
+                .and_then(|_| game.check_invariants().map_err(GameError::InvariantViolated));
+            steps += 1;
+            if let Err(e) = result {
+                failures += 1;
+                let mut snapshot = [0u8; MAX_SNAPSHOT_LEN];
+                rprintln!("Game {} (seed {:#010x}) failed at step {}: {:?}", i, game_seed, step, e);
+                if let Ok(len) = game.serialize(&mut snapshot) {
+                    rprintln!("Game state: {:02x?}", &snapshot[..len]);
+                }
+                break;
+            }
+        }
+        if (i + 1) % REPORT_INTERVAL == 0 {
+            rprintln!("{} games, {} steps, {} failures", i + 1, steps, failures);
+        }
+    }
+    rprintln!("Fuzzing finished: {} steps, {} failures", steps, failures);
+}
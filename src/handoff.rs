@@ -0,0 +1,63 @@
+//! Handing off a game in progress to another board, so that it can be continued there exactly
+//! where it left off.
+//!
+//! An offer is the game's rules (see [`ModeRules::to_bytes`]) followed by its snapshot, so that
+//! the game is carried on in the same mode, at the same speed and with the same scoring.
+
+use snakebit_core::game::Game;
+use snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN;
+
+use crate::radio::{PacketKind, RadioLink, MAX_PAYLOAD_LEN};
+use crate::rules::{ModeGame, ModeRules, MODE_RULES_LEN};
+
+/// Maximum length of an offer.
+const MAX_OFFER_LEN: usize = MODE_RULES_LEN + MAX_SNAPSHOT_LEN;
+/// How long to listen for a game offered by another board at a time, in microseconds.
+const LISTEN_US: u32 = 100_000;
+/// How long to listen for a reply after offering a game, in microseconds. Offers are made every
+/// frame while paused, so this is kept short to leave the main loop responsive; the receiving
+/// board replies as soon as it has checked the game.
+const OFFER_LISTEN_US: u32 = 2_500;
+/// Number of times to send an acknowledgement, in case the first is lost.
+const ACK_REPEATS: usize = 3;
+
+// Check at compile time that an offer fits in a packet, whatever the size of the grid
+const _: () = assert!(MAX_OFFER_LEN <= MAX_PAYLOAD_LEN);
+
+/// Broadcast the game once and briefly listen for another board taking it. Returns true if it was
+/// taken, in which case the game should not be continued on this board.
+pub(crate) fn offer_game(link: &mut RadioLink, game: &ModeGame) -> bool {
+    let mut offer = [0u8; MAX_OFFER_LEN];
+    offer[..MODE_RULES_LEN].copy_from_slice(&game.rules().to_bytes());
+    let len = match game.serialize(&mut offer[MODE_RULES_LEN..]) {
+        Ok(len) => MODE_RULES_LEN + len,
+        Err(_) => return false
+    };
+    link.send(PacketKind::Handoff, &offer[..len]);
+    link.receive(PacketKind::HandoffAck, &mut [], OFFER_LISTEN_US).is_some()
+}
+
+/// Listen for up to `timeout_ms` milliseconds for a game offered by another board, accepting the
+/// first valid one received.
+pub(crate) fn receive_game(link: &mut RadioLink, timeout_ms: u32) -> Option<ModeGame> {
+    let mut offer = [0u8; MAX_OFFER_LEN];
+    for _ in 0..(timeout_ms * 1000 / LISTEN_US) {
+        let len = match link.receive(PacketKind::Handoff, &mut offer, LISTEN_US) {
+            Some(len) if (MODE_RULES_LEN..=MAX_OFFER_LEN).contains(&len) => len,
+            _ => continue
+        };
+        let mut rules = [0u8; MODE_RULES_LEN];
+        rules.copy_from_slice(&offer[..MODE_RULES_LEN]);
+        let rules = match ModeRules::from_bytes(&rules) {
+            Some(rules) => rules,
+            None => continue
+        };
+        if let Ok(game) = Game::deserialize(&offer[MODE_RULES_LEN..len]) {
+            for _ in 0..ACK_REPEATS {
+                link.send(PacketKind::HandoffAck, &[]);
+            }
+            return Some(game.with_rules(rules));
+        }
+    }
+    None
+}
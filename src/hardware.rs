@@ -0,0 +1,96 @@
+//! Detection of optional on-board peripherals, so that modes and settings which depend on missing
+//! or faulty hardware can be hidden rather than hanging or panicking when they are first used.
+
+use lsm303agr::interface::I2cInterface;
+use lsm303agr::mode::MagOneShot;
+use lsm303agr::{AccelMode, AccelOutputDataRate, Lsm303agr};
+use microbit::board::I2CInternalPins;
+use microbit::hal::prelude::*;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::hal::twim::{self, Twim};
+use microbit::pac::{TIMER0, TWIM0};
+
+pub(crate) type Accelerometer = Lsm303agr<I2cInterface<Twim<TWIM0>>, MagOneShot>;
+
+/// I2C address of the interface MCU (the chip that handles USB) on the internal bus.
+const INTERFACE_ADDR: u8 = 0x70;
+/// Interface MCU command asking for the value of a property.
+const READ_REQUEST: u8 = 0x10;
+/// Interface MCU reply giving the value of a property.
+const READ_RESPONSE: u8 = 0x11;
+/// The interface MCU property holding the board ID, which identifies the board's revision.
+const BOARD_ID_PROPERTY: u8 = 0x01;
+/// Time the interface MCU is given to prepare its reply, in milliseconds.
+const INTERFACE_REPLY_MS: u32 = 10;
+/// Board IDs of the micro:bit revisions with a speaker and a touch logo (V2.00 to V2.21).
+const V2_BOARD_IDS: core::ops::RangeInclusive<u16> = 0x9903..=0x9906;
+
+/// Which optional peripherals are available.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Capabilities {
+    /// The LSM303AGR accelerometer responded on the internal I2C bus.
+    pub(crate) accelerometer: bool,
+    /// The board has a speaker. The speaker is a passive component that can't be probed, so this
+    /// goes by the board's revision, as reported by the interface MCU. If the interface MCU doesn't
+    /// report one (as with some older interface firmware), the speaker is assumed to be present.
+    pub(crate) speaker: bool,
+    /// The touch logo charged up in a plausible time while it was being calibrated.
+    pub(crate) touch_logo: bool,
+    /// A joystick was found on the edge connector.
+    pub(crate) joystick: bool
+}
+
+impl Capabilities {
+    pub(crate) fn new(
+        accelerometer: &Option<Accelerometer>,
+        board_id: Option<u16>,
+        touch_logo: bool,
+        joystick: bool
+    ) -> Self {
+        Self {
+            accelerometer: accelerometer.is_some(),
+            speaker: board_id.map_or(true, |id| V2_BOARD_IDS.contains(&id)),
+            touch_logo,
+            joystick
+        }
+    }
+}
+
+/// Probe the devices on the internal I2C bus: ask the interface MCU for the board ID, and check
+/// whether the accelerometer is present and responding, initialising it if so. Any error on the
+/// bus (including no device acknowledging its address) is treated as that device being absent.
+pub(crate) fn probe_internal_bus(
+    board_twim: TWIM0,
+    board_i2c: I2CInternalPins,
+    timer: &mut Timer<TIMER0, Periodic>
+) -> (Option<Accelerometer>, Option<u16>) {
+    let mut i2c = Twim::new(board_twim, board_i2c.into(), twim::Frequency::K100);
+    let board_id = read_board_id(&mut i2c, timer);
+    (probe_accelerometer(i2c, timer), board_id)
+}
+
+/// Ask the interface MCU for the board ID.
+fn read_board_id(i2c: &mut Twim<TWIM0>, timer: &mut Timer<TIMER0, Periodic>) -> Option<u16> {
+    i2c.write(INTERFACE_ADDR, &[READ_REQUEST, BOARD_ID_PROPERTY]).ok()?;
+    timer.delay_ms(INTERFACE_REPLY_MS);
+    // The reply is the command, the property, the length of its value and the value itself
+    let mut reply = [0u8; 5];
+    i2c.read(INTERFACE_ADDR, &mut reply).ok()?;
+    if reply[..3] != [READ_RESPONSE, BOARD_ID_PROPERTY, 2] {
+        return None;
+    }
+    Some(u16::from_le_bytes([reply[3], reply[4]]))
+}
+
+fn probe_accelerometer(
+    i2c: Twim<TWIM0>,
+    timer: &mut Timer<TIMER0, Periodic>
+) -> Option<Accelerometer> {
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    if !sensor.accelerometer_id().ok()?.is_correct() {
+        return None;
+    }
+    sensor.init().ok()?;
+    sensor.set_accel_mode_and_odr(timer, AccelMode::Normal, AccelOutputDataRate::Hz50).ok()?;
+    Some(sensor)
+}
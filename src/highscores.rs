@@ -0,0 +1,117 @@
+//! Tables of the best scores achieved in normal and blitz games, along with when each was achieved,
+//! if the time was known. Blitz games score differently, so have a table of their own.
+
+use core::fmt::{self, Write};
+
+use crate::clock::DateTime;
+#[cfg(feature = "persist")]
+use crate::storage::{Record, Storage};
+
+/// Number of entries in the high score table.
+pub(crate) const HIGH_SCORE_COUNT: usize = 3;
+/// Length of a single [`HighScore`] when serialized.
+#[cfg(feature = "persist")]
+const ENTRY_LEN: usize = 8;
+/// Length of [`HighScores`] when serialized.
+#[cfg(feature = "persist")]
+pub(crate) const HIGH_SCORES_LEN: usize = ENTRY_LEN * HIGH_SCORE_COUNT;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct HighScore {
+    pub(crate) score: u8,
+    /// When the score was achieved, or `None` if the time had not been set.
+    pub(crate) achieved_at: Option<DateTime>
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct HighScores {
+    /// The entries, best first. Scores that tie with an existing entry go after it.
+    entries: [Option<HighScore>; HIGH_SCORE_COUNT]
+}
+
+impl HighScores {
+    /// Record a score, returning its position in the table (0 being the best) if it made it in.
+    pub(crate) fn record(&mut self, score: u8, achieved_at: Option<DateTime>) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let pos = self.entries.iter().position(|e| e.map_or(true, |e| score > e.score))?;
+        self.entries.copy_within(pos..HIGH_SCORE_COUNT - 1, pos + 1);
+        self.entries[pos] = Some(HighScore { score, achieved_at });
+        Some(pos)
+    }
+
+    /// The best score in the table, if there is one.
+    pub(crate) fn best(&self) -> Option<u8> {
+        self.entries[0].map(|entry| entry.score)
+    }
+
+    /// Write the table out, one entry per line, each as its position, score and (if known) the
+    /// time it was achieved.
+    pub(crate) fn write_to(&self, out: &mut impl Write) -> fmt::Result {
+        for (i, entry) in self.entries.iter().flatten().enumerate() {
+            write!(out, "{}. {}", i + 1, entry.score)?;
+            if let Some(achieved_at) = entry.achieved_at {
+                write!(out, " {}", achieved_at)?;
+            }
+            write!(out, "\r\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write out the normal and blitz high score tables, each under a heading.
+pub(crate) fn write_leaderboard(
+    out: &mut impl Write,
+    high_scores: &HighScores,
+    blitz_scores: &HighScores
+) -> fmt::Result {
+    write!(out, "Normal\r\n")?;
+    high_scores.write_to(out)?;
+    write!(out, "Blitz\r\n")?;
+    blitz_scores.write_to(out)
+}
+
+#[cfg(feature = "persist")]
+impl HighScores {
+    /// Load the high score table kept in `record` from flash, or return an empty table if none has
+    /// been saved.
+    pub(crate) fn load(storage: &mut Storage, record: Record) -> Self {
+        let mut bytes = [0u8; HIGH_SCORES_LEN];
+        let mut high_scores = Self::default();
+        if !storage.read(record, &mut bytes) {
+            return high_scores;
+        }
+        for (entry, chunk) in high_scores.entries.iter_mut().zip(bytes.chunks_exact(ENTRY_LEN)) {
+            // A score of zero never makes it into the table, so marks an empty entry
+            if chunk[0] == 0 {
+                continue;
+            }
+            let mut word = [0u8; 4];
+            word.copy_from_slice(&chunk[4..8]);
+            *entry = Some(HighScore {
+                score: chunk[0],
+                achieved_at: if chunk[1] != 0 {
+                    Some(DateTime::from_unix_seconds(u32::from_le_bytes(word)))
+                } else {
+                    None
+                }
+            });
+        }
+        high_scores
+    }
+
+    pub(crate) fn save(&self, storage: &mut Storage, record: Record) {
+        let mut bytes = [0u8; HIGH_SCORES_LEN];
+        for (entry, chunk) in self.entries.iter().zip(bytes.chunks_exact_mut(ENTRY_LEN)) {
+            if let Some(entry) = entry {
+                chunk[0] = entry.score;
+                if let Some(achieved_at) = entry.achieved_at {
+                    chunk[1] = 1;
+                    chunk[4..8].copy_from_slice(&achieved_at.unix_seconds().to_le_bytes());
+                }
+            }
+        }
+        storage.write(record, &bytes);
+    }
+}
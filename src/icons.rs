@@ -0,0 +1,381 @@
+//! 5x5 icons, for use with `BitImage`.
+
+pub(crate) type Icon = [[u8; 5]; 5];
+
+/// Radio mast.
+pub(crate) const RADIO: Icon = [
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+/// Speaker.
+pub(crate) const SOUND: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 0, 1],
+    [1, 1, 1, 0, 1],
+    [0, 1, 1, 0, 1],
+    [0, 0, 1, 0, 0],
+];
+
+/// Speaker with a cross, for sound being off.
+pub(crate) const MUTE: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 0, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 1],
+];
+
+/// Joystick, seen from the side.
+pub(crate) const JOYSTICK: Icon = [
+    [0, 1, 1, 0, 0],
+    [0, 1, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [1, 1, 1, 1, 1],
+];
+
+/// Tilted board.
+pub(crate) const TILT: Icon = [
+    [0, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0],
+    [0, 1, 1, 0, 0],
+    [1, 1, 0, 0, 0],
+    [1, 0, 0, 0, 0],
+];
+
+/// Cross.
+pub(crate) const CROSS: Icon = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+/// Grid of lit dots.
+pub(crate) const GAMES: Icon = [
+    [1, 0, 1, 0, 1],
+    [0, 0, 0, 0, 0],
+    [1, 0, 1, 0, 1],
+    [0, 0, 0, 0, 0],
+    [1, 0, 1, 0, 1],
+];
+
+/// Apple.
+pub(crate) const FOOD: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+];
+
+/// Coiled snake.
+pub(crate) const SNAKE: Icon = [
+    [1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 1],
+    [1, 1, 1, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+/// Clock face.
+pub(crate) const CLOCK: Icon = [
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+    [1, 0, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+/// Pause bars.
+pub(crate) const PAUSE: Icon = [
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+];
+
+/// Arrow turning left, for relative controls.
+pub(crate) const RELATIVE: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+    [1, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 1, 0],
+];
+
+/// Compass points, for absolute controls.
+pub(crate) const ABSOLUTE: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [1, 1, 0, 1, 1],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+/// Calendar page, for the daily puzzle.
+pub(crate) const CALENDAR: Icon = [
+    [0, 1, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+/// Pencil, for the wall editor.
+pub(crate) const EDIT: Icon = [
+    [0, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 0, 0],
+    [1, 1, 0, 0, 0],
+];
+
+/// Lightning bolt, for blitz mode.
+pub(crate) const BLITZ: Icon = [
+    [0, 0, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+];
+
+/// Figure of eight, for marathon mode.
+pub(crate) const MARATHON: Icon = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+];
+
+/// Flag, for hard mode, where the food is always as far away as it can get.
+pub(crate) const FLAG: Icon = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 0, 0],
+    [0, 1, 0, 0, 0],
+    [1, 1, 1, 0, 0],
+];
+
+/// Scissors, for penalty mode, where running into itself cuts the snake short.
+pub(crate) const SCISSORS: Icon = [
+    [1, 1, 0, 0, 1],
+    [1, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [1, 1, 0, 1, 0],
+    [1, 1, 0, 0, 1],
+];
+
+/// Star, for celebrating the snake filling the grid in marathon mode.
+pub(crate) const STAR: Icon = [
+    [1, 0, 1, 0, 1],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+];
+
+/// Arrows pointing off opposite edges, for wraparound being on.
+pub(crate) const WRAP: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+/// Solid border, for wraparound being off.
+pub(crate) const NO_WRAP: Icon = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+/// Hourglass, for the game loop not keeping up with the frame timer.
+pub(crate) const LAGGING: Icon = [
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+];
+
+/// Compass needle, pointing north.
+pub(crate) const COMPASS: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+];
+
+/// Shield, for invincibility.
+pub(crate) const SHIELD: Icon = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 1, 0, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+];
+
+/// Lines of text, for logging each step.
+pub(crate) const LOG: Icon = [
+    [1, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+    [1, 1, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1],
+];
+
+/// Tick, for checking the game's invariants.
+pub(crate) const TICK: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 1, 0],
+    [1, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+];
+
+/// Picture frame, for capturing displayed frames.
+pub(crate) const FRAME: Icon = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+/// Filled circle, for recording a game's inputs.
+pub(crate) const RECORD: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+/// Chip, for the flash memory.
+pub(crate) const FLASH: Icon = [
+    [0, 1, 1, 1, 0],
+    [1, 1, 0, 1, 1],
+    [0, 1, 0, 1, 0],
+    [1, 1, 0, 1, 1],
+    [0, 1, 1, 1, 0],
+];
+
+/// Exclamation mark, for an internal error.
+pub(crate) const ERROR: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+/// One short bar, for the easiest difficulty.
+pub(crate) const EASY: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [1, 0, 0, 0, 0],
+    [1, 0, 0, 0, 0],
+];
+
+/// Two bars, rising, for the usual difficulty.
+pub(crate) const MEDIUM: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [1, 0, 1, 0, 0],
+    [1, 0, 1, 0, 0],
+];
+
+/// Three bars, rising, for the hardest difficulty.
+pub(crate) const HARD: Icon = [
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 0, 1],
+    [0, 0, 1, 0, 1],
+    [1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 1],
+];
+
+/// Sun, for the display's brightness.
+pub(crate) const SUN: Icon = [
+    [1, 0, 1, 0, 1],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+];
+
+/// Trophy, for the best score from normal games.
+pub(crate) const TROPHY: Icon = [
+    [1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+];
+
+/// Arrow pointing up, for which way round the board is held.
+pub(crate) const UP: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+/// Eye, for reduced flashing.
+pub(crate) const EYE: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+/// Empty battery.
+pub(crate) const BATTERY: Icon = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+];
+
+/// Letter A, for scrolling text.
+pub(crate) const LETTER: Icon = [
+    [0, 1, 1, 0, 0],
+    [1, 0, 0, 1, 0],
+    [1, 1, 1, 1, 0],
+    [1, 0, 0, 1, 0],
+    [1, 0, 0, 1, 0],
+];
+
+/// Three pads along the bottom edge, for the touch pads on the edge connector.
+pub(crate) const PADS: Icon = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 1],
+];
+
+/// Screen on a stand, for an external display.
+pub(crate) const SCREEN: Icon = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+];
@@ -0,0 +1,98 @@
+//! Steering with an analog joystick wired to the edge connector: its X axis to pin 0, its Y axis
+//! to pin 1 and its supply to 3V and GND. Both axes are read with the SAADC, and whichever is
+//! pushed furthest from its centre picks the direction for the snake to head in.
+
+use microbit::hal::gpio::p0::{P0_02, P0_03};
+use microbit::hal::gpio::{Disconnected, Input, PullDown};
+use microbit::hal::prelude::*;
+use microbit::hal::saadc::{Saadc, SaadcConfig};
+use microbit::pac::SAADC;
+use snakebit_core::game::Direction;
+
+use crate::control::{InputEvent, InputSource};
+
+/// Reading for the full supply voltage, at the SAADC's default 14-bit resolution.
+const FULL_SCALE: i32 = 1 << 14;
+/// Number of readings averaged to find the centre of each axis.
+const CALIBRATION_READINGS: i32 = 8;
+/// How far an axis must be pushed from its centre to count, as a percentage of full scale.
+const DEADZONE_PERCENT: i32 = 25;
+
+/// A joystick on the edge connector, read once a frame.
+pub(crate) struct Joystick {
+    saadc: Saadc,
+    x: P0_02<Input<PullDown>>,
+    y: P0_03<Input<PullDown>>,
+    /// Readings of the X and Y axes at rest.
+    centre: (i32, i32),
+    /// Which way the joystick was pushed at the last poll, if any.
+    direction: Option<Direction>,
+    /// A new direction that hasn't been gathered yet.
+    pending: Option<Direction>
+}
+
+impl Joystick {
+    /// Set up the joystick, calibrating it by taking the current position of each axis as its
+    /// centre, so the joystick shouldn't be touched while the board starts up. The pins are pulled
+    /// down, so that with nothing wired to them they read close to zero rather than floating. If
+    /// either axis doesn't rest near the middle of its range, no joystick is taken to be connected
+    /// and `None` is returned.
+    pub(crate) fn new(
+        board_saadc: SAADC,
+        x: P0_02<Disconnected>,
+        y: P0_03<Disconnected>
+    ) -> Option<Self> {
+        let mut joystick = Self {
+            saadc: Saadc::new(board_saadc, SaadcConfig::default()),
+            x: x.into_pulldown_input(),
+            y: y.into_pulldown_input(),
+            centre: (0, 0),
+            direction: None,
+            pending: None
+        };
+        let mut total = (0, 0);
+        for _ in 0..CALIBRATION_READINGS {
+            let (x, y) = joystick.read()?;
+            total = (total.0 + x, total.1 + y);
+        }
+        joystick.centre = (total.0 / CALIBRATION_READINGS, total.1 / CALIBRATION_READINGS);
+        let centred = |reading: i32| (FULL_SCALE / 4..FULL_SCALE * 3 / 4).contains(&reading);
+        (centred(joystick.centre.0) && centred(joystick.centre.1)).then_some(joystick)
+    }
+
+    /// Read both axes, or return `None` if the SAADC fails.
+    fn read(&mut self) -> Option<(i32, i32)> {
+        let x = self.saadc.read(&mut self.x).ok()?;
+        let y = self.saadc.read(&mut self.y).ok()?;
+        Some((x as i32, y as i32))
+    }
+
+    /// Read the joystick, noting any change in the direction it is pushed in. Pushing it further
+    /// towards the higher reading on either axis is right or up. This should be called every
+    /// frame.
+    pub(crate) fn poll(&mut self) {
+        let Some((x, y)) = self.read() else {
+            return;
+        };
+        let (dx, dy) = (x - self.centre.0, y - self.centre.1);
+        let direction = if dx.abs().max(dy.abs()) < FULL_SCALE * DEADZONE_PERCENT / 100 {
+            None
+        } else if dx.abs() > dy.abs() {
+            Some(if dx > 0 { Direction::Right } else { Direction::Left })
+        } else {
+            Some(if dy > 0 { Direction::Up } else { Direction::Down })
+        };
+        if direction != self.direction {
+            self.direction = direction;
+            self.pending = direction;
+        }
+    }
+}
+
+impl InputSource for Joystick {
+    /// Head in each new direction the joystick is pushed in. Holding it there doesn't repeat the
+    /// event, so it can't crowd out button presses.
+    fn next_event(&mut self) -> Option<InputEvent> {
+        self.pending.take().map(InputEvent::Head)
+    }
+}
@@ -1,56 +1,1512 @@
 #![no_main]
 #![no_std]
 
-mod game;
 mod control;
 mod display;
+mod icons;
+mod about;
+mod actions;
+mod animation;
+mod budget;
+mod cheats;
+mod clock;
+#[cfg(feature = "compass")]
+mod compass;
+mod config;
+mod custom;
+mod daily;
+mod devmenu;
+#[cfg(feature = "fonts")]
+mod entry;
+#[cfg(feature = "fonts")]
+mod font;
+mod entropy;
+#[cfg(feature = "fuzz")]
+mod fuzz;
+mod highscores;
+mod render;
+mod rules;
+mod serial;
+mod settings;
+mod share;
+#[cfg(feature = "audio")]
+mod sound;
+#[cfg(feature = "ssd1306")]
+mod ssd1306;
+mod hardware;
+mod stats;
+#[cfg(feature = "tilt")]
+mod tilt;
+mod trail;
+#[cfg(feature = "joystick")]
+mod joystick;
+#[cfg(feature = "max7219")]
+mod max7219;
+#[cfg(feature = "neopixel")]
+mod neopixel;
+#[cfg(feature = "persist")]
+mod storage;
+#[cfg(feature = "radio")]
+mod radio;
+#[cfg(feature = "radio")]
+mod handoff;
+#[cfg(feature = "radio")]
+mod remote;
+
+// Both need pins 0 and 1 of the edge connector
+#[cfg(all(feature = "pads", feature = "joystick"))]
+compile_error!("the `pads` and `joystick` features can't be enabled together");
+// Each plays on a grid the size of its display: 8x8 and 9x9
+#[cfg(all(feature = "max7219", feature = "ssd1306"))]
+compile_error!("the `max7219` and `ssd1306` features can't be enabled together");
+// The 9x9 grid doesn't fit on the 8x8 panel
+#[cfg(all(feature = "neopixel", feature = "ssd1306"))]
+compile_error!("the `neopixel` and `ssd1306` features can't be enabled together");
+// Both need the SAADC: the controller to read its battery voltage
+#[cfg(all(feature = "controller", feature = "joystick"))]
+compile_error!("the `controller` and `joystick` features can't be enabled together");
 
 use cortex_m_rt::entry;
 use microbit::Board;
-use rtt_target::rtt_init_print;
+use rtt_target::{rprintln, rtt_init_print};
 use microbit::hal::{Rng, Timer};
+use microbit::hal::clocks::Clocks;
+use microbit::hal::timer::Periodic;
+use microbit::pac::TIMER0;
 use microbit::display::nonblocking::{BitImage, GreyscaleImage};
 use microbit::hal::prelude::*;
 use panic_rtt_target as _;
+use snakebit_core::game::{
+    blend_matrix, count_matrix, Game, GameError, GameStatus, Turn, N_COLS, N_ROWS
+};
+use snakebit_core::game::snapshot::MAX_SNAPSHOT_LEN;
+use snakebit_core::replay::Recording;
+use snakebit_core::seed::SeedSource;
+
+use crate::about::show_features;
+#[cfg(feature = "persist")]
+use crate::about::show_flash_wear;
+use crate::actions::{Action, ActionMap, Gesture};
+use crate::animation::{self, Frame};
+use crate::budget::{init_budget, within_budget, FrameCheck};
+use crate::cheats::{Cheat, CheatCodes};
+use crate::clock::{init_clock, now, set_now, today, Date};
+#[cfg(feature = "compass")]
+use crate::compass::CompassSteering;
+use crate::config::{GameConfig, GameMode, SessionOverrides};
+use crate::control::{
+    clear_events, gather_input, init_buttons, next_event, next_turn, repeat_held_turns,
+    set_action_map, set_buttons_swapped, set_turn_on_press, settle_buttons, take_action,
+    take_chord, take_long_press, Button, InputEvent, TouchLogo
+};
+#[cfg(feature = "tilt")]
+use crate::control::{discard_turns, TiltSteering};
+#[cfg(feature = "latency")]
+use crate::control::take_turn_latency_ms;
+#[cfg(feature = "pads")]
+use crate::control::take_event;
+use crate::custom::CustomWalls;
+use crate::daily::{daily_game, DailyBest};
+use crate::devmenu::{dev_menu, DevSettings, ForcedOutcome};
+use crate::display::{
+    boost, clear_display, display_image, flash_image, high_contrast, init_display, set_brightness,
+    set_rotation, show_count, transition, viewport, Compositor, Transition, LED_COLS, LED_ROWS
+};
+use crate::entropy::{boot_seeds, Seeds};
+#[cfg(feature = "fonts")]
+use crate::font::scroll_text;
+use crate::highscores::{write_leaderboard, HighScores};
+use crate::hardware::{probe_internal_bus, Capabilities};
+#[cfg(feature = "tilt")]
+use crate::hardware::Accelerometer;
+use crate::icons::{self, Icon};
+#[cfg(feature = "joystick")]
+use crate::joystick::Joystick;
+#[cfg(feature = "max7219")]
+use crate::max7219::Max7219;
+#[cfg(feature = "neopixel")]
+use crate::neopixel::NeoPixels;
+use crate::render::{Ascii, Colour, LedMatrix, Renderer, Rtt, Tile};
+#[cfg(feature = "ascii")]
+use crate::render::AsciiArt;
+use crate::rules::{ModeGame, ModeRules};
+use crate::serial::{Command, SerialPort};
+use crate::settings::{controls_icon, next_controls, settings_menu};
+use crate::share::{announce, record_share_code, share_code};
+#[cfg(feature = "fonts")]
+use crate::share::enter_share_code;
+#[cfg(feature = "audio")]
+use crate::sound::Beeper;
+#[cfg(feature = "ssd1306")]
+use crate::ssd1306::Ssd1306;
+use crate::stats::{show_stats, Stats};
+#[cfg(feature = "tilt")]
+use crate::tilt::ShakeDetector;
+use crate::trail::ScentTrail;
+#[cfg(feature = "radio")]
+use crate::handoff::{offer_game, receive_game};
+#[cfg(feature = "radio")]
+use crate::radio::RadioLink;
+#[cfg(feature = "radio")]
+use crate::remote::{measure_round_trip, RemoteControl, RemoteNotice};
+#[cfg(feature = "controller")]
+use crate::remote::run_controller;
+#[cfg(feature = "persist")]
+use crate::storage::{Record, Storage};
+#[cfg(feature = "fuzz")]
+use crate::fuzz::run_fuzz;
+
+
+/// Interval between display refreshes, in milliseconds. Game steps happen on their own schedule,
+/// every `Game::step_len_ms` milliseconds, rounded up to a whole number of frames.
+const FRAME_MS: u32 = 20;
+/// How long to wait at startup for a game to be handed off from another board, in milliseconds.
+#[cfg(feature = "radio")]
+const HANDOFF_WAIT_MS: u32 = 30_000;
+/// How long the hunter stays lit, and then unlit, as it blinks during a chase, in milliseconds.
+const HUNTER_BLINK_MS: u32 = 200;
+/// How long food stays lit, and then unlit, as it blinks on the high contrast display, in
+/// milliseconds. Slower than the hunter, so the two can be told apart.
+const FOOD_BLINK_MS: u32 = 400;
+/// How long the hunter stays lit, and then unlit, with flashing reduced, in milliseconds.
+const CALM_HUNTER_BLINK_MS: u32 = 1000;
+/// How long food stays lit, and then unlit, on the high contrast display with flashing reduced,
+/// in milliseconds.
+const CALM_FOOD_BLINK_MS: u32 = 2000;
+/// How long the end of a game takes to fade in with flashing reduced, rather than flashing, in
+/// milliseconds.
+const CALM_FADE_MS: u32 = 600;
+/// How long the end of a game is then shown for, in milliseconds.
+const CALM_HOLD_MS: u32 = 800;
+/// How long the game has to be left paused, with no input, before the clock is shown instead of
+/// the pause icon, in milliseconds.
+const CLOCK_IDLE_MS: u32 = 30_000;
+/// Number of beats in the countdown before each game.
+const COUNTDOWN_BEATS: usize = 3;
+/// Length of each beat of the countdown, in milliseconds.
+const COUNTDOWN_BEAT_MS: u32 = 600;
+/// Number of turns a game's recording can hold (steps without a turn take up no room).
+const MAX_RECORDED_TURNS: usize = 256;
+/// Longest the change made by a step is animated for, in milliseconds. Slower steps are animated
+/// over the same time, so the snake doesn't look like it's crawling, and faster ones over half
+/// their length, so that the animation is over well before the next step.
+const MAX_SLIDE_MS: u32 = 120;
+/// Time the snake takes to go out, a tile at a time, after it crashes, in milliseconds. Short
+/// snakes go out more slowly, taking no more than [`MAX_DEATH_TILE_MS`] for each tile.
+const DEATH_MS: u32 = 1200;
+/// Longest each of the snake's tiles takes to go out after it crashes, in milliseconds.
+const MAX_DEATH_TILE_MS: u32 = 150;
+/// How long status icons interrupt play for, in milliseconds.
+const STATUS_ICON_MS: u32 = 1000;
+/// Brightness of the snake's tail just behind its head.
+const TAIL_BRIGHTNESS: u8 = 5;
+/// Brightness of the end of the snake's tail. The tail fades to this from [`TAIL_BRIGHTNESS`].
+const TAIL_END_BRIGHTNESS: u8 = 3;
+/// Colour of the snake's head, on displays that can show colour.
+const HEAD_COLOUR: Colour = Colour::new(64, 64, 0);
+/// Colour of the rest of the snake.
+const TAIL_COLOUR: Colour = Colour::new(0, 64, 0);
+/// Colour of food.
+const FOOD_COLOUR: Colour = Colour::new(128, 0, 0);
+/// Colour of walls.
+const WALL_COLOUR: Colour = Colour::new(0, 0, 128);
+/// Colour of the hunter.
+const HUNTER_COLOUR: Colour = Colour::new(96, 0, 96);
+
+/// The snake on its own, as a layer of the game's frame: its head at `head_brightness`, and its
+/// tail fading from [`TAIL_BRIGHTNESS`] just behind the head to [`TAIL_END_BRIGHTNESS`] at its end,
+/// so that which way the snake is going can be seen at a glance. Displays without the levels to
+/// show this draw the tail by what it is (see [`Tile`]), so its dim end isn't taken for a wall.
+fn snake_layer(game: &ModeGame, head_brightness: u8) -> [[u8; N_COLS]; N_ROWS] {
+    let mut layer = [[0; N_COLS]; N_ROWS];
+    let last = game.snake_len().saturating_sub(2).max(1);
+    let range = (TAIL_BRIGHTNESS - TAIL_END_BRIGHTNESS) as usize;
+    for (i, (row, col)) in game.segments().enumerate() {
+        layer[row][col] = match i {
+            0 => head_brightness,
+            i => TAIL_BRIGHTNESS - (range * (i - 1) / last) as u8
+        };
+    }
+    layer
+}
+
+/// Interrupt play to show `icon` for a moment, eg when sound is muted, then start timing frames
+/// again.
+fn flash_status(timer: &mut Timer<TIMER0, Periodic>, icon: Icon) {
+    flash_image(timer, &BitImage::new(&icon), STATUS_ICON_MS);
+    timer.start(FRAME_MS * 1000);
+}
+
+/// Show that a controller has just been linked: the radio mast, then the round trip to the
+/// controller and back in milliseconds (see [`show_count`]), so that the player can tell whether it
+/// will be responsive enough. Then start timing frames again.
+#[cfg(feature = "radio")]
+fn show_linked(timer: &mut Timer<TIMER0, Periodic>, link: &mut RadioLink) {
+    flash_image(timer, &BitImage::new(&icons::RADIO), STATUS_ICON_MS);
+    match measure_round_trip(link) {
+        Some(round_trip_us) => {
+            rprintln!("Controller round trip: {}us", round_trip_us);
+            // Rounded up, so that even the quickest round trip lights an LED
+            let round_trip_ms = (round_trip_us + 999) / 1000;
+            show_count("PING", round_trip_ms as usize, |ms| {
+                timer.delay_ms(ms);
+                true
+            });
+            clear_display();
+        },
+        None => rprintln!("Controller didn't answer any pings")
+    }
+    timer.start(FRAME_MS * 1000);
+}
+
+/// Wait for the end of the current frame, handling serial input in the meantime. Any steering
+/// received over serial is then queued with the button presses, and any button let go too soon
+/// after being pressed to have been noticed is caught up on.
+fn wait_frame(timer: &mut Timer<TIMER0, Periodic>, serial: &mut SerialPort) {
+    while timer.wait().is_err() {
+        within_budget("serial", || serial.poll());
+    }
+    gather_input(serial);
+    settle_buttons();
+}
 
-use crate::control::{get_turn, init_buttons};
-use crate::display::{clear_display, display_image, init_display};
-use crate::game::{Game, GameStatus};
+/// Show that the game logic has failed (which means there is a bug), by flashing a warning sign,
+/// and log the error over RTT.
+fn show_error(timer: &mut Timer<TIMER0, Periodic>, error: GameError) {
+    rprintln!("Game error: {:?}", error);
+    for _ in 0..3 {
+        flash_image(timer, &BitImage::new(&icons::ERROR), 500);
+    }
+}
 
+/// Log marker number `number` over RTT, with the time since the game loop started, what is being
+/// shown and the game's state, so that testers can flag moments for later analysis.
+fn log_marker(number: u32, shown_ms: u32, game: &ModeGame, matrix: &[[u8; N_COLS]; N_ROWS]) {
+    rprintln!("Marker {} at {}ms", number, shown_ms);
+    rprintln!("Frame: {:?}", matrix);
+    let mut snapshot = [0u8; MAX_SNAPSHOT_LEN];
+    if let Ok(len) = game.serialize(&mut snapshot) {
+        rprintln!("Game state: {:02x?}", &snapshot[..len]);
+    }
+}
+
+/// Log `recording` over RTT, as the game's state when it started and the turn applied at each step
+/// since, in the form the golden replay tests take.
+fn log_recording(recording: &Recording<MAX_RECORDED_TURNS>, playing: GameMode) {
+    rprintln!("Recording of {:?} game: start {:02x?}", playing, recording.start());
+    rprintln!("Inputs: {}", recording.inputs());
+    if recording.is_full() {
+        rprintln!("Recording filled up after {} steps", recording.steps());
+    }
+}
+
+/// Celebrate the snake filling the grid in a marathon game, showing how many times it has done so
+/// before play carries on. The star flashes, unless flashing is reduced.
+fn celebrate_loop(timer: &mut Timer<TIMER0, Periodic>, loops: u8, reduced_flashing: bool) {
+    if reduced_flashing {
+        flash_image(timer, &BitImage::new(&icons::STAR), 1000);
+    } else {
+        for _ in 0..3 {
+            flash_image(timer, &BitImage::new(&icons::STAR), 300);
+        }
+    }
+    flash_image(timer, &BitImage::new(&count_matrix(loops as usize)), 1000);
+    // Presses made during the celebration shouldn't be applied once play carries on
+    clear_events();
+    timer.start(FRAME_MS * 1000);
+}
+
+/// A best score to show after a game.
+#[derive(Debug, Copy, Clone)]
+struct Best {
+    /// What the score is the best of, eg a calendar for the daily puzzle.
+    icon: Icon,
+    score: u8,
+    /// Whether the game just played beat the previous best.
+    beaten: bool
+}
+
+/// Show the end of a game: the final state, then the score and the best score for the game's mode,
+/// if it has one. A lost game shows the snake going out a tile at a time from its tail, then where
+/// it crashed flashing; a won one flashes as a whole, then fireworks go off. With
+/// `reduced_flashing`, these fade in and stay lit instead. `matrix` is what was shown when the game
+/// ended; where the grid is bigger than the LEDs, they show the part of it around where the game
+/// ended. Shaking the board skips the rest of the sequence, to restart at once.
+fn show_game_over(
+    timer: &mut Timer<TIMER0, Periodic>,
+    matrix: &[[u8; N_COLS]; N_ROWS],
+    game: &ModeGame,
+    best: Option<Best>,
+    reduced_flashing: bool,
+    #[cfg(feature = "tilt")] accelerometer: &mut Option<Accelerometer>
+) {
+    #[cfg(feature = "tilt")]
+    let mut shake = ShakeDetector::new();
+    // Wait for `ms` milliseconds, returning false if the board is shaken in the meantime
+    let mut wait = |timer: &mut Timer<TIMER0, Periodic>, ms: u32| {
+        #[cfg(feature = "tilt")]
+        if let Some(accelerometer) = accelerometer.as_mut() {
+            timer.start(FRAME_MS * 1000);
+            for _ in 0..ms / FRAME_MS {
+                while timer.wait().is_err() {}
+                if within_budget("shake", || shake.poll(accelerometer, FRAME_MS)) {
+                    return false;
+                }
+            }
+            return true;
+        }
+        timer.delay_ms(ms);
+        true
+    };
+
+    if game.status == GameStatus::Lost {
+        // A crash off the edge of the grid (or forced by a tester) has nothing better to show
+        let (row, col) = game.crash_site().unwrap_or_else(|| game.head());
+        // The snake goes out tile by tile, then where it crashed flashes over what is left
+        let mut remains = *matrix;
+        let tile_ms = (DEATH_MS / game.snake_len() as u32).clamp(FRAME_MS, MAX_DEATH_TILE_MS);
+        let dying = game.snake_tiles().map(|(tile_row, tile_col)| {
+            remains[tile_row][tile_col] = 0;
+            Frame { matrix: viewport(&remains, row, col), ms: tile_ms }
+        });
+        if !animation::play(dying, |ms| wait(timer, ms)) {
+            return;
+        }
+        let flash = Compositor::new().layer(&remains).tile(row, col, 9).frame();
+        let (remains, flash) = (viewport(&remains, row, col), viewport(&flash, row, col));
+        let shown = if reduced_flashing {
+            let fade = animation::fade(remains, flash, CALM_FADE_MS, CALM_HOLD_MS);
+            animation::play(fade, |ms| wait(timer, ms))
+        } else {
+            animation::play(animation::blink(remains, flash, 3, 200), |ms| wait(timer, ms))
+        };
+        if !shown {
+            return;
+        }
+    } else {
+        let (row, col) = game.head();
+        let matrix = viewport(matrix, row, col);
+        let shown = if reduced_flashing {
+            let fade =
+                animation::fade([[0; LED_COLS]; LED_ROWS], matrix, CALM_FADE_MS, CALM_HOLD_MS);
+            animation::play(fade, |ms| wait(timer, ms))
+        } else {
+            let won = animation::blink([[0; LED_COLS]; LED_ROWS], matrix, 3, 200)
+                .chain(animation::fireworks());
+            animation::play(won, |ms| wait(timer, ms))
+        };
+        if !shown {
+            return;
+        }
+    }
+    // The score wipes across what the game ended with
+    if !show_count("SCORE", game.score() as usize, |ms| wait(timer, ms)) {
+        return;
+    }
+    if let Some(best) = best {
+        clear_display();
+        if !wait(timer, 200) {
+            return;
+        }
+        // A beaten best is the score just shown, so is celebrated instead of shown again
+        if best.beaten {
+            let shown = if reduced_flashing {
+                animation::play(animation::calm_new_high(), |ms| wait(timer, ms))
+            } else {
+                animation::play(animation::new_high(), |ms| wait(timer, ms))
+            };
+            if !shown {
+                return;
+            }
+            #[cfg(feature = "fonts")]
+            scroll_text(b"NEW HIGH", |ms| wait(timer, ms));
+        } else {
+            flash_image(timer, &BitImage::new(&best.icon), 1000);
+            show_count("BEST", best.score as usize, |ms| wait(timer, ms));
+        }
+    }
+}
+
+/// Start a normal game with a new seed, showing the error and trying again with another seed if
+/// that fails.
+fn fresh_game(
+    timer: &mut Timer<TIMER0, Periodic>,
+    seeds: &mut Seeds,
+    config: &GameConfig
+) -> ModeGame {
+    loop {
+        match Game::new(seeds.next_u32()) {
+            Ok(game) => return game.with_rules(ModeRules::new(GameMode::Normal, config)),
+            Err(e) => show_error(timer, e)
+        }
+    }
+}
+
+/// Write any changed records to flash, which stalls the CPU for a while, so should only be done
+/// while the game isn't running.
+#[cfg(feature = "persist")]
+fn flush_storage(storage: &mut Storage) {
+    if let Err(e) = storage.flush() {
+        rprintln!("Failed to save to flash: {:?}", e);
+    }
+}
+
+/// Carry out any commands received over serial, returning whether either high score table was
+/// changed.
+fn handle_commands(
+    serial: &mut SerialPort,
+    config: &mut GameConfig,
+    high_scores: &mut HighScores,
+    blitz_scores: &mut HighScores,
+    dev: &mut DevSettings,
+    game: &mut ModeGame
+) -> bool {
+    let mut scores_changed = false;
+    while let Some(command) = serial.next_command() {
+        let ok = match command {
+            Command::SetTime(now) => {
+                set_now(now);
+                true
+            },
+            Command::ListHighScores => write_leaderboard(serial, high_scores, blitz_scores).is_ok(),
+            Command::SetBouncingWalls(on) => {
+                config.bouncing_walls = on;
+                true
+            },
+            Command::SetSpeedSchedule(schedule) => {
+                config.speed_schedule = schedule;
+                true
+            },
+            Command::SetBonusRounds(on) => {
+                config.bonus_rounds = on;
+                true
+            },
+            Command::SetHighContrast(on) => {
+                config.high_contrast = on;
+                true
+            },
+            Command::SetLevelBeeps(on) => {
+                config.level_beeps = on;
+                true
+            },
+            Command::SetRandomStart(on) => {
+                config.random_start = on;
+                true
+            },
+            Command::SetFoodTrails(on) => {
+                config.food_trails = on;
+                true
+            },
+            Command::SetHoldToTurn(on) => {
+                config.hold_to_turn = on;
+                true
+            },
+            // Takes effect at the next frame of play
+            Command::SetTurnOnPress(on) => {
+                config.turn_on_press = on;
+                true
+            },
+            Command::SetSwapButtons(on) => {
+                config.swap_buttons = on;
+                set_buttons_swapped(on);
+                true
+            },
+            Command::SetAction(gesture, action) => {
+                let ok = config.actions.set(gesture, action);
+                set_action_map(config.actions);
+                ok
+            },
+            Command::ResetActions => {
+                config.actions = ActionMap::DEFAULT;
+                set_action_map(config.actions);
+                true
+            },
+            Command::RecordShareCode(code) => {
+                match record_share_code(&code, high_scores, blitz_scores) {
+                    Some(changed) => {
+                        scores_changed |= changed;
+                        true
+                    },
+                    None => false
+                }
+            },
+            Command::ForceOutcome(outcome) => {
+                dev.next_outcome = Some(outcome);
+                true
+            },
+            Command::MoveFood(row, col) => game.move_food(row, col).unwrap_or(false),
+            Command::SetEcho(on) => {
+                serial.set_echo(on);
+                true
+            },
+            Command::Invalid => false
+        };
+        serial.reply(ok);
+    }
+    scores_changed
+}
+
+/// Show the title screen until a button is pressed (or a restart is asked for over serial). The
+/// press only starts the game, so isn't left to be handled by anything else. Holding B opens the
+/// settings menu, returning to the title screen once it is left. While the logo is touched, presses
+/// only enter cheat codes, as on the pause screen.
+fn title_screen(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    config: &mut GameConfig,
+    logo: &mut TouchLogo,
+    dev: &mut DevSettings,
+    overrides: &mut SessionOverrides,
+    capabilities: &Capabilities
+) {
+    let mut cheats = CheatCodes::new();
+    loop {
+        timer.start(FRAME_MS * 1000);
+        let mut open_settings = false;
+        let mut entered = None;
+        // Wait for `ms` milliseconds, returning false if the title screen should be left in the
+        // meantime
+        let mut wait = |ms: u32| {
+            for _ in 0..ms / FRAME_MS {
+                wait_frame(timer, serial);
+                if take_long_press() == Some(Button::B) {
+                    open_settings = true;
+                    return false;
+                }
+                logo.poll();
+                let event = next_event();
+                if !logo.is_touched() {
+                    cheats.reset();
+                } else if let Some(event) = event {
+                    logo.cancel_tap();
+                    entered = cheats.feed(event);
+                    if entered.is_some() {
+                        return false;
+                    }
+                    continue;
+                }
+                if event.is_some() || serial.take_restart() {
+                    return false;
+                }
+            }
+            true
+        };
+        while animation::play(animation::title(), &mut wait) {}
+        match entered {
+            Some(Cheat::DevMenu) => {
+                // An outcome forced from the menu is kept for the game about to start
+                dev_menu(timer, serial, dev);
+            },
+            Some(cheat) => {
+                if let Some(icon) = toggle_cheat(
+                    cheat,
+                    overrides,
+                    #[cfg(feature = "compass")] capabilities.accelerometer
+                ) {
+                    flash_image(timer, &BitImage::new(&icon), 1000);
+                }
+            },
+            None if open_settings => {
+                settings_menu(timer, serial, config, overrides, capabilities);
+            },
+            None => break
+        }
+    }
+    // The title fades out, rather than vanishing mid-step
+    transition(&[[0; LED_COLS]; LED_ROWS], Transition::Fade, |ms| {
+        for _ in 0..ms / FRAME_MS {
+            wait_frame(timer, serial);
+        }
+        true
+    });
+    clear_events();
+}
+
+/// Count down to the start of a game, showing one dot fewer on each beat (and, with the `audio`
+/// feature, beeping on each if sound is on). Pressing B during the countdown turns wraparound on or
+/// off for the rest of the session, without changing the saved setting (unless the game is
+/// `resumed` from another board, as it keeps the wraparound it was started with), and with the
+/// `audio` feature pressing A does the same for sound (if the board has a speaker).
+fn countdown(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    config: &GameConfig,
+    overrides: &mut SessionOverrides,
+    resumed: bool,
+    #[cfg(feature = "audio")] speaker: bool,
+    #[cfg(feature = "audio")] beeper: &mut Beeper
+) {
+    for beat in (1..=COUNTDOWN_BEATS).rev() {
+        let image = BitImage::new(&count_matrix(beat));
+        display_image(&image);
+        #[cfg(feature = "audio")]
+        if overrides.apply(*config).sound {
+            beeper.play(1);
+        }
+        timer.start(FRAME_MS * 1000);
+        let mut elapsed_ms = 0;
+        while elapsed_ms < COUNTDOWN_BEAT_MS {
+            wait_frame(timer, serial);
+            elapsed_ms += FRAME_MS;
+            #[cfg(feature = "audio")]
+            beeper.poll(FRAME_MS);
+            let icon = match next_event() {
+                Some(InputEvent::TurnRight) if !resumed => {
+                    let wraparound = !overrides.apply(*config).wraparound;
+                    overrides.wraparound = Some(wraparound);
+                    if wraparound { icons::WRAP } else { icons::NO_WRAP }
+                },
+                #[cfg(feature = "audio")]
+                Some(InputEvent::TurnLeft) if speaker => {
+                    let sound = !overrides.apply(*config).sound;
+                    overrides.sound = Some(sound);
+                    if sound { icons::SOUND } else { icons::MUTE }
+                },
+                _ => continue
+            };
+            #[cfg(feature = "audio")]
+            beeper.stop();
+            flash_image(timer, &BitImage::new(&icon), 600);
+            display_image(&image);
+            timer.start(FRAME_MS * 1000);
+        }
+    }
+    clear_display();
+    // Presses made during the countdown shouldn't be applied once play starts
+    clear_events();
+}
+
+/// Turn the hidden mode unlocked by `cheat` on or off, returning an icon showing whether it is now
+/// on, or `None` if nothing was changed (as for the developer menu, which isn't a mode, or for
+/// compass steering without a working accelerometer).
+fn toggle_cheat(
+    cheat: Cheat,
+    overrides: &mut SessionOverrides,
+    #[cfg(feature = "compass")] compass_available: bool
+) -> Option<Icon> {
+    match cheat {
+        Cheat::DevMenu => None,
+        Cheat::Invincible => {
+            overrides.invincible = !overrides.invincible;
+            Some(if overrides.invincible { icons::SHIELD } else { icons::CROSS })
+        },
+        // Compass steering takes precedence over the other controls while it is on
+        #[cfg(feature = "compass")]
+        Cheat::Compass if compass_available => {
+            overrides.compass_steering = !overrides.compass_steering;
+            Some(if overrides.compass_steering { icons::COMPASS } else { icons::CROSS })
+        },
+        #[cfg(feature = "compass")]
+        Cheat::Compass => None
+    }
+}
+
+/// The ways in which a pause can end.
+enum PauseOutcome {
+    /// The player resumed the game.
+    Resumed,
+    /// Another board took the game over.
+    #[cfg(feature = "radio")]
+    HandedOff
+}
+
+/// Pause the game until both buttons are pressed together again, A is held down or the logo is
+/// tapped. While paused, pressing A cycles through the controls, pressing B cycles through the game
+/// modes (taking effect from the next game) and the game is offered to other boards over the radio.
+/// Switching to the custom mode opens the wall editor, and holding B opens the share code entry
+/// screen. If nothing is pressed for a while and the time has been set, the time is shown on a
+/// binary clock until the next input, which does nothing else. While the logo is touched, presses
+/// only enter cheat codes.
+fn pause(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    config: &mut GameConfig,
+    custom_walls: &mut CustomWalls,
+    logo: &mut TouchLogo,
+    dev: &mut DevSettings,
+    overrides: &mut SessionOverrides,
+    #[cfg(feature = "fonts")] high_scores: &mut HighScores,
+    #[cfg(feature = "fonts")] blitz_scores: &mut HighScores,
+    #[cfg(feature = "tilt")] accelerometer: &mut Option<Accelerometer>,
+    #[cfg(feature = "radio")] game: &ModeGame,
+    #[cfg(feature = "radio")] link: &mut RadioLink
+) -> PauseOutcome {
+    // Menus need to tell presses from long presses
+    set_turn_on_press(false);
+    display_image(&BitImage::new(&icons::PAUSE));
+    // Time since the last input, for switching to the clock
+    let mut idle_ms = 0;
+    let mut cheats = CheatCodes::new();
+    loop {
+        #[cfg(feature = "radio")]
+        if within_budget("handoff offer", || offer_game(link, game)) {
+            return PauseOutcome::HandedOff;
+        }
+        wait_frame(timer, serial);
+        let logo_tapped = logo.poll();
+        let long_press = take_long_press();
+        let event = next_event();
+        if !logo_tapped && long_press.is_none() && event.is_none() {
+            idle_ms = idle_ms.saturating_add(FRAME_MS);
+            if idle_ms >= CLOCK_IDLE_MS {
+                match now() {
+                    Some(now) => display_image(&GreyscaleImage::new(&now.clock_face())),
+                    // Without the time there's no clock to show, so stay on the pause icon
+                    None => idle_ms = 0
+                }
+            }
+            continue;
+        }
+        if core::mem::take(&mut idle_ms) >= CLOCK_IDLE_MS {
+            // Waking the board up shouldn't also change anything
+            clear_events();
+            display_image(&BitImage::new(&icons::PAUSE));
+            continue;
+        }
+        if !logo.is_touched() {
+            cheats.reset();
+        } else if let Some(event) = event {
+            logo.cancel_tap();
+            let icon = match cheats.feed(event) {
+                Some(Cheat::DevMenu) => {
+                    if dev_menu(timer, serial, dev) {
+                        return PauseOutcome::Resumed;
+                    }
+                    None
+                },
+                Some(cheat) => toggle_cheat(
+                    cheat,
+                    overrides,
+                    #[cfg(feature = "compass")] accelerometer.is_some()
+                ),
+                None => continue
+            };
+            if let Some(icon) = icon {
+                flash_image(timer, &BitImage::new(&icon), 1000);
+            }
+            display_image(&BitImage::new(&icons::PAUSE));
+            timer.start(FRAME_MS * 1000);
+            continue;
+        }
+        #[cfg(feature = "fonts")]
+        if long_press == Some(Button::B) {
+            enter_share_code(timer, serial, high_scores, blitz_scores);
+            display_image(&BitImage::new(&icons::PAUSE));
+            timer.start(FRAME_MS * 1000);
+            continue;
+        }
+        if event == Some(InputEvent::Both) || logo_tapped || long_press == Some(Button::A) {
+            // Presses made while paused shouldn't be applied once play resumes
+            clear_events();
+            return PauseOutcome::Resumed;
+        }
+        let icon = match event {
+            Some(InputEvent::TurnLeft) => {
+                // Tilt steering needs a working accelerometer
+                #[cfg(feature = "tilt")]
+                let tilt_available = accelerometer.is_some();
+                #[cfg(not(feature = "tilt"))]
+                let tilt_available = false;
+                next_controls(config, tilt_available);
+                controls_icon(config)
+            },
+            Some(InputEvent::TurnRight) => {
+                // The daily puzzle can only be played if we know what day it is, and layouts can
+                // only be drawn with the accelerometer
+                let daily_available = today().is_some();
+                #[cfg(feature = "tilt")]
+                let editor_available = accelerometer.is_some();
+                #[cfg(not(feature = "tilt"))]
+                let editor_available = false;
+                config.mode = match config.mode {
+                    GameMode::Normal if daily_available => GameMode::Daily,
+                    GameMode::Normal | GameMode::Daily if editor_available => GameMode::Custom,
+                    GameMode::Blitz => GameMode::Marathon,
+                    GameMode::Marathon => GameMode::Hard,
+                    GameMode::Hard => GameMode::Penalty,
+                    GameMode::Penalty => GameMode::Normal,
+                    _ => GameMode::Blitz
+                };
+                match config.mode {
+                    GameMode::Normal => icons::CROSS,
+                    GameMode::Daily => icons::CALENDAR,
+                    GameMode::Custom => icons::EDIT,
+                    GameMode::Blitz => icons::BLITZ,
+                    GameMode::Marathon => icons::MARATHON,
+                    GameMode::Hard => icons::FLAG,
+                    GameMode::Penalty => icons::SCISSORS
+                }
+            },
+            _ => continue
+        };
+        flash_image(timer, &BitImage::new(&icon), 1000);
+        #[cfg(feature = "tilt")]
+        if let (GameMode::Custom, Some(accelerometer)) = (config.mode, accelerometer.as_mut()) {
+            custom_walls.edit(timer, serial, accelerometer);
+        }
+        display_image(&BitImage::new(&icons::PAUSE));
+        timer.start(FRAME_MS * 1000);
+    }
+}
 
 #[entry]
+// A controller build never gets past setting up the radio
+#[cfg_attr(feature = "controller", allow(unreachable_code, unused_variables, unused_mut))]
 fn main() -> ! {
     rtt_init_print!();
     let mut board = Board::take().unwrap();
+    init_budget(&mut board.DCB, &mut board.DWT);
     let mut timer = Timer::new(board.TIMER0).into_periodic();
-    let mut rng = Rng::new(board.RNG);
-    let mut game = Game::new(rng.random_u32());
+    let mut seeds = boot_seeds(Rng::new(board.RNG), board.TEMP);
+    #[cfg(feature = "fuzz")]
+    run_fuzz(seeds.next_u32());
+
+    // Holding A at startup shows which optional features this firmware was built with, holding B
+    // shows gameplay statistics and holding both waits for a game to be handed off from another
+    // board
+    let a_held = board.buttons.button_a.is_low().unwrap();
+    let b_held = board.buttons.button_b.is_low().unwrap();
+
+    #[cfg(feature = "persist")]
+    let mut storage = Storage::new(board.NVMC);
+    #[cfg(feature = "persist")]
+    let mut stats = Stats::load(&mut storage);
+    #[cfg(feature = "persist")]
+    let mut config = GameConfig::load(&mut storage);
+    #[cfg(feature = "persist")]
+    let mut daily_best = DailyBest::load(&mut storage);
+    #[cfg(feature = "persist")]
+    let mut high_scores = HighScores::load(&mut storage, Record::HighScores);
+    #[cfg(feature = "persist")]
+    let mut blitz_scores = HighScores::load(&mut storage, Record::BlitzHighScores);
+    #[cfg(feature = "persist")]
+    let mut custom_walls = CustomWalls::load(&mut storage);
+    #[cfg(not(feature = "persist"))]
+    let mut stats = Stats::default();
+    #[cfg(not(feature = "persist"))]
+    let mut config = GameConfig::default();
+    #[cfg(not(feature = "persist"))]
+    let mut daily_best = DailyBest::default();
+    #[cfg(not(feature = "persist"))]
+    let mut high_scores = HighScores::default();
+    #[cfg(not(feature = "persist"))]
+    let mut blitz_scores = HighScores::default();
+    #[cfg(not(feature = "persist"))]
+    let mut custom_walls = CustomWalls::default();
 
-    init_buttons(board.GPIOTE, board.buttons);
+    // With the buttons swapped, holding the physical B button at startup counts as holding A
+    let (a_held, b_held) = if config.swap_buttons { (b_held, a_held) } else { (a_held, b_held) };
+    init_buttons(
+        board.GPIOTE,
+        board.buttons,
+        #[cfg(feature = "pads")]
+        [board.edge.e00.degrade(), board.edge.e01.degrade(), board.edge.e02.degrade()]
+    );
+    set_buttons_swapped(config.swap_buttons);
+    set_action_map(config.actions);
+    let mut logo = TouchLogo::new(board.pins.p1_04);
+    #[cfg(feature = "audio")]
+    let mut beeper = Beeper::new(board.PWM0, board.speaker_pin);
     init_display(board.TIMER1, board.display_pins);
+    set_brightness(config.brightness);
+    set_rotation(config.rotation);
+    // Where the game is shown during play. The board's own LEDs mirror any external displays
+    let screen = LedMatrix::default();
+    #[cfg(feature = "max7219")]
+    let screen = (screen, Max7219::new(board.pins.p0_17, board.pins.p0_13, board.pins.p1_02));
+    // SAFETY: `Board` doesn't hand out TWIM1, and nothing else uses it or the peripherals that
+    // share its resources (SPIM1, SPIS1 and TWIS1)
+    #[cfg(feature = "ssd1306")]
+    let twim1 = unsafe { microbit::pac::Peripherals::steal() }.TWIM1;
+    #[cfg(feature = "ssd1306")]
+    let screen = (screen, Ssd1306::new(twim1, board.i2c_external));
+    #[cfg(feature = "neopixel")]
+    let screen = (screen, NeoPixels::new(board.PWM1, board.pins.p0_12));
+    let mut screen = screen;
+    #[cfg(feature = "ascii")]
+    let mut ascii_art = AsciiArt::new(Rtt);
+    let mut game = fresh_game(&mut timer, &mut seeds, &config);
 
+    let (accelerometer, board_id) = probe_internal_bus(board.TWIM0, board.i2c_internal, &mut timer);
+    #[cfg(feature = "joystick")]
+    let mut joystick = Joystick::new(board.SAADC, board.edge.e00, board.edge.e01);
+    #[cfg(feature = "joystick")]
+    let joystick_present = joystick.is_some();
+    #[cfg(not(feature = "joystick"))]
+    let joystick_present = false;
+    let capabilities =
+        Capabilities::new(&accelerometer, board_id, logo.is_present(), joystick_present);
+    if !capabilities.touch_logo {
+        rprintln!("Touch logo not found, so it will be ignored until the board is restarted");
+    }
+    #[cfg(feature = "tilt")]
+    let mut accelerometer = accelerometer;
+
+    // The RTC needs the low frequency clock, and the radio needs the external high frequency
+    // oscillator
+    #[cfg(feature = "radio")]
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc().start_lfclk();
+    #[cfg(not(feature = "radio"))]
+    Clocks::new(board.CLOCK).start_lfclk();
+    init_clock(board.RTC0);
+    let mut serial = SerialPort::new(board.UARTE0, board.uart);
+    #[cfg(feature = "radio")]
+    let mut link = RadioLink::new(board.RADIO, &clocks, board.TIMER2);
+    #[cfg(feature = "controller")]
+    run_controller(&mut timer, &mut link, board.SAADC);
+    #[cfg(feature = "radio")]
+    let mut remote = RemoteControl::new();
+
+    // The kind of game being played, and the date of the daily puzzle if that's what it is
+    let mut playing = GameMode::Normal;
+    let mut puzzle_date: Option<Date> = None;
+    // Whether the coming game is one carried on from another board, rather than a new one
+    let mut resumed = false;
+
+    match (a_held, b_held) {
+        (true, false) => {
+            show_features(&mut timer, &capabilities);
+            #[cfg(feature = "persist")]
+            show_flash_wear(&mut timer, &storage);
+        },
+        (false, true) => show_stats(&mut timer, &stats),
+        #[cfg(feature = "radio")]
+        (true, true) => {
+            display_image(&BitImage::new(&icons::RADIO));
+            if let Some(received) = receive_game(&mut link, HANDOFF_WAIT_MS) {
+                // Carried on in the mode it was started in, so it's scored as a game of that mode
+                playing = received.rules().mode();
+                game = received;
+                resumed = true;
+            }
+            clear_display();
+        },
+        _ => ()
+    }
+
+    let mut overrides = SessionOverrides::default();
+    // Without a speaker, sound stays off whatever the saved setting
+    if !capabilities.speaker {
+        overrides.sound = Some(false);
+    }
+    let mut dev = DevSettings::default();
+    // Kept between games, so that the compass only has to be calibrated once
+    #[cfg(feature = "compass")]
+    let mut compass_steering = CompassSteering::new();
 
     loop {
+        set_turn_on_press(false);
+        #[cfg(feature = "persist")]
+        let old_config = config;
+        title_screen(
+            &mut timer,
+            &mut serial,
+            &mut config,
+            &mut logo,
+            &mut dev,
+            &mut overrides,
+            &capabilities
+        );
+        #[cfg(feature = "persist")]
+        if config != old_config {
+            config.save(&mut storage);
+        }
+        countdown(
+            &mut timer,
+            &mut serial,
+            &config,
+            &mut overrides,
+            resumed,
+            #[cfg(feature = "audio")] capabilities.speaker,
+            #[cfg(feature = "audio")] &mut beeper
+        );
+        // Restarts asked for before the game began would only throw it away straight away
+        serial.take_restart();
+        // A game carried on from another board keeps the settings it was started with. Cheats can
+        // still be turned on for it, but not off, so that a game played with one never counts
+        if resumed {
+            game.set_invincible(game.is_invincible() || overrides.invincible);
+        } else {
+            game.set_wraparound(overrides.apply(config).wraparound);
+            game.set_bouncing_walls(config.bouncing_walls);
+            // Only now is it settled whether the snake can cross the edges, which decides where it
+            // can safely start
+            if config.random_start {
+                if let Err(e) = game.randomise_start() {
+                    show_error(&mut timer, e);
+                }
+            }
+            game.set_invincible(overrides.invincible);
+        }
+        // Started before the first step, so that it can be played back from the same state
+        let mut recording = if dev.record_inputs {
+            Recording::new(&game).map_err(|e| rprintln!("Can't record game: {:?}", e)).ok()
+        } else {
+            None
+        };
+        // Time elapsed since the last game step
+        let mut since_step_ms = 0;
+        // Time elapsed since the game loop started, for blinking the hunter
+        let mut shown_ms: u32 = 0;
+        #[cfg(feature = "tilt")]
+        let mut tilt_steering = TiltSteering::new();
+        // Number of markers logged during the game
+        let mut markers = 0;
+        // Whether the head and food are drawn brighter until the next step, after a near miss or
+        // on reaching a new level
+        let mut boosted = false;
+        // Leads the snake towards food placed where the LEDs can't show it
+        let mut trail = ScentTrail::default();
+        // What was shown before the last step, and how long to take over moving on from it
+        let mut slide: Option<([[u8; N_COLS]; N_ROWS], u32)> = None;
+        // Whether the snake's speed has been doubled (by the boost action or the boost pad) for
+        // the rest of the game
+        let mut sped_up = false;
+        // Checks that no frames are being dropped, until the player has been warned that they are
+        let mut frame_check = Some(FrameCheck::new(FRAME_MS));
+        timer.start(FRAME_MS * 1000);
         loop {  // Game loop
-            let image = GreyscaleImage::new(&game.game_matrix(6, 4, 9));
-            display_image(&image);
-            timer.delay_ms(game.step_len_ms());
+            // Menus opened while paused turn this off, and it can be changed over serial
+            set_turn_on_press(config.turn_on_press);
+            // High contrast would show the trail at full brightness, like everything else, so it
+            // is left out
+            let trail_layer =
+                if config.high_contrast { [[0; N_COLS]; N_ROWS] } else { trail.layer() };
+            let stepped_to = Compositor::new()
+                .layer(&trail_layer)
+                .layer(&game.tile_matrix(0, 0, 0, 2, 0))
+                .layer(&snake_layer(&game, boost(6, boosted)))
+                .layer(&game.tile_matrix(0, 0, boost(9, boosted), 0, 0))
+                .frame();
+            // The head slides into its new tile, rather than jumping, by fading between the
+            // matrices from either side of the step. High contrast has no levels to fade through
+            let mut matrix = match slide {
+                Some((from, slide_ms)) if !config.high_contrast => {
+                    blend_matrix(&from, &stepped_to, since_step_ms + FRAME_MS, slide_ms)
+                },
+                _ => stepped_to
+            };
+            if config.high_contrast {
+                let blink_ms =
+                    if config.reduced_flashing { CALM_FOOD_BLINK_MS } else { FOOD_BLINK_MS };
+                let food = game.tile_matrix(false, false, true, false, false);
+                high_contrast(&mut matrix, &food, (shown_ms / blink_ms) % 2 == 0);
+            }
+            // Effects go over everything else, even in high contrast
+            let mut effects = Compositor::new().layer(&matrix);
+            let mut colours = game.tile_matrix(
+                HEAD_COLOUR,
+                TAIL_COLOUR,
+                FOOD_COLOUR,
+                WALL_COLOUR,
+                Colour::default()
+            );
+            // What is on each tile, for displays that can't tell things apart by brightness
+            let mut tiles =
+                game.tile_matrix(Tile::Head, Tile::Tail, Tile::Food, Tile::Wall, Tile::Empty);
+            for (tiles_row, trail_row) in tiles.iter_mut().zip(trail_layer.iter()) {
+                for (tile, &strength) in tiles_row.iter_mut().zip(trail_row.iter()) {
+                    if *tile == Tile::Empty && strength > 0 {
+                        *tile = Tile::Trail;
+                    }
+                }
+            }
+            if let Some((row, col)) = game.hunter() {
+                colours[row][col] = HUNTER_COLOUR;
+                tiles[row][col] = Tile::Hunter;
+                let blink_ms =
+                    if config.reduced_flashing { CALM_HUNTER_BLINK_MS } else { HUNTER_BLINK_MS };
+                if (shown_ms / blink_ms) % 2 == 0 {
+                    effects = effects.tile(row, col, 9);
+                }
+            }
+            let matrix = effects.frame();
+            if dev.capture_frames {
+                Ascii(Rtt).present(&matrix);
+            }
+            #[cfg(feature = "ascii")]
+            ascii_art.present_game(&game);
+            let (head_row, head_col) = game.head();
+            screen.follow(head_row, head_col);
+            within_budget("screen", || {
+                screen.present_tiles(&matrix, &colours, &tiles);
+                screen.present_score(game.score());
+            });
+            wait_frame(&mut timer, &mut serial);
+            if frame_check.as_mut().and_then(FrameCheck::frame) == Some(false) {
+                rprintln!("Game loop is dropping frames");
+                flash_status(&mut timer, icons::LAGGING);
+                frame_check = None;
+            }
+            #[cfg(feature = "tilt")]
+            if let (true, Some(accelerometer)) = (config.tilt_steering, accelerometer.as_mut()) {
+                within_budget("tilt", || tilt_steering.poll(accelerometer, FRAME_MS));
+                // Nothing else steers while tilting
+                discard_turns();
+                gather_input(&mut tilt_steering);
+            }
+            #[cfg(feature = "compass")]
+            if let (true, Some(sensor)) = (overrides.compass_steering, accelerometer.as_mut()) {
+                within_budget("compass", || compass_steering.poll(sensor, FRAME_MS));
+                // Nothing else steers while steering by compass, not even tilting
+                discard_turns();
+                gather_input(&mut compass_steering);
+            }
+            #[cfg(feature = "joystick")]
+            if let Some(joystick) = joystick.as_mut() {
+                within_budget("joystick", || joystick.poll());
+                gather_input(joystick);
+            }
+            #[cfg(feature = "radio")]
+            {
+                match within_budget("remote", || remote.poll(&mut link)) {
+                    Some(RemoteNotice::Linked) => {
+                        show_linked(&mut timer, &mut link);
+                        if let Some(check) = frame_check.as_mut() {
+                            check.restart();
+                        }
+                    },
+                    Some(RemoteNotice::LowBattery) => {
+                        rprintln!("Controller's batteries are running out");
+                        flash_status(&mut timer, icons::BATTERY);
+                    },
+                    None => ()
+                }
+                gather_input(&mut remote);
+            }
+            #[cfg(feature = "persist")]
+            let old_config = config;
+            // The mute pad turns level beeps off, or back on, as if changed over serial
+            #[cfg(feature = "pads")]
+            if take_event(InputEvent::Mute) {
+                config.level_beeps = !config.level_beeps;
+                #[cfg(feature = "audio")]
+                beeper.stop();
+                let icon = if config.level_beeps { icons::SOUND } else { icons::MUTE };
+                flash_status(&mut timer, icon);
+            }
+            #[cfg(feature = "pads")]
+            if take_event(InputEvent::Boost) {
+                sped_up = !sped_up;
+            }
+            // Not timed, as replies are written out at the UART's pace (see the budget module)
+            #[cfg_attr(not(feature = "persist"), allow(unused_variables))]
+            let scores_changed = handle_commands(
+                &mut serial,
+                &mut config,
+                &mut high_scores,
+                &mut blitz_scores,
+                &mut dev,
+                &mut game
+            );
+            // Saved to flash at the next idle time
+            #[cfg(feature = "persist")]
+            if config != old_config {
+                config.save(&mut storage);
+            }
+            #[cfg(feature = "persist")]
+            if scores_changed {
+                high_scores.save(&mut storage, Record::HighScores);
+                blitz_scores.save(&mut storage, Record::BlitzHighScores);
+            }
+            if serial.take_restart() {
+                // Abandoned games aren't recorded, like those that end in an error
+                #[cfg(feature = "audio")]
+                beeper.stop();
+                break;
+            }
+            let logo_tapped = logo.poll();
+            // Holding the logo turns a chord into a marker for testers, which doesn't stop the
+            // game, whatever the chord would otherwise do
+            if logo.is_touched() && take_chord() {
+                markers += 1;
+                log_marker(markers, shown_ms, &game, &matrix);
+                if let Some(recording) = recording.as_ref() {
+                    log_recording(recording, playing);
+                }
+                logo.cancel_tap();
+            }
+            let tap_action = if logo_tapped {
+                config.actions.action(Gesture::LogoTap)
+            } else {
+                None
+            };
+            let mut pausing = false;
+            // Holding a button keeps turning instead, with hold-to-turn on, and can't be told
+            // apart from a press that has already turned, with turning on press
+            let long_presses = !config.hold_to_turn && !config.turn_on_press;
+            let actions = core::iter::from_fn(|| take_action(long_presses));
+            for action in tap_action.into_iter().chain(actions) {
+                match action {
+                    Action::Pause => pausing = true,
+                    Action::Boost => sped_up = !sped_up,
+                    // Taken at the next step instead
+                    Action::TurnLeft | Action::TurnRight => ()
+                }
+            }
+            if matches!(game.status, GameStatus::Ongoing) && pausing {
+                #[cfg(feature = "audio")]
+                beeper.stop();
+                #[cfg(feature = "persist")]
+                let (old_config, old_custom_walls) = (config, custom_walls);
+                #[cfg(all(feature = "persist", feature = "fonts"))]
+                let old_scores = (high_scores, blitz_scores);
+                let outcome = pause(
+                    &mut timer,
+                    &mut serial,
+                    &mut config,
+                    &mut custom_walls,
+                    &mut logo,
+                    &mut dev,
+                    &mut overrides,
+                    #[cfg(feature = "fonts")] &mut high_scores,
+                    #[cfg(feature = "fonts")] &mut blitz_scores,
+                    #[cfg(feature = "tilt")] &mut accelerometer,
+                    #[cfg(feature = "radio")] &game,
+                    #[cfg(feature = "radio")] &mut link
+                );
+                #[cfg(feature = "persist")]
+                if config != old_config {
+                    config.save(&mut storage);
+                }
+                #[cfg(feature = "persist")]
+                if custom_walls != old_custom_walls {
+                    custom_walls.save(&mut storage);
+                }
+                #[cfg(all(feature = "persist", feature = "fonts"))]
+                if (high_scores, blitz_scores) != old_scores {
+                    high_scores.save(&mut storage, Record::HighScores);
+                    blitz_scores.save(&mut storage, Record::BlitzHighScores);
+                }
+                // Play hasn't restarted yet, so this is a good time to write to flash
+                #[cfg(feature = "persist")]
+                flush_storage(&mut storage);
+                // Cheats take effect straight away, but once on they stay on for the rest of the
+                // game
+                game.set_invincible(game.is_invincible() || overrides.invincible);
+                match outcome {
+                    PauseOutcome::Resumed => {
+                        if let Some(check) = frame_check.as_mut() {
+                            check.restart();
+                        }
+                        continue
+                    },
+                    // The game continues on the other board, so start a new one here
+                    #[cfg(feature = "radio")]
+                    PauseOutcome::HandedOff => break
+                }
+            }
+            #[cfg(feature = "audio")]
+            beeper.poll(FRAME_MS);
+            since_step_ms += FRAME_MS;
+            shown_ms = shown_ms.wrapping_add(FRAME_MS);
+            let step_len_ms = game.step_len_ms();
+            let step_len_ms = if sped_up { step_len_ms / 2 } else { step_len_ms };
+            if since_step_ms < step_len_ms {
+                continue;
+            }
+            since_step_ms = 0;
+            slide = Some((stepped_to, (step_len_ms / 2).min(MAX_SLIDE_MS)));
+            // Each step goes through the same stages in the same order (see `Game::step`): the
+            // turn is taken first, then the step, then any sound it calls for, and the next frame
+            // is drawn last
             match game.status {
-                GameStatus::Ongoing => game.step(get_turn(true)),
+                GameStatus::Ongoing => {
+                    if config.hold_to_turn {
+                        repeat_held_turns();
+                    }
+                    let mut turn = next_turn(config.control_scheme, game.direction());
+                    let (loops, level, danger) = (game.loops(), game.level(), game.danger_ahead());
+                    let food = game.food();
+                    #[cfg(feature = "audio")]
+                    let speed = game.speed();
+                    let forced = dev.next_outcome.take();
+                    if forced == Some(ForcedOutcome::Eat) {
+                        let moved = match game.tile_ahead() {
+                            Some((row, col)) => game.move_food(row, col).unwrap_or(false),
+                            None => false
+                        };
+                        if moved {
+                            // The snake has to carry straight on to eat it
+                            turn = Turn::None;
+                        } else {
+                            rprintln!("No room to put food in the snake's path");
+                        }
+                    }
+                    if let Some(recording) = recording.as_mut() {
+                        recording.record(turn);
+                    }
+                    let stepped = within_budget("step", || {
+                        game.step(turn)?;
+                        // Ending the game here lets it end as usual, as if played to the end
+                        match forced {
+                            Some(ForcedOutcome::Collision) => game.status = GameStatus::Lost,
+                            Some(ForcedOutcome::Win) => game.status = GameStatus::Won,
+                            _ => ()
+                        }
+                        if dev.check_invariants {
+                            game.check_invariants().map_err(GameError::InvariantViolated)?;
+                        }
+                        Ok(())
+                    });
+                    if let Err(e) = stepped {
+                        // The game can't safely continue, so abandon it without recording it
+                        #[cfg(feature = "audio")]
+                        beeper.stop();
+                        show_error(&mut timer, e);
+                        break;
+                    }
+                    #[cfg(feature = "latency")]
+                    if let Some(latency_ms) = take_turn_latency_ms() {
+                        rprintln!("Turn applied {}ms after it was made", latency_ms);
+                    }
+                    stats.record_step(step_len_ms);
+                    serial.echo_game(&game);
+                    trail.fade();
+                    if config.food_trails && game.food() != food {
+                        trail.lay(game.head(), game.food());
+                    }
+                    if dev.verbose {
+                        rprintln!(
+                            "Step: score {}, level {}, head {:?}, heading {:?}, length {}",
+                            game.score(),
+                            game.level(),
+                            game.head(),
+                            game.direction(),
+                            game.snake_len()
+                        );
+                    }
+                    // Reduced flashing does without the sudden brightening
+                    boosted = !config.reduced_flashing
+                        && ((danger && game.status == GameStatus::Ongoing) || game.level() > level);
+                    #[cfg(feature = "audio")]
+                    if overrides.apply(config).sound && config.level_beeps && game.speed() > speed {
+                        beeper.play(game.speed());
+                    }
+                    if game.loops() > loops {
+                        #[cfg(feature = "audio")]
+                        beeper.stop();
+                        celebrate_loop(&mut timer, game.loops(), config.reduced_flashing);
+                        if let Some(check) = frame_check.as_mut() {
+                            check.restart();
+                        }
+                    }
+                },
                 _ => {
-                    for _ in 0..3 {
-                        clear_display();
-                        timer.delay_ms(200u32);
-                        display_image(&image);
-                        timer.delay_ms(200u32);
-                    }
-                    clear_display();
-                    display_image(&BitImage::new(&game.score_matrix()));
-                    timer.delay_ms(2000u32);
+                    #[cfg(feature = "audio")]
+                    beeper.stop();
+                    stats.record_game(&game);
+                    #[cfg(feature = "persist")]
+                    stats.save(&mut storage);
+                    // Dump the final game state over RTT, to help with debugging
+                    let mut snapshot = [0u8; MAX_SNAPSHOT_LEN];
+                    if let Ok(len) = game.serialize(&mut snapshot) {
+                        rprintln!("Game state: {:02x?}", &snapshot[..len]);
+                    }
+                    if let Some(recording) = recording.as_ref() {
+                        log_recording(recording, playing);
+                    }
+                    // Games played with a cheat don't count towards any records
+                    let cheated = game.is_invincible();
+                    let daily = puzzle_date.filter(|_| !cheated).map(|date| Best {
+                        icon: icons::CALENDAR,
+                        score: daily_best.record(date, game.score()),
+                        beaten: false
+                    });
+                    #[cfg(feature = "persist")]
+                    if daily.is_some() {
+                        daily_best.save(&mut storage);
+                    }
+                    // Blitz games score differently, so have a table of their own. Marathon games
+                    // have no limit on their scores, and hard and penalty games are played by
+                    // different rules, so none of them are recorded
+                    let best = match playing {
+                        _ if cheated => None,
+                        GameMode::Normal => {
+                            let pos = high_scores.record(game.score(), now());
+                            #[cfg(feature = "persist")]
+                            if pos.is_some() {
+                                high_scores.save(&mut storage, Record::HighScores);
+                            }
+                            high_scores.best().map(|score| {
+                                Best { icon: icons::TROPHY, score, beaten: pos == Some(0) }
+                            })
+                        },
+                        GameMode::Blitz => {
+                            let pos = blitz_scores.record(game.score(), now());
+                            #[cfg(feature = "persist")]
+                            if pos.is_some() {
+                                blitz_scores.save(&mut storage, Record::BlitzHighScores);
+                            }
+                            blitz_scores.best().map(|score| {
+                                Best { icon: icons::BLITZ, score, beaten: pos == Some(0) }
+                            })
+                        },
+                        GameMode::Daily => daily,
+                        GameMode::Custom
+                        | GameMode::Marathon
+                        | GameMode::Hard
+                        | GameMode::Penalty => None
+                    };
+                    let code = share_code(playing, game.score()).filter(|_| !cheated);
+                    if let Some(code) = code {
+                        announce(&mut serial, &code);
+                    }
+                    #[cfg(feature = "persist")]
+                    flush_storage(&mut storage);
+                    show_game_over(
+                        &mut timer,
+                        &matrix,
+                        &game,
+                        best,
+                        config.reduced_flashing,
+                        #[cfg(feature = "tilt")] &mut accelerometer
+                    );
                     break
                 }
             }
         }
-        game.reset();
+        puzzle_date = today().filter(|_| config.mode == GameMode::Daily);
+        let started = match (config.mode, puzzle_date) {
+            (GameMode::Daily, Some(date)) => daily_game(date).map(|new| {
+                game = new.with_rules(ModeRules::new(GameMode::Daily, &config));
+                GameMode::Daily
+            }),
+            (GameMode::Custom, _) => custom_walls.game(seeds.next_u32()).map(|new| {
+                game = new.with_rules(ModeRules::new(GameMode::Custom, &config));
+                GameMode::Custom
+            }),
+            (mode, _) => {
+                // Without a date, the daily puzzle falls back to a normal game
+                let mode = match mode {
+                    GameMode::Blitz
+                    | GameMode::Marathon
+                    | GameMode::Hard
+                    | GameMode::Penalty => mode,
+                    _ => GameMode::Normal
+                };
+                if playing == GameMode::Daily {
+                    // The daily puzzle's PRNG was seeded from the date, so reseed from hardware
+                    Game::new(seeds.next_u32()).map(|new| {
+                        game = new.with_rules(ModeRules::new(mode, &config));
+                        mode
+                    })
+                } else {
+                    *game.rules_mut() = ModeRules::new(mode, &config);
+                    game.reset().map(|_| mode)
+                }
+            }
+        };
+        resumed = false;
+        playing = match started {
+            Ok(mode) => mode,
+            Err(e) => {
+                show_error(&mut timer, e);
+                puzzle_date = None;
+                game = fresh_game(&mut timer, &mut seeds, &config);
+                GameMode::Normal
+            }
+        };
+        clear_events();
     }
 }
@@ -0,0 +1,120 @@
+//! An external 8x8 LED matrix driven by a MAX7219, wired to the edge connector: its CLK to pin 13,
+//! DIN to pin 15, CS to pin 16 and its supply to 3V and GND. The chip only takes data in, over
+//! SPI, which is clocked out on the pins directly as it only takes a few microseconds a frame.
+//!
+//! The `max7219` feature makes the game's grid 8x8, the size of the panel, so each tile has an LED
+//! of its own; the board's own LEDs only have room for the part of the grid around the snake's
+//! head. The MAX7219 can only turn each LED on or off, so walls and scent trails blink, to tell
+//! them apart from the snake, food and hunter, which are lit steadily.
+
+use microbit::hal::gpio::p0::{P0_13, P0_17};
+use microbit::hal::gpio::p1::P1_02;
+use microbit::hal::gpio::{Disconnected, Level, Output, Pin, PushPull};
+use microbit::hal::prelude::*;
+use snakebit_core::game::{N_COLS, N_ROWS};
+
+use crate::render::{Colour, Renderer, Tile};
+
+/// Number of rows and columns of LEDs on the panel.
+const PANEL_SIZE: usize = 8;
+/// Number of frames that blinking LEDs spend on, and then off, as they blink.
+const BLINK_FRAMES: u32 = 10;
+
+/// Register holding the first row of the panel. The rest follow it.
+const ROW_REGISTER: u8 = 0x01;
+/// Registers set up when the panel is started, with their values: no BCD decoding, a middling
+/// intensity, all eight rows scanned, the display test off and the chip out of shutdown.
+const SETUP: [(u8, u8); 5] = [
+    (0x09, 0x00),
+    (0x0a, 0x07),
+    (0x0b, 0x07),
+    (0x0f, 0x00),
+    (0x0c, 0x01)
+];
+
+// Check at compile time that the game's grid is the size of the panel
+const _: () = assert!(N_ROWS == PANEL_SIZE && N_COLS == PANEL_SIZE);
+
+/// A MAX7219 panel on the edge connector.
+pub(crate) struct Max7219 {
+    clk: Pin<Output<PushPull>>,
+    din: Pin<Output<PushPull>>,
+    cs: Pin<Output<PushPull>>,
+    /// Number of frames presented, for blinking.
+    frames: u32
+}
+
+impl Max7219 {
+    /// Set up the panel, clearing it. Nothing can be read back from the chip, so there is no way
+    /// to tell whether a panel is actually connected.
+    pub(crate) fn new(
+        clk: P0_17<Disconnected>,
+        din: P0_13<Disconnected>,
+        cs: P1_02<Disconnected>
+    ) -> Self {
+        let mut panel = Self {
+            clk: clk.into_push_pull_output(Level::Low).degrade(),
+            din: din.into_push_pull_output(Level::Low).degrade(),
+            cs: cs.into_push_pull_output(Level::High).degrade(),
+            frames: 0
+        };
+        for &(register, value) in SETUP.iter() {
+            panel.write(register, value);
+        }
+        panel.present(&[[0; N_COLS]; N_ROWS]);
+        panel
+    }
+
+    /// Write `value` to `register`, most significant bit first. The chip latches it when CS goes
+    /// back up.
+    fn write(&mut self, register: u8, value: u8) {
+        let word = u16::from_be_bytes([register, value]);
+        self.cs.set_low().ok();
+        for bit in (0..16).rev() {
+            if (word >> bit) & 1 == 1 {
+                self.din.set_high().ok();
+            } else {
+                self.din.set_low().ok();
+            }
+            self.clk.set_high().ok();
+            self.clk.set_low().ok();
+        }
+        self.cs.set_high().ok();
+    }
+
+    /// Light the LEDs for the tiles of `frame` that aren't off, each if `lit` returns true for its
+    /// row and column.
+    fn show(&mut self, frame: &[[u8; N_COLS]; N_ROWS], lit: impl Fn(usize, usize) -> bool) {
+        for (row, cells) in frame.iter().enumerate() {
+            // Column 0 is the most significant bit
+            let mut bits = 0u8;
+            for (col, &brightness) in cells.iter().enumerate() {
+                if brightness > 0 && lit(row, col) {
+                    bits |= 0x80 >> col;
+                }
+            }
+            self.write(ROW_REGISTER + row as u8, bits);
+        }
+    }
+}
+
+impl Renderer for Max7219 {
+    /// Not knowing what is on each tile, light every LED that isn't off steadily.
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]) {
+        self.show(frame, |_, _| true);
+    }
+
+    fn present_tiles(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        _colours: &[[Colour; N_COLS]; N_ROWS],
+        tiles: &[[Tile; N_COLS]; N_ROWS]
+    ) {
+        let blink_lit = (self.frames / BLINK_FRAMES) % 2 == 0;
+        self.frames = self.frames.wrapping_add(1);
+        self.show(frame, |row, col| match tiles[row][col] {
+            Tile::Wall | Tile::Trail => blink_lit,
+            _ => true
+        });
+    }
+}
@@ -0,0 +1,118 @@
+//! An external 8x8 panel of WS2812 ("NeoPixel") LEDs, with its data input wired to pin 12 of the
+//! edge connector and powered separately (a full panel draws far more than the board can supply),
+//! with its GND joined to the board's. The LEDs are chained row by row, left to right, from the
+//! top left.
+//!
+//! Each LED takes 24 bits of colour, sent one after another as pulses 1.25µs apart, where a long
+//! pulse is a 1 and a short one a 0. The PWM peripheral plays these pulses from a buffer by DMA,
+//! one PWM period per bit, so the timing doesn't depend on the CPU.
+//!
+//! The game's grid is drawn in the middle of the panel (which an 8x8 grid fills), in colour: the
+//! snake green (its head yellow), food red, walls blue and the hunter magenta.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::gpio::p0::P0_12;
+use microbit::hal::gpio::{Disconnected, Level};
+use microbit::hal::pwm::{Channel, LoadMode, Prescaler, Pwm, PwmEvent, Seq};
+use microbit::pac::PWM1;
+use snakebit_core::game::{N_COLS, N_ROWS};
+
+use crate::render::{Colour, Renderer};
+
+/// Number of rows and columns of LEDs on the panel.
+const PANEL_SIZE: usize = 8;
+/// Offset of the game's grid from the top of the panel, to centre it.
+const ROW_OFFSET: usize = (PANEL_SIZE - N_ROWS) / 2;
+/// Offset of the game's grid from the left of the panel, likewise.
+const COL_OFFSET: usize = (PANEL_SIZE - N_COLS) / 2;
+/// Length of each bit, in ticks of the PWM's 16MHz clock.
+const BIT_TICKS: u16 = 20;
+/// Duty cycle sending a 0: high for the first 0.375µs of the bit, then low. The top bit makes the
+/// output start each period high.
+const ZERO: u16 = 0x8000 | 6;
+/// Duty cycle sending a 1: high for the first 0.8125µs of the bit, then low.
+const ONE: u16 = 0x8000 | 13;
+/// Duty cycle holding the output low for a whole period.
+const LOW: u16 = 0x8000;
+/// Number of low periods after the colours, long enough (at over 50µs) for the LEDs to latch
+/// them.
+const RESET_PERIODS: usize = 48;
+/// Number of duty cycles in a whole update of the panel.
+const WORDS: usize = PANEL_SIZE * PANEL_SIZE * 24 + RESET_PERIODS;
+/// Colour of tiles shown by [`Renderer::present`], which has no colours to go by.
+const WHITE: Colour = Colour::new(48, 48, 48);
+
+/// A WS2812 panel on the edge connector.
+pub(crate) struct NeoPixels {
+    pwm: Pwm<PWM1>,
+    /// Duty cycles for the PWM to play, one for each bit sent to the panel.
+    words: [u16; WORDS],
+    /// The colours last sent to the panel, if any have been.
+    shown: Option<[[Colour; PANEL_SIZE]; PANEL_SIZE]>
+}
+
+impl NeoPixels {
+    /// Set up the panel, turning all of its LEDs off. Nothing can be read back from the panel, so
+    /// there is no way to tell whether one is actually connected.
+    pub(crate) fn new(board_pwm: PWM1, pin: P0_12<Disconnected>) -> Self {
+        let pin = pin.into_push_pull_output(Level::Low).degrade();
+        let pwm = Pwm::new(board_pwm);
+        pwm.set_output_pin(Channel::C0, pin)
+            .set_prescaler(Prescaler::Div1)
+            .set_max_duty(BIT_TICKS)
+            .set_load_mode(LoadMode::Common)
+            .one_shot();
+        let mut panel = Self { pwm, words: [LOW; WORDS], shown: None };
+        panel.show([[Colour::default(); PANEL_SIZE]; PANEL_SIZE]);
+        panel
+    }
+
+    /// Send `colours` to the panel, unless they are already showing, waiting until they have all
+    /// been sent (about 2ms).
+    fn show(&mut self, colours: [[Colour; PANEL_SIZE]; PANEL_SIZE]) {
+        if self.shown == Some(colours) {
+            return;
+        }
+        self.shown = Some(colours);
+        // The LEDs take green first, then red, then blue, each most significant bit first
+        let bits = colours
+            .iter()
+            .flatten()
+            .flat_map(|c| [c.green, c.red, c.blue])
+            .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1));
+        for (word, bit) in self.words.iter_mut().zip(bits) {
+            *word = if bit { ONE } else { ZERO };
+        }
+        // The PWM reads the buffer by DMA, which the HAL only offers for static buffers, so the
+        // sequence is pointed at it directly. It is only read until the sequence ends, below
+        let pwm = unsafe { &*PWM1::ptr() };
+        compiler_fence(Ordering::SeqCst);
+        pwm.seq0.ptr.write(|w| unsafe { w.bits(self.words.as_ptr() as u32) });
+        pwm.seq0.cnt.write(|w| unsafe { w.bits(WORDS as u32) });
+        self.pwm.start_seq(Seq::Seq0);
+        while !self.pwm.is_event_triggered(PwmEvent::SeqEnd(Seq::Seq0)) {}
+        self.pwm.reset_event(PwmEvent::SeqEnd(Seq::Seq0));
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl Renderer for NeoPixels {
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]) {
+        self.present_coloured(frame, &[[WHITE; N_COLS]; N_ROWS]);
+    }
+
+    fn present_coloured(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        colours: &[[Colour; N_COLS]; N_ROWS]
+    ) {
+        let mut panel = [[Colour::default(); PANEL_SIZE]; PANEL_SIZE];
+        for (row, (frame_row, colours_row)) in frame.iter().zip(colours.iter()).enumerate() {
+            let tiles = frame_row.iter().zip(colours_row.iter());
+            for (col, (&brightness, &colour)) in tiles.enumerate() {
+                panel[row + ROW_OFFSET][col + COL_OFFSET] = colour.dimmed(brightness);
+            }
+        }
+        self.show(panel);
+    }
+}
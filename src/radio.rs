@@ -0,0 +1,94 @@
+//! A simple packet link between micro:bits, using the nRF52's IEEE 802.15.4 radio mode.
+//!
+//! Each packet starts with a magic byte identifying it as ours, followed by a byte giving the kind
+//! of packet and then the packet's payload.
+
+use cortex_m::peripheral::DWT;
+use microbit::hal::clocks::{Clocks, ExternalOscillator, Internal, LfOscStarted};
+use microbit::hal::ieee802154::{Channel, Packet, Radio};
+use microbit::hal::Timer;
+use microbit::pac::{RADIO, TIMER2};
+
+use crate::budget::us_since;
+
+/// Identifies packets sent by snakebit (as opposed to other devices using the same channel).
+const MAGIC: u8 = 0x5b;
+/// Number of bytes preceding the payload.
+const HEADER_LEN: usize = 2;
+/// Maximum payload length (the 127 byte PSDU, less our header and the 2 byte CRC).
+pub(crate) const MAX_PAYLOAD_LEN: usize = 127 - 2 - HEADER_LEN;
+
+pub(crate) type RadioClocks = Clocks<ExternalOscillator, Internal, LfOscStarted>;
+
+/// The kinds of packet that can be sent.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum PacketKind {
+    /// A serialized game, offered to another board.
+    Handoff = 1,
+    /// Confirms that an offered game has been taken.
+    HandoffAck = 2,
+    /// The running counts of each kind of press made on a controller.
+    Remote = 3,
+    /// Asks a controller to reply at once, to time the round trip.
+    Ping = 4,
+    /// A controller's reply to a ping, carrying the ping's sequence number.
+    Pong = 5
+}
+
+pub(crate) struct RadioLink<'c> {
+    radio: Radio<'c>,
+    timer: Timer<TIMER2>,
+    packet: Packet
+}
+
+impl<'c> RadioLink<'c> {
+    pub(crate) fn new(board_radio: RADIO, clocks: &'c RadioClocks, board_timer: TIMER2) -> Self {
+        let mut radio = Radio::init(board_radio, clocks);
+        radio.set_channel(Channel::_20);
+        Self {
+            radio,
+            timer: Timer::new(board_timer),
+            packet: Packet::new()
+        }
+    }
+
+    /// Send a packet. `payload` must be no longer than [`MAX_PAYLOAD_LEN`].
+    pub(crate) fn send(&mut self, kind: PacketKind, payload: &[u8]) {
+        let mut buf = [0u8; HEADER_LEN + MAX_PAYLOAD_LEN];
+        buf[0] = MAGIC;
+        buf[1] = kind as u8;
+        buf[HEADER_LEN..HEADER_LEN + payload.len()].copy_from_slice(payload);
+        self.packet.copy_from_slice(&buf[..HEADER_LEN + payload.len()]);
+        self.radio.send(&mut self.packet);
+    }
+
+    /// Wait up to `timeout_us` microseconds for a packet of the given kind, ignoring any other
+    /// packets received in the meantime. If one arrives, its payload is copied into `buf`
+    /// (truncated if necessary) and the payload's length is returned.
+    pub(crate) fn receive(
+        &mut self,
+        kind: PacketKind,
+        buf: &mut [u8],
+        timeout_us: u32
+    ) -> Option<usize> {
+        let start = DWT::cycle_count();
+        loop {
+            let remaining_us = timeout_us.saturating_sub(us_since(start));
+            if remaining_us == 0 {
+                return None;
+            }
+            if self.radio.recv_timeout(&mut self.packet, &mut self.timer, remaining_us).is_err() {
+                // Timed out, which is checked for above, or failed its CRC
+                continue;
+            }
+            let data: &[u8] = &self.packet;
+            if data.len() < HEADER_LEN || data[0] != MAGIC || data[1] != kind as u8 {
+                continue;
+            }
+            let payload = &data[HEADER_LEN..];
+            let len = payload.len().min(buf.len());
+            buf[..len].copy_from_slice(&payload[..len]);
+            return Some(payload.len());
+        }
+    }
+}
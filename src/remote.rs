@@ -0,0 +1,225 @@
+//! Steering the snake from a second micro:bit over the radio. A board running a controller build
+//! sends its button presses, and the game board treats them like presses of its own buttons.
+//!
+//! Rather than a packet per press, which could be lost, the controller keeps a running count of
+//! each kind of press and broadcasts all three counts every few milliseconds. The game board only
+//! listens briefly each frame, and turns any increase in the counts since the last packet it heard
+//! into presses, so a press is only lost if the controller is switched off before it gets through.
+//! Repeated or out-of-date packets carry no increase, so are harmless. At most one press is passed
+//! on each frame, however many arrive at once; the rest wait for the frames after.
+//!
+//! Each packet also carries the controller's supply voltage, read every second or so, so that the
+//! game board can warn the player before the controller's batteries run out mid-game.
+//!
+//! Between packets, the controller listens for pings from the game board and answers each at once,
+//! so that the game board can time the round trip when the controller is first linked.
+
+use cortex_m::peripheral::DWT;
+#[cfg(feature = "controller")]
+use microbit::display::nonblocking::BitImage;
+#[cfg(feature = "controller")]
+use microbit::hal::prelude::*;
+#[cfg(feature = "controller")]
+use microbit::hal::saadc::{InternalVdd, Saadc, SaadcConfig};
+#[cfg(feature = "controller")]
+use microbit::hal::timer::{Periodic, Timer};
+#[cfg(feature = "controller")]
+use microbit::pac::{SAADC, TIMER0};
+
+use crate::budget::us_since;
+#[cfg(feature = "controller")]
+use crate::control::{next_event, settle_buttons};
+use crate::control::{InputEvent, InputSource};
+#[cfg(feature = "controller")]
+use crate::display::display_image;
+#[cfg(feature = "controller")]
+use crate::icons;
+use crate::radio::{PacketKind, RadioLink};
+
+/// The presses a controller sends, in the order their counts appear in its packets.
+const PRESSES: [InputEvent; 3] = [InputEvent::TurnLeft, InputEvent::TurnRight, InputEvent::Both];
+/// Length of a controller's packets: the count of each kind of press, then the controller's supply
+/// voltage in millivolts (little-endian, or 0 if it couldn't be read).
+const PACKET_LEN: usize = PRESSES.len() + 2;
+/// How long the game board listens for the controller each frame, in microseconds. The controller
+/// sends more often than this, so at least one of its packets should be heard.
+const LISTEN_US: u32 = 2_000;
+/// Interval between the controller's packets, in microseconds.
+#[cfg(feature = "controller")]
+const SEND_INTERVAL_US: u32 = 1_000;
+/// How long the controller listens for a ping after each packet, in microseconds. Sending a packet
+/// takes about half of [`SEND_INTERVAL_US`], so this is the rest of it.
+#[cfg(feature = "controller")]
+const PING_LISTEN_US: u32 = 500;
+/// Number of pings the game board sends to time the round trip. Pings sent while the controller
+/// is sending rather than listening go unanswered, so several are sent.
+const PINGS: u8 = 8;
+/// How long the game board waits for the answer to each ping, in microseconds.
+const PONG_WAIT_US: u32 = 5_000;
+/// Maximum number of presses of each kind from the controller waiting to be passed on. A bigger
+/// backlog means the controller has restarted and begun counting again, so it is dropped.
+const MAX_BACKLOG: u8 = 4;
+/// Number of packets the controller sends between readings of its supply voltage.
+#[cfg(feature = "controller")]
+const BATTERY_INTERVAL: u32 = 1_000_000 / SEND_INTERVAL_US;
+/// Full scale of the SAADC's default configuration (the internal 0.6V reference with a gain of
+/// 1/6), in millivolts.
+#[cfg(feature = "controller")]
+const FULL_SCALE_MV: i32 = 3600;
+/// Reading for [`FULL_SCALE_MV`], at the SAADC's default 14-bit resolution.
+#[cfg(feature = "controller")]
+const FULL_SCALE: i32 = 1 << 14;
+/// Supply voltage below which the controller's batteries are running out, in millivolts. A pair of
+/// fresh AA cells gives about 3V, and the board stops working at about 1.8V.
+const LOW_BATTERY_MV: u16 = 2200;
+/// Supply voltage the controller must get back up to, in millivolts, before running low again is
+/// warned about again, so that a voltage hovering around [`LOW_BATTERY_MV`] only warns once.
+const RECOVERED_BATTERY_MV: u16 = 2500;
+
+/// Something about the controller worth showing the player, noticed while listening for it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum RemoteNotice {
+    /// The first packet from a controller has been heard, so one has just been linked.
+    Linked,
+    /// The controller's batteries are running out.
+    LowBattery
+}
+
+/// Presses received from a controller.
+pub(crate) struct RemoteControl {
+    /// How many of each press have been passed on, in the controller's counting, or `None` if no
+    /// packet has been heard yet.
+    passed_on: Option<[u8; PRESSES.len()]>,
+    /// The press passed on this frame, if it hasn't been gathered yet.
+    press: Option<InputEvent>,
+    /// Whether the player has been warned that the controller's batteries are running out, since
+    /// they were last found to be fine.
+    battery_warned: bool
+}
+
+impl RemoteControl {
+    pub(crate) fn new() -> Self {
+        Self { passed_on: None, press: None, battery_warned: false }
+    }
+
+    /// Listen briefly for a packet from the controller, and pass on the oldest kind of press made
+    /// since the last one passed on, if any. Returns a notice if this is the first packet heard, or
+    /// if the controller's batteries have just been found to be running out.
+    pub(crate) fn poll(&mut self, link: &mut RadioLink) -> Option<RemoteNotice> {
+        let mut packet = [0u8; PACKET_LEN];
+        match link.receive(PacketKind::Remote, &mut packet, LISTEN_US) {
+            Some(len) if len == packet.len() => (),
+            _ => return None
+        }
+        let mut counts = [0u8; PRESSES.len()];
+        counts.copy_from_slice(&packet[..PRESSES.len()]);
+        let battery_mv = u16::from_le_bytes([packet[PRESSES.len()], packet[PRESSES.len() + 1]]);
+        // Presses made before the first packet was heard may be long stale, so are ignored
+        let Some(passed_on) = self.passed_on.as_mut() else {
+            self.passed_on = Some(counts);
+            return Some(RemoteNotice::Linked);
+        };
+        for (passed, &count) in passed_on.iter_mut().zip(counts.iter()) {
+            if count.wrapping_sub(*passed) > MAX_BACKLOG {
+                *passed = count;
+            }
+        }
+        if self.press.is_none() {
+            let behind = passed_on.iter().zip(counts.iter()).position(|(&p, &c)| p != c);
+            if let Some(i) = behind {
+                passed_on[i] = passed_on[i].wrapping_add(1);
+                self.press = Some(PRESSES[i]);
+            }
+        }
+        match battery_mv {
+            0 => None,
+            mv if mv < LOW_BATTERY_MV && !self.battery_warned => {
+                self.battery_warned = true;
+                Some(RemoteNotice::LowBattery)
+            },
+            mv => {
+                if mv >= RECOVERED_BATTERY_MV {
+                    self.battery_warned = false;
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Time the round trip to the controller and back, by pinging it several times. Returns the mean of
+/// the round trips of the pings that were answered, in microseconds, or `None` if none were.
+pub(crate) fn measure_round_trip(link: &mut RadioLink) -> Option<u32> {
+    let mut total_us = 0;
+    let mut answered = 0;
+    for seq in 0..PINGS {
+        let start = DWT::cycle_count();
+        link.send(PacketKind::Ping, &[seq]);
+        // A late pong to an earlier ping may arrive first, so keep listening past it
+        loop {
+            let remaining_us = PONG_WAIT_US.saturating_sub(us_since(start));
+            if remaining_us == 0 {
+                break;
+            }
+            let mut reply = [0u8; 1];
+            let len = link.receive(PacketKind::Pong, &mut reply, remaining_us);
+            if len == Some(1) && reply[0] == seq {
+                total_us += us_since(start);
+                answered += 1;
+                break;
+            }
+        }
+    }
+    (answered > 0).then(|| total_us / answered)
+}
+
+impl InputSource for RemoteControl {
+    fn next_event(&mut self) -> Option<InputEvent> {
+        self.press.take()
+    }
+}
+
+/// Read the board's supply voltage, in millivolts, or return 0 if the SAADC fails.
+#[cfg(feature = "controller")]
+fn read_battery_mv(saadc: &mut Saadc) -> u16 {
+    match saadc.read(&mut InternalVdd) {
+        Ok(reading) => (reading.max(0) as i32 * FULL_SCALE_MV / FULL_SCALE) as u16,
+        Err(_) => 0
+    }
+}
+
+/// Act as a controller for another board, sending it every press of this board's buttons and how
+/// much charge is left in its batteries, and answering its pings. This never returns, as a
+/// controller does nothing else.
+#[cfg(feature = "controller")]
+pub(crate) fn run_controller(
+    timer: &mut Timer<TIMER0, Periodic>,
+    link: &mut RadioLink,
+    board_saadc: SAADC
+) -> ! {
+    let mut saadc = Saadc::new(board_saadc, SaadcConfig::default());
+    let mut packet = [0u8; PACKET_LEN];
+    display_image(&BitImage::new(&icons::RADIO));
+    // Packets left to send before the supply voltage is next read
+    let mut until_reading = 0;
+    timer.start(SEND_INTERVAL_US);
+    loop {
+        settle_buttons();
+        while let Some(event) = next_event() {
+            if let Some(i) = PRESSES.iter().position(|&press| press == event) {
+                packet[i] = packet[i].wrapping_add(1);
+            }
+        }
+        if until_reading == 0 {
+            packet[PRESSES.len()..].copy_from_slice(&read_battery_mv(&mut saadc).to_le_bytes());
+            until_reading = BATTERY_INTERVAL;
+        }
+        until_reading -= 1;
+        link.send(PacketKind::Remote, &packet);
+        let mut seq = [0u8; 1];
+        if link.receive(PacketKind::Ping, &mut seq, PING_LISTEN_US) == Some(1) {
+            link.send(PacketKind::Pong, &seq);
+        }
+        while timer.wait().is_err() {}
+    }
+}
@@ -0,0 +1,211 @@
+//! Places the game's frames can be shown. The game loop draws each frame as a matrix of brightness
+//! values, one per tile of the grid, and hands it to a [`Renderer`], so that it doesn't need to
+//! know what the frame ends up on.
+
+use core::fmt::{self, Write};
+use microbit::display::nonblocking::GreyscaleImage;
+use rtt_target::rprint;
+#[cfg(feature = "ascii")]
+use snakebit_core::game::rules::Rules;
+#[cfg(feature = "ascii")]
+use snakebit_core::game::Game;
+use snakebit_core::game::{N_COLS, N_ROWS};
+
+use crate::display::{display_image, viewport};
+
+/// A colour, as levels of red, green and blue from 0 to 255.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Colour {
+    pub(crate) red: u8,
+    pub(crate) green: u8,
+    pub(crate) blue: u8
+}
+
+impl Colour {
+    pub(crate) const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// The colour at `brightness` (from 0 to 9) times its full level.
+    pub(crate) fn dimmed(self, brightness: u8) -> Self {
+        let dim = |level: u8| (level as u16 * brightness.min(9) as u16 / 9) as u8;
+        Self::new(dim(self.red), dim(self.green), dim(self.blue))
+    }
+}
+
+/// What is on a tile of the grid, for renderers that can't tell things apart by brightness alone.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Tile {
+    #[default]
+    Empty,
+    Head,
+    Tail,
+    Food,
+    Wall,
+    Hunter,
+    /// Part of a scent trail towards food out of sight (see [`crate::trail`]).
+    Trail
+}
+
+/// Something that can show frames of the game.
+pub(crate) trait Renderer {
+    /// Show `frame`, which holds a brightness from 0 (off) to 9 (full) for each tile of the grid,
+    /// row by row from the top, until the next frame is presented.
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]);
+
+    /// Show `frame` as [`Renderer::present`] does, with each tile in the colour `colours` gives
+    /// it, at the brightness `frame` gives it. Renderers that can only show brightness ignore the
+    /// colours, as this does by default.
+    fn present_coloured(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        _colours: &[[Colour; N_COLS]; N_ROWS]
+    ) {
+        self.present(frame);
+    }
+
+    /// Show `frame` as [`Renderer::present_coloured`] does, with `tiles` giving what is on each
+    /// tile. Renderers that can show enough levels of brightness to tell things apart ignore the
+    /// tiles, as this does by default.
+    fn present_tiles(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        colours: &[[Colour; N_COLS]; N_ROWS],
+        _tiles: &[[Tile; N_COLS]; N_ROWS]
+    ) {
+        self.present_coloured(frame, colours);
+    }
+
+    /// Show the player's score alongside the game, where there is room for it. There is no room on
+    /// most displays, so by default this does nothing.
+    fn present_score(&mut self, _score: u8) {}
+
+    /// Keep the tile at `row` and `col` (the snake's head, say) in view in the frames presented
+    /// from now on. Most displays show the whole grid, so by default this does nothing.
+    fn follow(&mut self, _row: usize, _col: usize) {}
+}
+
+/// The LED matrix on the front of the board. Where the grid is bigger than the LEDs, they show the
+/// part of it around the tile last passed to [`Renderer::follow`].
+#[derive(Debug, Default)]
+pub(crate) struct LedMatrix {
+    /// The tile that is kept in view, as `(row, col)`.
+    focus: (usize, usize)
+}
+
+impl Renderer for LedMatrix {
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]) {
+        let (row, col) = self.focus;
+        display_image(&GreyscaleImage::new(&viewport(frame, row, col)));
+    }
+
+    fn follow(&mut self, row: usize, col: usize) {
+        self.focus = (row, col);
+    }
+}
+
+/// Frames written out as text, to RTT (with [`Rtt`]), the serial port or anything else that text
+/// can be written to: a line of brightness digits for each row, with `.` for LEDs that are off,
+/// and a blank line after each frame.
+pub(crate) struct Ascii<W>(pub(crate) W);
+
+impl<W: Write> Renderer for Ascii<W> {
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]) {
+        for row in frame.iter() {
+            for &brightness in row.iter() {
+                let c = match brightness {
+                    0 => '.',
+                    b => char::from(b'0' + b.min(9))
+                };
+                let _ = self.0.write_char(c);
+            }
+            let _ = self.0.write_str("\r\n");
+        }
+        let _ = self.0.write_str("\r\n");
+    }
+}
+
+/// The game written out as ASCII art, to RTT (with [`Rtt`]) or anything else that text can be
+/// written to: `@` for the snake's head, `o` for the rest of it, `*` for food, `#` for walls, `x`
+/// for the hunter and `.` for empty tiles, a line for each row, with a blank line after each frame.
+/// Unlike [`Ascii`], this shows what is on each tile rather than how bright it is, so only changes
+/// when the game steps; frames the same as the last one aren't written again.
+#[cfg(feature = "ascii")]
+pub(crate) struct AsciiArt<W> {
+    out: W,
+    /// The last frame written, if any has been.
+    last: Option<[[u8; N_COLS]; N_ROWS]>
+}
+
+#[cfg(feature = "ascii")]
+impl<W: Write> AsciiArt<W> {
+    pub(crate) fn new(out: W) -> Self {
+        Self { out, last: None }
+    }
+
+    /// Write out `game` as it stands, unless it looks the same as when last written.
+    pub(crate) fn present_game<R: Rules>(&mut self, game: &Game<R>) {
+        let mut tiles = game.tile_matrix(b'@', b'o', b'*', b'#', b'.');
+        if let Some((row, col)) = game.hunter() {
+            tiles[row][col] = b'x';
+        }
+        if self.last == Some(tiles) {
+            return;
+        }
+        self.last = Some(tiles);
+        for row in tiles.iter() {
+            for &tile in row.iter() {
+                let _ = self.out.write_char(char::from(tile));
+            }
+            let _ = self.out.write_str("\r\n");
+        }
+        let _ = self.out.write_str("\r\n");
+    }
+}
+
+/// The RTT channel that everything else is logged to, as something text can be written to.
+pub(crate) struct Rtt;
+
+impl Write for Rtt {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        rprint!("{}", s);
+        Ok(())
+    }
+}
+
+/// Two renderers showing the same frames, eg the LED matrix mirroring an external display.
+impl<A: Renderer, B: Renderer> Renderer for (A, B) {
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]) {
+        self.0.present(frame);
+        self.1.present(frame);
+    }
+
+    fn present_coloured(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        colours: &[[Colour; N_COLS]; N_ROWS]
+    ) {
+        self.0.present_coloured(frame, colours);
+        self.1.present_coloured(frame, colours);
+    }
+
+    fn present_tiles(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        colours: &[[Colour; N_COLS]; N_ROWS],
+        tiles: &[[Tile; N_COLS]; N_ROWS]
+    ) {
+        self.0.present_tiles(frame, colours, tiles);
+        self.1.present_tiles(frame, colours, tiles);
+    }
+
+    fn present_score(&mut self, score: u8) {
+        self.0.present_score(score);
+        self.1.present_score(score);
+    }
+
+    fn follow(&mut self, row: usize, col: usize) {
+        self.0.follow(row, col);
+        self.1.follow(row, col);
+    }
+}
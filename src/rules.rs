@@ -0,0 +1,121 @@
+//! The rules each kind of game is played by.
+
+use snakebit_core::game::{Game, GameStatus};
+use snakebit_core::game::rules::{
+    AdversarialRules, BlitzRules, FoodGrid, FullGrid, Outcome, Rules, SelfCollision, SpeedSchedule,
+    StandardRules, TruncatingRules
+};
+
+use crate::config::{GameConfig, GameMode};
+
+/// Length of each bonus round, when they are turned on, in milliseconds.
+const BONUS_ROUND_MS: u32 = 10_000;
+/// Length of [`ModeRules`] when serialized.
+#[cfg(feature = "radio")]
+pub(crate) const MODE_RULES_LEN: usize = 4;
+
+/// A game played by whichever rules its mode calls for.
+pub(crate) type ModeGame = Game<ModeRules>;
+
+/// The rules for any mode, chosen when the game starts.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct ModeRules {
+    mode: GameMode,
+    /// How the snake speeds up, in place of the mode's own schedule.
+    speed_schedule: SpeedSchedule,
+    bonus_rounds: bool
+}
+
+impl ModeRules {
+    /// The rules for a game of `mode`, with the speed schedule and bonus rounds from `config`.
+    pub(crate) fn new(mode: GameMode, config: &GameConfig) -> Self {
+        Self {
+            mode,
+            speed_schedule: config.speed_schedule,
+            bonus_rounds: config.bonus_rounds
+        }
+    }
+
+    /// The mode these are the rules for.
+    pub(crate) fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    #[cfg(feature = "radio")]
+    pub(crate) fn to_bytes(&self) -> [u8; MODE_RULES_LEN] {
+        [
+            self.mode.to_byte(),
+            self.speed_schedule.food_per_speed_up,
+            self.speed_schedule.max_speed,
+            self.bonus_rounds as u8
+        ]
+    }
+
+    /// Read rules written by [`ModeRules::to_bytes`], or return `None` if they aren't valid.
+    #[cfg(feature = "radio")]
+    pub(crate) fn from_bytes(bytes: &[u8; MODE_RULES_LEN]) -> Option<Self> {
+        let bonus_rounds = match bytes[3] {
+            0 => false,
+            1 => true,
+            _ => return None
+        };
+        Some(Self {
+            mode: GameMode::from_byte(bytes[0])?,
+            speed_schedule: SpeedSchedule { food_per_speed_up: bytes[1], max_speed: bytes[2] },
+            bonus_rounds
+        })
+    }
+
+    /// Call `f` with the rules this stands for. None of them keep any state, so a fresh copy is
+    /// used each time.
+    fn with<T>(&self, f: impl FnOnce(&mut dyn Rules) -> T) -> T {
+        match self.mode {
+            GameMode::Blitz => f(&mut BlitzRules),
+            GameMode::Hard => f(&mut AdversarialRules),
+            GameMode::Penalty => f(&mut TruncatingRules),
+            _ => f(&mut StandardRules)
+        }
+    }
+}
+
+impl Rules for ModeRules {
+    fn food_points(&mut self, score: u8) -> u8 {
+        self.with(|rules| rules.food_points(score))
+    }
+
+    fn speed(&self, food_eaten: u8) -> u8 {
+        self.speed_schedule.speed(food_eaten)
+    }
+
+    fn level(&self, food_eaten: u8) -> u8 {
+        self.speed_schedule.level(food_eaten)
+    }
+
+    fn step_len_ms(&self, speed: u8) -> u32 {
+        self.with(|rules| rules.step_len_ms(speed))
+    }
+
+    fn status(&mut self, outcome: Outcome, status: GameStatus) -> GameStatus {
+        self.with(|rules| rules.status(outcome, status))
+    }
+
+    fn bonus_round_ms(&self) -> u32 {
+        if self.bonus_rounds { BONUS_ROUND_MS } else { 0 }
+    }
+
+    fn chase_steps(&self) -> u8 {
+        self.with(|rules| rules.chase_steps())
+    }
+
+    fn self_collision(&self) -> SelfCollision {
+        self.with(|rules| rules.self_collision())
+    }
+
+    fn full_grid(&self) -> FullGrid {
+        if self.mode == GameMode::Marathon { FullGrid::Loop } else { FullGrid::Win }
+    }
+
+    fn food_tile(&mut self, grid: &FoodGrid) -> Option<(usize, usize)> {
+        self.with(|rules| rules.food_tile(grid))
+    }
+}
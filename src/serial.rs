@@ -0,0 +1,341 @@
+//! Line-based commands received over the micro:bit's serial port (which appears as a USB serial
+//! device on the host computer, at 115200 baud).
+//!
+//! Supported commands:
+//!
+//! - `T YYYY-MM-DD HH:MM:SS`: set the date and time.
+//! - `H`: list the normal and blitz high scores, with when each was achieved.
+//! - `B 1` or `B 0`: turn bouncing walls on or off, from the next game.
+//! - `S F M`: from the next game, speed up every `F` pieces of food (1-9), up to speed `M` (1-5).
+//! - `R 1` or `R 0`: turn bonus rounds on or off, from the next game.
+//! - `C 1` or `C 0`: turn the high contrast display on or off.
+//! - `L 1` or `L 0`: turn beeping out the speed at each speed-up on or off.
+//! - `X 1` or `X 0`: swap the A and B buttons, or swap them back.
+//! - `P 1` or `P 0`: turn random starting positions on or off, from the next game.
+//! - `N 1` or `N 0`: turn trails leading to food placed out of sight of the LEDs on or off, from
+//!   the next step.
+//! - `K 1` or `K 0`: turn keeping on turning while a button is held down on or off.
+//! - `E 1` or `E 0`: have buttons turn the snake as soon as they are pressed, or when they are let
+//!   go.
+//! - `G W A`: during play, have gesture `W` make action `A`. The gestures are `A` and `B` (short
+//!   presses), `HA` and `HB` (holding a button down), `AB` (both buttons together) and `T` (tapping
+//!   the logo). The actions are `L` and `R` (turning, for short presses only), `P` (pausing), `S`
+//!   (speeding up or back down) and `-` (nothing).
+//! - `G D`: put every gesture back to what it usually does.
+//! - `V CODE`: check the share code shown after a game on another board, and record its score in
+//!   this board's normal or blitz high scores if it is valid.
+//! - `F E`, `F C` or `F W`: for testing, make the snake eat, crash or win at the next step.
+//! - `M R C`: for testing, move the food to row `R` and column `C` (each less than the grid size),
+//!   if it is free.
+//! - `W 1` or `W 0`: start or stop writing the grid and score out after every step, until the
+//!   board is powered off.
+//!
+//! Each command is answered with `OK` or `ERR`, after any output it produces. Commands are queued
+//! as they arrive and carried out by the game loop, so are not answered while the game is paused.
+//!
+//! The snake can also be steered over serial, with lines that aren't answered:
+//!
+//! - `<` or `>`: turn left or right, like pressing A or B.
+//! - `!`: pause or resume the game, like pressing both buttons together.
+//! - `*`: abandon the game in progress without recording it, and start a new one. This is ignored
+//!   while the game is paused or counting down to its start.
+//!
+//! A terminal's arrow keys steer the snake too, heading it straight for the way the key points
+//! whatever the controls are, without waiting for the end of the line. Together with `W 1`, this
+//! lets the game be played from a terminal on the host computer (set to send each key as it is
+//! pressed, as most do), watching the grid as it is written out.
+
+use core::fmt::{self, Write};
+use heapless::{Deque, Vec};
+use microbit::board::UartPins;
+use microbit::hal::prelude::*;
+use microbit::hal::uarte::{Baudrate, Parity, Uarte, UarteRx, UarteTx};
+use microbit::pac::UARTE0;
+use snakebit_core::game::rules::SpeedSchedule;
+use snakebit_core::game::{Direction, Game};
+use snakebit_core::share::ShareCode;
+
+use crate::actions::{Action, Gesture};
+use crate::clock::DateTime;
+use crate::control::{InputEvent, InputSource};
+use crate::devmenu::ForcedOutcome;
+
+/// Maximum length of a command, excluding the line ending.
+const MAX_LINE_LEN: usize = 32;
+/// Maximum number of commands waiting to be carried out. Any more are dropped.
+const MAX_QUEUED_COMMANDS: usize = 4;
+/// Maximum number of steering lines waiting to be gathered. Any more are dropped.
+const MAX_QUEUED_INPUTS: usize = 4;
+
+/// A command received over serial.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Command {
+    SetTime(DateTime),
+    ListHighScores,
+    SetBouncingWalls(bool),
+    SetSpeedSchedule(SpeedSchedule),
+    SetBonusRounds(bool),
+    SetHighContrast(bool),
+    SetLevelBeeps(bool),
+    SetSwapButtons(bool),
+    SetRandomStart(bool),
+    SetFoodTrails(bool),
+    SetHoldToTurn(bool),
+    SetTurnOnPress(bool),
+    SetAction(Gesture, Option<Action>),
+    ResetActions,
+    RecordShareCode(ShareCode),
+    ForceOutcome(ForcedOutcome),
+    MoveFood(usize, usize),
+    SetEcho(bool),
+    /// A line that was not a valid command.
+    Invalid
+}
+
+pub(crate) struct SerialPort {
+    tx: UarteTx<UARTE0>,
+    rx: UarteRx<UARTE0>,
+    /// The command received so far.
+    line: Vec<u8, MAX_LINE_LEN>,
+    /// Whether the current command has been too long to fit in `line`.
+    overflowed: bool,
+    /// Commands received but not yet carried out.
+    commands: Deque<Command, MAX_QUEUED_COMMANDS>,
+    /// Steering lines received but not yet gathered.
+    inputs: Deque<InputEvent, MAX_QUEUED_INPUTS>,
+    /// Whether a restart has been asked for since the last call to [`SerialPort::take_restart`].
+    restart: bool,
+    /// How far through an escape sequence the bytes received are.
+    escape: Escape,
+    /// Whether the grid is written out after every step.
+    echo: bool
+}
+
+/// How far through an escape sequence, as sent by a terminal's arrow keys (`ESC [ A` for up, and
+/// so on), the bytes received are. Escape sequences are never part of a command.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Escape {
+    None,
+    /// `ESC` has been received.
+    Started,
+    /// `ESC [` has been received, so the next byte says which key was pressed.
+    Bracket
+}
+
+impl SerialPort {
+    pub(crate) fn new(board_uarte: UARTE0, board_uart: UartPins) -> Self {
+        let uarte = Uarte::new(board_uarte, board_uart.into(), Parity::EXCLUDED, Baudrate::BAUD115200);
+        let tx_buf = cortex_m::singleton!(: [u8; 1] = [0; 1]).unwrap();
+        let rx_buf = cortex_m::singleton!(: [u8; 1] = [0; 1]).unwrap();
+        let (tx, rx) = uarte.split(tx_buf, rx_buf).unwrap();
+        Self {
+            tx,
+            rx,
+            line: Vec::new(),
+            overflowed: false,
+            commands: Deque::new(),
+            inputs: Deque::new(),
+            restart: false,
+            escape: Escape::None,
+            echo: false
+        }
+    }
+
+    /// Handle any bytes received since the last call, queueing any complete commands. The serial
+    /// port has very little buffering, so this should be called as often as possible.
+    pub(crate) fn poll(&mut self) {
+        while let Ok(byte) = self.rx.read() {
+            match (self.escape, byte) {
+                (Escape::None, 0x1b) => {
+                    self.escape = Escape::Started;
+                    continue;
+                },
+                (Escape::Started, b'[') => {
+                    self.escape = Escape::Bracket;
+                    continue;
+                },
+                (Escape::Bracket, key) => {
+                    self.escape = Escape::None;
+                    let direction = match key {
+                        b'A' => Direction::Up,
+                        b'B' => Direction::Down,
+                        b'C' => Direction::Right,
+                        b'D' => Direction::Left,
+                        // Some other key, which does nothing
+                        _ => continue
+                    };
+                    let _ = self.inputs.push_back(InputEvent::Head(direction));
+                    continue;
+                },
+                // Some other escape sequence, which ends here
+                (Escape::Started, _) => {
+                    self.escape = Escape::None;
+                    continue;
+                },
+                (Escape::None, _) => ()
+            }
+            match byte {
+                b'\r' | b'\n' => {
+                    if let (false, Some(event)) = (self.overflowed, parse_input(&self.line)) {
+                        let _ = self.inputs.push_back(event);
+                    } else if !self.overflowed && self.line.as_slice() == b"*" {
+                        self.restart = true;
+                    } else if !self.line.is_empty() || self.overflowed {
+                        let command = if self.overflowed {
+                            Command::Invalid
+                        } else {
+                            parse_command(&self.line)
+                        };
+                        let _ = self.commands.push_back(command);
+                    }
+                    self.line.clear();
+                    self.overflowed = false;
+                },
+                _ => {
+                    if self.line.push(byte).is_err() {
+                        self.overflowed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Take the next queued command, if any.
+    pub(crate) fn next_command(&mut self) -> Option<Command> {
+        self.commands.pop_front()
+    }
+
+    /// Return whether a restart has been asked for since this was last called.
+    pub(crate) fn take_restart(&mut self) -> bool {
+        core::mem::take(&mut self.restart)
+    }
+
+    /// Start or stop writing the grid out after every step.
+    pub(crate) fn set_echo(&mut self, on: bool) {
+        self.echo = on;
+    }
+
+    /// Write `game`'s grid and score out, if that has been asked for with `W 1`: a line for each
+    /// row, with `@` for the snake's head, `o` for the rest of it, `*` for food, `#` for walls and
+    /// `.` for empty tiles.
+    pub(crate) fn echo_game<R>(&mut self, game: &Game<R>) {
+        if !self.echo {
+            return;
+        }
+        for row in game.game_matrix(4, 3, 2, 1).iter() {
+            for value in row.iter() {
+                let c = match value {
+                    4 => '@',
+                    3 => 'o',
+                    2 => '*',
+                    1 => '#',
+                    _ => '.'
+                };
+                let _ = self.tx.write_char(c);
+            }
+            let _ = self.tx.write_str("\r\n");
+        }
+        let _ = write!(self.tx, "Score {}\r\n\r\n", game.score());
+    }
+
+    /// Answer a command with `OK` or `ERR`.
+    pub(crate) fn reply(&mut self, ok: bool) {
+        let _ = write!(self.tx, "{}\r\n", if ok { "OK" } else { "ERR" });
+    }
+}
+
+impl InputSource for SerialPort {
+    fn next_event(&mut self) -> Option<InputEvent> {
+        self.inputs.pop_front()
+    }
+}
+
+impl Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.tx.write_str(s)
+    }
+}
+
+/// The input event a steering line stands for, if it is one.
+fn parse_input(line: &[u8]) -> Option<InputEvent> {
+    match line {
+        [b'<'] => Some(InputEvent::TurnLeft),
+        [b'>'] => Some(InputEvent::TurnRight),
+        [b'!'] => Some(InputEvent::Both),
+        _ => None
+    }
+}
+
+fn parse_command(line: &[u8]) -> Command {
+    match line {
+        [b'T', b' ', rest @ ..] => match DateTime::parse(rest) {
+            Some(now) => Command::SetTime(now),
+            None => Command::Invalid
+        },
+        [b'H'] => Command::ListHighScores,
+        [b'B', b' ', b'0'] => Command::SetBouncingWalls(false),
+        [b'B', b' ', b'1'] => Command::SetBouncingWalls(true),
+        [b'R', b' ', b'0'] => Command::SetBonusRounds(false),
+        [b'R', b' ', b'1'] => Command::SetBonusRounds(true),
+        [b'C', b' ', b'0'] => Command::SetHighContrast(false),
+        [b'C', b' ', b'1'] => Command::SetHighContrast(true),
+        [b'L', b' ', b'0'] => Command::SetLevelBeeps(false),
+        [b'L', b' ', b'1'] => Command::SetLevelBeeps(true),
+        [b'X', b' ', b'0'] => Command::SetSwapButtons(false),
+        [b'X', b' ', b'1'] => Command::SetSwapButtons(true),
+        [b'P', b' ', b'0'] => Command::SetRandomStart(false),
+        [b'P', b' ', b'1'] => Command::SetRandomStart(true),
+        [b'N', b' ', b'0'] => Command::SetFoodTrails(false),
+        [b'N', b' ', b'1'] => Command::SetFoodTrails(true),
+        [b'K', b' ', b'0'] => Command::SetHoldToTurn(false),
+        [b'K', b' ', b'1'] => Command::SetHoldToTurn(true),
+        [b'E', b' ', b'0'] => Command::SetTurnOnPress(false),
+        [b'E', b' ', b'1'] => Command::SetTurnOnPress(true),
+        [b'G', b' ', b'D'] => Command::ResetActions,
+        [b'G', b' ', rest @ ..] => match parse_action(rest) {
+            Some((gesture, action)) => Command::SetAction(gesture, action),
+            None => Command::Invalid
+        },
+        [b'V', b' ', code @ ..] => match ShareCode::decode(code) {
+            Some(code) => Command::RecordShareCode(code),
+            None => Command::Invalid
+        },
+        [b'W', b' ', b'0'] => Command::SetEcho(false),
+        [b'W', b' ', b'1'] => Command::SetEcho(true),
+        [b'F', b' ', b'E'] => Command::ForceOutcome(ForcedOutcome::Eat),
+        [b'F', b' ', b'C'] => Command::ForceOutcome(ForcedOutcome::Collision),
+        [b'F', b' ', b'W'] => Command::ForceOutcome(ForcedOutcome::Win),
+        [b'M', b' ', row @ b'0'..=b'9', b' ', col @ b'0'..=b'9'] => {
+            Command::MoveFood((row - b'0') as usize, (col - b'0') as usize)
+        },
+        [b'S', b' ', food @ b'1'..=b'9', b' ', max @ b'1'..=b'5'] => {
+            Command::SetSpeedSchedule(SpeedSchedule {
+                food_per_speed_up: food - b'0',
+                max_speed: max - b'0'
+            })
+        },
+        _ => Command::Invalid
+    }
+}
+
+/// The gesture and action in a `G` command, after the `G`.
+fn parse_action(line: &[u8]) -> Option<(Gesture, Option<Action>)> {
+    let (gesture, action) = match line {
+        [b'A', b' ', action] => (Gesture::PressA, action),
+        [b'B', b' ', action] => (Gesture::PressB, action),
+        [b'H', b'A', b' ', action] => (Gesture::HoldA, action),
+        [b'H', b'B', b' ', action] => (Gesture::HoldB, action),
+        [b'A', b'B', b' ', action] => (Gesture::Chord, action),
+        [b'T', b' ', action] => (Gesture::LogoTap, action),
+        _ => return None
+    };
+    let action = match action {
+        b'L' => Some(Action::TurnLeft),
+        b'R' => Some(Action::TurnRight),
+        b'P' => Some(Action::Pause),
+        b'S' => Some(Action::Boost),
+        b'-' => None,
+        _ => return None
+    };
+    Some((gesture, action))
+}
@@ -0,0 +1,170 @@
+//! A menu of the main settings, opened by holding B on the title screen, so that they can be
+//! changed without a computer. Its choices are saved along with the rest of the settings (with the
+//! `persist` feature).
+//!
+//! In the menu, A moves on to the next item and B changes it. Each item is shown as an icon for its
+//! current value: the difficulty as one to three bars, the controls as in the pause menu, the
+//! speaker while sound is on and crossed out while it is off, wraparound as arrows or a border,
+//! the brightness as a sun, drawn at that brightness, and the way round the board is held as an
+//! arrow, turned with everything else so that it points up once the board is held that way. Reduced
+//! flashing is shown as an eye, brightly while it is on and dimly while it is off. Pressing both
+//! buttons together leaves the menu.
+
+use microbit::display::nonblocking::GreyscaleImage;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::TIMER0;
+use snakebit_core::game::rules::SpeedSchedule;
+use snakebit_core::input::ControlScheme;
+
+use crate::config::{GameConfig, SessionOverrides};
+use crate::control::{clear_events, next_event, InputEvent};
+use crate::display::{display_image, set_brightness, set_rotation, BRIGHTNESS_LEVELS, ROTATIONS};
+use crate::hardware::Capabilities;
+use crate::icons::{self, Icon};
+use crate::serial::SerialPort;
+use crate::{wait_frame, FRAME_MS};
+
+/// The difficulties that can be chosen, easiest first, as how quickly the snake speeds up.
+const DIFFICULTIES: [(SpeedSchedule, Icon); 3] = [
+    (SpeedSchedule { food_per_speed_up: 8, max_speed: 3 }, icons::EASY),
+    (SpeedSchedule { food_per_speed_up: 5, max_speed: 5 }, icons::MEDIUM),
+    (SpeedSchedule { food_per_speed_up: 3, max_speed: 5 }, icons::HARD)
+];
+
+/// The items in the menu, in order.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Item {
+    Difficulty,
+    Controls,
+    #[cfg(feature = "audio")]
+    Sound,
+    Wraparound,
+    Brightness,
+    Rotation,
+    ReducedFlashing
+}
+
+const ITEMS: &[Item] = &[
+    Item::Difficulty,
+    Item::Controls,
+    #[cfg(feature = "audio")]
+    Item::Sound,
+    Item::Wraparound,
+    Item::Brightness,
+    Item::Rotation,
+    Item::ReducedFlashing
+];
+
+impl Item {
+    /// The icon for the item's current value, and how brightly to show it.
+    fn icon(self, config: &GameConfig) -> (Icon, u8) {
+        match self {
+            // A schedule set over serial may not be one of the difficulties, so is shown dimly as
+            // the usual one
+            Item::Difficulty => match difficulty(config) {
+                Some(i) => (DIFFICULTIES[i].1, 9),
+                None => (icons::MEDIUM, 2)
+            },
+            Item::Controls => (controls_icon(config), 9),
+            #[cfg(feature = "audio")]
+            Item::Sound => (if config.sound { icons::SOUND } else { icons::MUTE }, 9),
+            Item::Wraparound => (if config.wraparound { icons::WRAP } else { icons::NO_WRAP }, 9),
+            Item::Brightness => (icons::SUN, 9),
+            Item::Rotation => (icons::UP, 9),
+            Item::ReducedFlashing => (icons::EYE, if config.reduced_flashing { 9 } else { 2 })
+        }
+    }
+}
+
+/// The index of the difficulty chosen in `config`, if it is one of them.
+fn difficulty(config: &GameConfig) -> Option<usize> {
+    DIFFICULTIES.iter().position(|&(schedule, _)| schedule == config.speed_schedule)
+}
+
+/// The icon for the controls chosen in `config`.
+pub(crate) fn controls_icon(config: &GameConfig) -> Icon {
+    match config.control_scheme {
+        _ if config.tilt_steering => icons::TILT,
+        ControlScheme::Relative => icons::RELATIVE,
+        ControlScheme::Absolute => icons::ABSOLUTE
+    }
+}
+
+/// Move `config` on to the next controls: relative, then absolute, then tilt steering (if
+/// `tilt_available`), then back to relative.
+pub(crate) fn next_controls(config: &mut GameConfig, tilt_available: bool) {
+    let (scheme, tilt_steering) = match config.control_scheme {
+        _ if config.tilt_steering => (ControlScheme::Relative, false),
+        ControlScheme::Relative => (ControlScheme::Absolute, false),
+        ControlScheme::Absolute if tilt_available => (ControlScheme::Absolute, true),
+        ControlScheme::Absolute => (ControlScheme::Relative, false)
+    };
+    config.control_scheme = scheme;
+    config.tilt_steering = tilt_steering;
+}
+
+/// Show the menu until the player leaves it. Changing wraparound or sound here changes the saved
+/// setting, so drops any change made for the session alone. Sound isn't offered without a speaker.
+pub(crate) fn settings_menu(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    config: &mut GameConfig,
+    overrides: &mut SessionOverrides,
+    capabilities: &Capabilities
+) {
+    let tilt_available = cfg!(feature = "tilt") && capabilities.accelerometer;
+    #[cfg(feature = "audio")]
+    let hidden = Some(Item::Sound).filter(|_| !capabilities.speaker);
+    #[cfg(not(feature = "audio"))]
+    let hidden: Option<Item> = None;
+    let mut index = 0;
+    clear_events();
+    timer.start(FRAME_MS * 1000);
+    loop {
+        let item = ITEMS[index];
+        let (mut matrix, brightness) = item.icon(config);
+        for cell in matrix.iter_mut().flatten() {
+            *cell *= brightness;
+        }
+        display_image(&GreyscaleImage::new(&matrix));
+        wait_frame(timer, serial);
+        match next_event() {
+            Some(InputEvent::TurnLeft) => {
+                index = (index + 1) % ITEMS.len();
+                if Some(ITEMS[index]) == hidden {
+                    index = (index + 1) % ITEMS.len();
+                }
+            },
+            Some(InputEvent::TurnRight) => match item {
+                Item::Difficulty => {
+                    let next = difficulty(config).map_or(0, |i| (i + 1) % DIFFICULTIES.len());
+                    config.speed_schedule = DIFFICULTIES[next].0;
+                },
+                Item::Controls => next_controls(config, tilt_available),
+                #[cfg(feature = "audio")]
+                Item::Sound => {
+                    config.sound = !config.sound;
+                    overrides.sound = None;
+                },
+                Item::Wraparound => {
+                    config.wraparound = !config.wraparound;
+                    overrides.wraparound = None;
+                },
+                Item::Brightness => {
+                    config.brightness = config.brightness % BRIGHTNESS_LEVELS + 1;
+                    set_brightness(config.brightness);
+                },
+                Item::Rotation => {
+                    config.rotation = (config.rotation + 1) % ROTATIONS;
+                    set_rotation(config.rotation);
+                },
+                Item::ReducedFlashing => config.reduced_flashing = !config.reduced_flashing
+            },
+            Some(InputEvent::Both) => {
+                clear_events();
+                return;
+            },
+            _ => ()
+        }
+    }
+}
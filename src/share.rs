@@ -0,0 +1,101 @@
+//! Share codes for finished normal and blitz games, so that a score can be copied by hand onto
+//! another board's leaderboard. The codes themselves are made and checked by
+//! [`snakebit_core::share`]; this module fills in which mode and which board they came from, and
+//! records the scores from codes entered on this board.
+
+use core::fmt::Write;
+use core::str;
+
+#[cfg(feature = "fonts")]
+use microbit::display::nonblocking::BitImage;
+#[cfg(feature = "fonts")]
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::FICR;
+#[cfg(feature = "fonts")]
+use microbit::pac::TIMER0;
+use rtt_target::rprintln;
+use snakebit_core::share::ShareCode;
+#[cfg(feature = "fonts")]
+use snakebit_core::share::{CODE_CHARS, CODE_LEN};
+
+use crate::clock::now;
+use crate::config::GameMode;
+#[cfg(feature = "fonts")]
+use crate::display::flash_image;
+#[cfg(feature = "fonts")]
+use crate::entry::enter_text;
+use crate::highscores::HighScores;
+#[cfg(feature = "fonts")]
+use crate::icons;
+use crate::serial::SerialPort;
+
+/// The lowest 16 bits of this board's device address, which is set at the factory and differs
+/// from board to board.
+pub(crate) fn device_id() -> u16 {
+    // SAFETY: the FICR is read-only and has no side effects when read
+    let ficr = unsafe { &*FICR::ptr() };
+    ficr.deviceaddr[0].read().bits() as u16
+}
+
+/// The share code for a game played on this board, if games of its mode have a leaderboard.
+pub(crate) fn share_code(mode: GameMode, score: u8) -> Option<ShareCode> {
+    let mode = match mode {
+        GameMode::Normal => 0,
+        GameMode::Blitz => 1,
+        GameMode::Daily
+        | GameMode::Custom
+        | GameMode::Marathon
+        | GameMode::Hard
+        | GameMode::Penalty => return None
+    };
+    Some(ShareCode { score, mode, device: device_id() })
+}
+
+/// Record the score from a share code in the table for its mode, returning whether the table was
+/// changed, or `None` if the code can't be recorded. Scores from this board were recorded when
+/// they were achieved, so recording them again from their codes would only duplicate them.
+pub(crate) fn record_share_code(
+    code: &ShareCode,
+    high_scores: &mut HighScores,
+    blitz_scores: &mut HighScores
+) -> Option<bool> {
+    if code.device == device_id() {
+        return None;
+    }
+    let table = match code.mode {
+        0 => high_scores,
+        1 => blitz_scores,
+        _ => return None
+    };
+    Some(table.record(code.score, now()).is_some())
+}
+
+/// Let the player type in a share code on the buttons, and record its score if it is valid. A
+/// star is shown if it was, and a cross if not.
+#[cfg(feature = "fonts")]
+pub(crate) fn enter_share_code(
+    timer: &mut Timer<TIMER0, Periodic>,
+    serial: &mut SerialPort,
+    high_scores: &mut HighScores,
+    blitz_scores: &mut HighScores
+) {
+    let text = match enter_text::<CODE_LEN>(timer, serial, CODE_CHARS) {
+        Some(text) => text,
+        None => return
+    };
+    let recorded = ShareCode::decode(&text)
+        .and_then(|code| record_share_code(&code, high_scores, blitz_scores))
+        .is_some();
+    let icon = if recorded { icons::STAR } else { icons::CROSS };
+    flash_image(timer, &BitImage::new(&icon), 1000);
+}
+
+/// Report the share code for a finished game over serial and RTT.
+pub(crate) fn announce(serial: &mut SerialPort, code: &ShareCode) {
+    let text = code.encode();
+    // Codes are always ASCII
+    let text = str::from_utf8(&text).unwrap_or("?");
+    rprintln!("Share code: {}", text);
+    // Nobody may be listening on the serial port, so there is nothing to do if this fails
+    let _ = write!(serial, "Share code: {}\r\n", text);
+}
@@ -0,0 +1,74 @@
+//! Beeps from the speaker on the back of the board, driven by PWM so that they play while the game
+//! loop carries on.
+
+use microbit::hal::gpio::p0::P0_00;
+use microbit::hal::gpio::{Disconnected, Level};
+use microbit::hal::pwm::{Channel, Prescaler, Pwm};
+use microbit::hal::time::Hertz;
+use microbit::pac::PWM0;
+
+/// Pitch of each beep, in hertz.
+const BEEP_HZ: u32 = 880;
+/// How long each beep lasts, in milliseconds.
+const BEEP_MS: u32 = 100;
+/// Length of the silence after each beep, in milliseconds, long enough that beeps can be counted.
+const GAP_MS: u32 = 150;
+
+/// Plays runs of short beeps, one after another.
+pub(crate) struct Beeper {
+    pwm: Pwm<PWM0>,
+    /// Beeps in the current run that haven't started yet.
+    beeps_left: u8,
+    /// Whether a beep is sounding.
+    sounding: bool,
+    /// Time until the current beep or gap ends.
+    until_change_ms: u32
+}
+
+impl Beeper {
+    pub(crate) fn new(board_pwm: PWM0, speaker_pin: P0_00<Disconnected>) -> Self {
+        let pin = speaker_pin.into_push_pull_output(Level::Low).degrade();
+        let pwm = Pwm::new(board_pwm);
+        pwm.set_output_pin(Channel::C0, pin)
+            .set_prescaler(Prescaler::Div16)
+            .set_period(Hertz(BEEP_HZ));
+        pwm.set_duty_on_common(pwm.max_duty() / 2);
+        pwm.disable();
+        Self { pwm, beeps_left: 0, sounding: false, until_change_ms: 0 }
+    }
+
+    /// Start a run of `count` beeps, cutting short any run still playing.
+    pub(crate) fn play(&mut self, count: u8) {
+        self.stop();
+        self.beeps_left = count;
+    }
+
+    /// Silence the speaker, abandoning any beeps still to be played. Anything that stops calling
+    /// [`Beeper::poll`] while beeps are playing should call this first, or a beep will sound
+    /// until polling resumes.
+    pub(crate) fn stop(&mut self) {
+        self.pwm.disable();
+        self.beeps_left = 0;
+        self.sounding = false;
+        self.until_change_ms = 0;
+    }
+
+    /// Count `elapsed_ms` milliseconds towards the end of the current beep or gap, starting or
+    /// ending a beep if it is due. This should be called every frame.
+    pub(crate) fn poll(&mut self, elapsed_ms: u32) {
+        self.until_change_ms = self.until_change_ms.saturating_sub(elapsed_ms);
+        if self.until_change_ms > 0 {
+            return;
+        }
+        if self.sounding {
+            self.pwm.disable();
+            self.sounding = false;
+            self.until_change_ms = GAP_MS;
+        } else if self.beeps_left > 0 {
+            self.pwm.enable();
+            self.sounding = true;
+            self.beeps_left -= 1;
+            self.until_change_ms = BEEP_MS;
+        }
+    }
+}
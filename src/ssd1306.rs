@@ -0,0 +1,259 @@
+//! An external 128x64 SSD1306 OLED display, wired to the edge connector's I2C pins: SCL to pin 19,
+//! SDA to pin 20 and its supply to 3V and GND. The board has pull-ups on these pins, so nothing
+//! else is needed. The display is driven by the TWIM1 peripheral at 400kHz, as TWIM0 is taken by
+//! the accelerometer on the internal bus.
+//!
+//! The `ssd1306` feature makes the game's grid 9x9, as big as fits the height of the display, of
+//! which the board's own LEDs only have room for the part around the snake's head. The grid is
+//! drawn on the left of the display, as a square for each tile: filled for the snake, food and
+//! hunter, and hollow for walls and scent trails. The score is drawn on the right.
+//!
+//! Writing a page of the grid (a band of 8 pixel rows) takes about 1.5ms, so only the pages that
+//! have changed are redrawn, and no more than [`MAX_PAGES_PER_FRAME`] of them (counting the
+//! score's) each frame. Any others are left for the frames after, which keeps each frame within
+//! the game loop's budget.
+
+use microbit::board::I2CExternalPins;
+use microbit::hal::twim::{self, Twim};
+use microbit::pac::TWIM1;
+use snakebit_core::game::{N_COLS, N_ROWS};
+
+use crate::render::{Colour, Renderer, Tile};
+
+/// The display's I2C address.
+const ADDRESS: u8 = 0x3c;
+/// Width of the display, in pixels.
+const WIDTH: u8 = 128;
+/// Number of pages on the display. Each page is a row of bytes, each byte a column of 8 pixels.
+const PAGES: u8 = 8;
+/// Control byte starting a run of commands.
+const COMMANDS: u8 = 0x00;
+/// Control byte starting a run of display data.
+const DATA: u8 = 0x40;
+/// Commands sent when the display is started: display off, clock divider, multiplex ratio for 64
+/// rows, no offset, start line 0, charge pump on, horizontal addressing, flipped to be the right
+/// way up, COM pins for 64 rows, contrast, precharge period, VCOMH level, show the RAM, not
+/// inverted, display on.
+const SETUP: [u8; 25] = [
+    0xae, 0xd5, 0x80, 0xa8, 0x3f, 0xd3, 0x00, 0x40, 0x8d, 0x14, 0x20, 0x00, 0xa1, 0xc8, 0xda, 0x12,
+    0x81, 0xcf, 0xd9, 0xf1, 0xdb, 0x40, 0xa4, 0xa6, 0xaf
+];
+
+/// Size of each tile of the grid, in pixels, including a gap of one pixel on its right and below
+/// it: as big as lets the whole grid fit the height of the display.
+const TILE_PX: u8 = PAGES * 8 / N_ROWS as u8;
+/// Distance of the grid from the top of the display, to centre it.
+const GRID_TOP: u8 = (PAGES * 8 - N_ROWS as u8 * TILE_PX) / 2;
+/// Brightness at and above which a tile is drawn filled, for frames where it isn't known what is
+/// on each tile. Dimmer tiles are drawn hollow.
+const FILLED_BRIGHTNESS: u8 = 5;
+/// Most pages redrawn in a frame.
+const MAX_PAGES_PER_FRAME: u32 = 2;
+
+/// Distance of the score from the left of the display, in pixels.
+const SCORE_LEFT: u8 = 72;
+/// Distance of the score from the top of the display, in pixels.
+const SCORE_TOP: u8 = 24;
+/// Number of digits in the score, which is at most 255.
+const SCORE_DIGITS: u8 = 3;
+/// Size of each pixel of the score's font, in pixels.
+const DIGIT_SCALE: u8 = 3;
+/// Width of each digit, including the gap after it, in pixels.
+const DIGIT_PX: u8 = 4 * DIGIT_SCALE;
+/// Digits from 0 to 9, each 3 pixels wide and 5 high, a row to a byte with the leftmost pixel as
+/// the highest of the three bits.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111]
+];
+
+// Check at compile time that the grid fits on the display, to the left of the score
+const _: () = assert!(N_ROWS * (TILE_PX as usize) <= (PAGES as usize) * 8);
+const _: () = assert!(N_COLS * (TILE_PX as usize) < SCORE_LEFT as usize);
+
+/// How a tile of the grid is drawn.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Fill {
+    Empty,
+    Hollow,
+    Filled
+}
+
+/// An SSD1306 display on the edge connector.
+pub(crate) struct Ssd1306 {
+    i2c: Twim<TWIM1>,
+    /// The grid as it should be drawn.
+    tiles: [[Fill; N_COLS]; N_ROWS],
+    /// Pages of the grid that haven't been redrawn since it changed, a bit for each.
+    stale_pages: u8,
+    /// Number of pages that can still be redrawn this frame.
+    pages_left: u32,
+    /// The score as last drawn, if it has been.
+    score: Option<u8>
+}
+
+impl Ssd1306 {
+    /// Set up the display, clearing it. Errors writing to the display are ignored, so there is no
+    /// way to tell whether one is actually connected.
+    pub(crate) fn new(twim: TWIM1, board_i2c: I2CExternalPins) -> Self {
+        let mut display = Self {
+            i2c: Twim::new(twim, board_i2c.into(), twim::Frequency::K400),
+            tiles: [[Fill::Empty; N_COLS]; N_ROWS],
+            stale_pages: 0,
+            pages_left: MAX_PAGES_PER_FRAME,
+            score: None
+        };
+        let mut setup = [COMMANDS; 1 + SETUP.len()];
+        setup[1..].copy_from_slice(&SETUP);
+        display.i2c.write(ADDRESS, &setup).ok();
+        for page in 0..PAGES {
+            display.draw(0, WIDTH - 1, page, |_, _| false);
+        }
+        display
+    }
+
+    /// Draw the pixels from column `left` to `right` (inclusive) of `page`, each lit if `lit`
+    /// returns true for its column and row.
+    fn draw(&mut self, left: u8, right: u8, page: u8, lit: impl Fn(u8, u8) -> bool) {
+        let commands = [COMMANDS, 0x21, left, right, 0x22, page, page];
+        self.i2c.write(ADDRESS, &commands).ok();
+        // Written from RAM, where EasyDMA can read it
+        let mut data = [DATA; 1 + WIDTH as usize];
+        for (byte, x) in data[1..].iter_mut().zip(left..=right) {
+            let bits = (0..8).filter(|bit| lit(x, page * 8 + bit));
+            *byte = bits.fold(0, |byte, bit| byte | (1 << bit));
+        }
+        let len = 2 + (right - left) as usize;
+        self.i2c.write(ADDRESS, &data[..len]).ok();
+    }
+
+    /// Draw the grid as `tiles`, redrawing as many of the pages that change as can be this frame.
+    fn show(&mut self, tiles: [[Fill; N_COLS]; N_ROWS]) {
+        self.show(tiles);
+    }
+
+    fn present_tiles(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        _colours: &[[Colour; N_COLS]; N_ROWS],
+        tiles: &[[Tile; N_COLS]; N_ROWS]
+    ) {
+        let mut fills = [[Fill::Empty; N_COLS]; N_ROWS];
+        for (row, fills_row) in fills.iter_mut().enumerate() {
+            for (col, fill) in fills_row.iter_mut().enumerate() {
+                // Tiles fading out as the snake slides off them are left empty
+                *fill = match (tiles[row][col], frame[row][col]) {
+                    (Tile::Empty, _) | (_, 0) => Fill::Empty,
+                    (Tile::Wall | Tile::Trail, _) => Fill::Hollow,
+                    _ => Fill::Filled
+                };
+            }
+        }
+        self.show(fills);
+    }
+
+    /// Redraw as many of the grid's stale pages as can be this frame.
+    fn redraw_stale_pages(&mut self) {
+        let tiles = self.tiles;
+        let right = N_COLS as u8 * TILE_PX - 1;
+        while self.stale_pages != 0 && self.pages_left > 0 {
+            self.pages_left -= 1;
+            let page = self.stale_pages.trailing_zeros() as u8;
+            self.stale_pages &= !(1 << page);
+            self.draw(0, right, page, |x, y| {
+                let Some(y) = y.checked_sub(GRID_TOP) else {
+                    return false;
+                };
+                let (row, col) = ((y / TILE_PX) as usize, (x / TILE_PX) as usize);
+                let (tile_x, tile_y) = (x % TILE_PX, y % TILE_PX);
+                let edge = TILE_PX - 2;
+                match tiles.get(row).map(|tiles_row| tiles_row[col]) {
+                    _ if tile_x > edge || tile_y > edge => false,
+                    Some(Fill::Filled) => true,
+                    Some(Fill::Hollow) => {
+                        tile_x == 0 || tile_y == 0 || tile_x == edge || tile_y == edge
+                    },
+                    _ => false
+                }
+            });
+        }
+    }
+}
+
+/// The pages covered by `row` of the grid, a bit for each.
+fn row_pages(row: usize) -> u8 {
+    let top = GRID_TOP + row as u8 * TILE_PX;
+    let bottom = top + TILE_PX - 1;
+    (top / 8..=bottom / 8).fold(0, |pages, page| pages | (1 << page))
+}
+
+impl Renderer for Ssd1306 {
+    fn present(&mut self, frame: &[[u8; N_COLS]; N_ROWS]) {
+        let mut tiles = [[Fill::Empty; N_COLS]; N_ROWS];
+        for (tiles_row, frame_row) in tiles.iter_mut().zip(frame.iter()) {
+            for (tile, &brightness) in tiles_row.iter_mut().zip(frame_row.iter()) {
+                *tile = match brightness {
+                    0 => Fill::Empty,
+                    b if b < FILLED_BRIGHTNESS => Fill::Hollow,
+                    _ => Fill::Filled
+                };
+            }
+        }
+        self.show(tiles);
+    }
+
+    fn present_tiles(
+        &mut self,
+        frame: &[[u8; N_COLS]; N_ROWS],
+        _colours: &[[Colour; N_COLS]; N_ROWS],
+        tiles: &[[Tile; N_COLS]; N_ROWS]
+    ) {
+        let mut fills = [[Fill::Empty; N_COLS]; N_ROWS];
+        for (row, fills_row) in fills.iter_mut().enumerate() {
+            for (col, fill) in fills_row.iter_mut().enumerate() {
+                // Tiles fading out as the snake slides off them are left empty
+                *fill = match (tiles[row][col], frame[row][col]) {
+                    (Tile::Empty, _) | (_, 0) => Fill::Empty,
+                    (Tile::Wall | Tile::Trail, _) => Fill::Hollow,
+                    _ => Fill::Filled
+                };
+            }
+        }
+        self.show(fills);
+    }
+
+    fn present_score(&mut self, score: u8) {
+        let right = SCORE_LEFT + SCORE_DIGITS * DIGIT_PX - 1;
+        let (top, bottom) = (SCORE_TOP / 8, (SCORE_TOP + 5 * DIGIT_SCALE - 1) / 8);
+        // Left until a frame with room to redraw all of it, if there isn't room in this one
+        let pages = (bottom - top + 1) as u32;
+        if self.score == Some(score) || self.pages_left < pages {
+            return;
+        }
+        self.score = Some(score);
+        self.pages_left -= pages;
+        for page in top..=bottom {
+            self.draw(SCORE_LEFT, right, page, |x, y| {
+                let Some(y) = y.checked_sub(SCORE_TOP) else {
+                    return false;
+                };
+                let (index, digit_x) = ((x - SCORE_LEFT) / DIGIT_PX, (x - SCORE_LEFT) % DIGIT_PX);
+                let (col, row) = (digit_x / DIGIT_SCALE, (y / DIGIT_SCALE) as usize);
+                // Leading zeros are left blank
+                let place = 10u32.pow((SCORE_DIGITS - 1 - index) as u32);
+                if col >= 3 || row >= 5 || ((score as u32) < place && place > 1) {
+                    return false;
+                }
+                let digit = (score as u32 / place % 10) as usize;
+                DIGITS[digit][row] & (0b100 >> col) != 0
+            });
+        }
+    }
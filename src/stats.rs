@@ -0,0 +1,104 @@
+//! Gameplay statistics, accumulated across games.
+
+use microbit::display::nonblocking::BitImage;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::TIMER0;
+use rtt_target::rprintln;
+use snakebit_core::game::{count_matrix, Game};
+use snakebit_core::game::rules::Rules;
+
+use crate::display::flash_image;
+use crate::icons;
+#[cfg(feature = "persist")]
+use crate::storage::{Record, Storage};
+
+/// Length of [`Stats`] when serialized.
+#[cfg(feature = "persist")]
+const STATS_LEN: usize = 20;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct Stats {
+    /// Number of games played to completion.
+    pub(crate) games_played: u32,
+    /// Total amount of food eaten.
+    pub(crate) foods_eaten: u32,
+    /// Total time spent playing, in milliseconds.
+    pub(crate) play_time_ms: u64,
+    /// Length of the longest snake achieved.
+    pub(crate) longest_snake: u8
+}
+
+impl Stats {
+    /// Record that a game step of the given length has been played.
+    pub(crate) fn record_step(&mut self, step_len_ms: u32) {
+        self.play_time_ms += step_len_ms as u64;
+    }
+
+    /// Record the outcome of a finished game.
+    pub(crate) fn record_game<R: Rules>(&mut self, game: &Game<R>) {
+        self.games_played += 1;
+        // Not the score, as food isn't worth one point in every mode
+        self.foods_eaten = self.foods_eaten.saturating_add(game.pieces_eaten());
+        self.longest_snake = self.longest_snake.max(game.snake_len() as u8);
+    }
+}
+
+#[cfg(feature = "persist")]
+impl Stats {
+    /// Load stats from flash, or return empty stats if none have been saved.
+    pub(crate) fn load(storage: &mut Storage) -> Self {
+        let mut bytes = [0u8; STATS_LEN];
+        if storage.read(Record::Stats, &mut bytes) {
+            Self::from_bytes(&bytes)
+        } else {
+            Self::default()
+        }
+    }
+
+    pub(crate) fn save(&self, storage: &mut Storage) {
+        storage.write(Record::Stats, &self.to_bytes());
+    }
+
+    fn to_bytes(&self) -> [u8; STATS_LEN] {
+        let mut bytes = [0u8; STATS_LEN];
+        bytes[0..4].copy_from_slice(&self.games_played.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.foods_eaten.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.play_time_ms.to_le_bytes());
+        bytes[16] = self.longest_snake;
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; STATS_LEN]) -> Self {
+        let mut word = [0u8; 4];
+        let mut dword = [0u8; 8];
+        word.copy_from_slice(&bytes[0..4]);
+        let games_played = u32::from_le_bytes(word);
+        word.copy_from_slice(&bytes[4..8]);
+        let foods_eaten = u32::from_le_bytes(word);
+        dword.copy_from_slice(&bytes[8..16]);
+        let play_time_ms = u64::from_le_bytes(dword);
+        Self {
+            games_played,
+            foods_eaten,
+            play_time_ms,
+            longest_snake: bytes[16]
+        }
+    }
+}
+
+/// Show the stats screen: an icon for each statistic followed by its value as a number of lit
+/// LEDs (capped at the size of the grid). The exact values are also printed over RTT.
+pub(crate) fn show_stats(timer: &mut Timer<TIMER0, Periodic>, stats: &Stats) {
+    rprintln!("{:?}", stats);
+    let play_time_mins = (stats.play_time_ms / 60_000) as usize;
+    let values = [
+        (icons::GAMES, stats.games_played as usize),
+        (icons::FOOD, stats.foods_eaten as usize),
+        (icons::SNAKE, stats.longest_snake as usize),
+        (icons::CLOCK, play_time_mins),
+    ];
+    for (icon, value) in values.iter() {
+        flash_image(timer, &BitImage::new(icon), 1000);
+        flash_image(timer, &BitImage::new(&count_matrix(*value)), 1500);
+    }
+}
@@ -0,0 +1,178 @@
+//! Persistent storage of small records in the last page of flash.
+//!
+//! Each record lives at a fixed offset in the page and starts with a 4-byte tag identifying it,
+//! so that a blank or corrupted page is not mistaken for real data. Flash can only be erased a
+//! page at a time, so writing one record means reading back the others and rewriting the lot.
+//!
+//! Writes are not made straight away. Instead, records are updated in a copy of the page held in
+//! RAM, which is written back to flash by [`Storage::flush`]. Writing to flash stalls the CPU, so
+//! flushing is left to idle times (the game-over screen, or when the game is paused) rather than
+//! happening mid-game.
+//!
+//! The CPU is halted while flash is being erased, which would freeze the display for the ~85ms it
+//! takes to erase a page, so the page is erased a few milliseconds at a time instead, letting the
+//! display refresh in between.
+
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+use microbit::hal::nvmc::{Nvmc, NvmcError};
+use microbit::pac::NVMC;
+use snakebit_core::game::N_ROWS;
+
+use crate::custom::CUSTOM_WALLS_LEN;
+
+/// Address of the flash page used for storage (the last page of the nRF52833's 512KB of flash).
+const PAGE_ADDR: usize = 0x7f000;
+/// Size of a flash page, in bytes.
+const PAGE_SIZE: usize = 4096;
+/// Number of bytes at the start of the page that are occupied by records. Must be a multiple of 4,
+/// as flash is written a word at a time.
+const USED_LEN: usize = 124 + CUSTOM_WALLS_LEN;
+/// Length of the tag at the start of each record.
+const TAG_LEN: usize = 4;
+/// Length of each partial erase, in milliseconds.
+const ERASE_CHUNK_MS: u8 = 4;
+/// Total erase time needed to erase a page (tERASEPAGE in the nRF52833 product specification), in
+/// milliseconds.
+const PAGE_ERASE_MS: u8 = 85;
+
+/// The kinds of record that can be stored.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Record {
+    Stats,
+    Config,
+    DailyBest,
+    HighScores,
+    BlitzHighScores,
+    /// The number of times the page has been erased, which [`Storage`] keeps up to date itself.
+    EraseCount,
+    /// Last, as its length depends on the size of the grid, so that the others' offsets don't.
+    CustomWalls
+}
+
+impl Record {
+    /// Offset of the record within the storage page.
+    fn offset(self) -> usize {
+        match self {
+            Record::Stats => 0,
+            Record::Config => 24,
+            Record::DailyBest => 44,
+            Record::HighScores => 56,
+            Record::BlitzHighScores => 84,
+            Record::EraseCount => 112,
+            Record::CustomWalls => 120
+        }
+    }
+
+    /// Length of the record's data (excluding its tag).
+    fn len(self) -> usize {
+        match self {
+            Record::Stats => 20,
+            Record::Config => 16,
+            Record::DailyBest => 8,
+            Record::HighScores => 24,
+            Record::BlitzHighScores => 24,
+            Record::EraseCount => 4,
+            Record::CustomWalls => CUSTOM_WALLS_LEN
+        }
+    }
+
+    fn tag(self) -> [u8; TAG_LEN] {
+        match self {
+            Record::Stats => *b"STAT",
+            Record::Config => *b"CONF",
+            Record::DailyBest => *b"DAYB",
+            Record::HighScores => *b"HIGH",
+            Record::BlitzHighScores => *b"BLTZ",
+            Record::EraseCount => *b"ERAS",
+            // Tagged with the size of the grid, eg `WAL5`, so that a layout saved by a build with
+            // a different grid isn't loaded as if it fitted this one
+            Record::CustomWalls => [b'W', b'A', b'L', b'0' + N_ROWS as u8]
+        }
+    }
+}
+
+pub(crate) struct Storage {
+    nvmc: Nvmc<NVMC>,
+    /// The records, as they should be in flash.
+    image: [u8; USED_LEN],
+    /// Whether `image` has changed since it was last written to flash.
+    dirty: bool
+}
+
+impl Storage {
+    pub(crate) fn new(board_nvmc: NVMC) -> Self {
+        // Safety: the firmware is nowhere near large enough to reach the last page of flash, and
+        // the page is only ever accessed through this struct.
+        let page = unsafe {
+            core::slice::from_raw_parts_mut(PAGE_ADDR as *mut u8, PAGE_SIZE)
+        };
+        let mut nvmc = Nvmc::new(board_nvmc, page);
+        let mut image = [0u8; USED_LEN];
+        if nvmc.read(0, &mut image).is_err() {
+            // Treat the page as blank, so that no records are found
+            image = [0xff; USED_LEN];
+        }
+        Self { nvmc, image, dirty: false }
+    }
+
+    /// Read a record's data into `buf`, which must be the same length as the record. Returns false
+    /// if no valid record is stored. Records that have been written but not yet flushed are read
+    /// back as written.
+    pub(crate) fn read(&self, record: Record, buf: &mut [u8]) -> bool {
+        let offset = record.offset();
+        if self.image[offset..offset + TAG_LEN] != record.tag() {
+            return false;
+        }
+        buf.copy_from_slice(&self.image[offset + TAG_LEN..offset + TAG_LEN + record.len()]);
+        true
+    }
+
+    /// Write a record's data, which must be the same length as the record. The record is only
+    /// saved to flash at the next call to [`Storage::flush`].
+    pub(crate) fn write(&mut self, record: Record, data: &[u8]) {
+        let offset = record.offset();
+        self.image[offset..offset + TAG_LEN].copy_from_slice(&record.tag());
+        self.image[offset + TAG_LEN..offset + TAG_LEN + record.len()].copy_from_slice(data);
+        self.dirty = true;
+    }
+
+    /// Save any records written since the last flush to flash. This stalls the CPU for a total of
+    /// around 100ms, so should only be called when the game isn't running.
+    pub(crate) fn flush(&mut self) -> Result<(), NvmcError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        // Counted in the same write as the erase, so that it can't be lost without the records
+        let erases = self.erase_count().saturating_add(1);
+        self.write(Record::EraseCount, &erases.to_le_bytes());
+        self.erase_page();
+        self.nvmc.write(0, &self.image)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// The number of times the storage page has been erased, as far as is known. Erases made by
+    /// other firmware aren't included.
+    pub(crate) fn erase_count(&self) -> u32 {
+        let mut bytes = [0u8; 4];
+        if self.read(Record::EraseCount, &mut bytes) {
+            u32::from_le_bytes(bytes)
+        } else {
+            0
+        }
+    }
+
+    /// Erase the storage page, in chunks of `ERASE_CHUNK_MS`.
+    fn erase_page(&mut self) {
+        // Safety: the NVMC is otherwise only used through `self.nvmc`, which isn't in use while this
+        // runs, and the registers are left configured for reading afterwards, as `Nvmc` expects.
+        let nvmc = unsafe { &*NVMC::ptr() };
+        nvmc.config.write(|w| w.wen().een());
+        nvmc.erasepagepartialcfg.write(|w| unsafe { w.duration().bits(ERASE_CHUNK_MS) });
+        for _ in 0..(PAGE_ERASE_MS + ERASE_CHUNK_MS - 1) / ERASE_CHUNK_MS {
+            nvmc.erasepagepartial.write(|w| unsafe { w.bits(PAGE_ADDR as u32) });
+            while nvmc.ready.read().ready().bit_is_clear() {}
+        }
+        nvmc.config.write(|w| w.wen().ren());
+    }
+}
@@ -0,0 +1,78 @@
+//! Reading which way the board is tilted, from the accelerometer.
+
+use snakebit_core::game::Direction;
+
+use crate::display::upright;
+use crate::hardware::Accelerometer;
+
+/// Acceleration along the X or Y axis (in milli-g) beyond which the board counts as tilted. About
+/// 17 degrees from flat.
+const TILT_THRESHOLD_MG: i32 = 300;
+
+/// Which way the board is tilted, if it is tilted far enough to count, as a direction on the
+/// display the way round the board is held. If it is tilted along both axes, the steeper one wins.
+pub(crate) fn tilt_direction(accelerometer: &mut Accelerometer) -> Option<Direction> {
+    let (x, y, _) = accelerometer.acceleration().ok()?.xyz_mg();
+    // Taking the top edge of the board (the one with the USB socket) as up
+    let direction = if x.abs().max(y.abs()) < TILT_THRESHOLD_MG {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x > 0 { Direction::Right } else { Direction::Left })
+    } else {
+        Some(if y > 0 { Direction::Up } else { Direction::Down })
+    };
+    direction.map(upright)
+}
+
+/// Total acceleration (in milli-g) beyond which the board counts as jolted: well over the 1g of
+/// gravity, so tilting or turning the board over never counts.
+const JOLT_THRESHOLD_MG: i32 = 1800;
+/// Number of separate jolts that make a shake.
+const SHAKE_JOLTS: u8 = 3;
+/// Time within which the jolts of a shake must all happen, in milliseconds.
+const SHAKE_WINDOW_MS: u32 = 800;
+
+/// Detects the board being shaken: several sharp jolts in quick succession, so that knocking or
+/// dropping it doesn't count. A jolt lasting several readings only counts once.
+pub(crate) struct ShakeDetector {
+    /// Jolts counted in the current window.
+    jolts: u8,
+    /// Time since the first jolt in the current window.
+    since_first_ms: u32,
+    /// Whether the board was jolted at the last reading.
+    jolted: bool
+}
+
+impl ShakeDetector {
+    pub(crate) fn new() -> Self {
+        Self { jolts: 0, since_first_ms: 0, jolted: false }
+    }
+
+    /// Read the accelerometer, `elapsed_ms` milliseconds after the last reading, and return
+    /// whether the board has just been shaken. Once a shake is detected, counting starts again.
+    pub(crate) fn poll(&mut self, accelerometer: &mut Accelerometer, elapsed_ms: u32) -> bool {
+        if self.jolts > 0 {
+            self.since_first_ms += elapsed_ms;
+            if self.since_first_ms > SHAKE_WINDOW_MS {
+                self.jolts = 0;
+            }
+        }
+        let jolted = accelerometer.acceleration().is_ok_and(|a| {
+            let (x, y, z) = a.xyz_mg();
+            x * x + y * y + z * z > JOLT_THRESHOLD_MG * JOLT_THRESHOLD_MG
+        });
+        if jolted && !self.jolted {
+            if self.jolts == 0 {
+                self.since_first_ms = 0;
+            }
+            self.jolts += 1;
+        }
+        self.jolted = jolted;
+        if self.jolts >= SHAKE_JOLTS {
+            self.jolts = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Scent trails, which lead the snake towards food that the board's own LEDs can't show. Where the
+//! grid is bigger than the LEDs, they only show the part of it around the snake's head (see
+//! [`viewport`](crate::display::viewport)), so food can be placed out of sight.
+//!
+//! When that happens, a trail of dim tiles is laid from the snake's head towards the food, dimmest
+//! nearest the head. Each tile fades by a level at every step of the game, so the trail is gone
+//! after [`TRAIL_LEN`] steps, by which time the player should know which way to go.
+
+use heapless::Vec;
+use snakebit_core::game::{N_COLS, N_ROWS};
+
+use crate::display::in_view;
+
+/// Number of tiles in a trail, which is also the number of steps it lasts.
+const TRAIL_LEN: usize = 3;
+
+/// A tile of a trail.
+#[derive(Debug, Copy, Clone)]
+struct Scent {
+    row: usize,
+    col: usize,
+    /// How bright the tile is drawn, and the number of steps until it fades out.
+    strength: u8
+}
+
+/// The trail towards the food last placed out of sight, or nothing once it has faded.
+#[derive(Debug, Default)]
+pub(crate) struct ScentTrail {
+    scents: Vec<Scent, TRAIL_LEN>
+}
+
+impl ScentTrail {
+    /// Lay a trail from the snake's head, at `head`, towards food just placed at `food`, in place
+    /// of any trail already laid. If the food is in view of the LEDs, no trail is needed and none
+    /// is laid. The trail heads along whichever axis the food is further away on, so it follows
+    /// the way the snake would go to get there (ignoring walls and wraparound).
+    pub(crate) fn lay(&mut self, head: (usize, usize), food: (usize, usize)) {
+        self.scents.clear();
+        if in_view(food.0, food.1, head) {
+            return;
+        }
+        let (mut row, mut col) = head;
+        for strength in 1..=TRAIL_LEN as u8 {
+            let rows_away = food.0 as isize - row as isize;
+            let cols_away = food.1 as isize - col as isize;
+            if rows_away.abs() >= cols_away.abs() {
+                row = (row as isize + rows_away.signum()) as usize;
+            } else {
+                col = (col as isize + cols_away.signum()) as usize;
+            }
+            if (row, col) == food {
+                break;
+            }
+            // Can't fail, as there are never more than `TRAIL_LEN` tiles
+            let _ = self.scents.push(Scent { row, col, strength });
+        }
+    }
+
+    /// Fade the trail by a level, at a step of the game. Tiles that have faded out are removed.
+    pub(crate) fn fade(&mut self) {
+        for scent in self.scents.iter_mut() {
+            scent.strength -= 1;
+        }
+        self.scents.retain(|scent| scent.strength > 0);
+    }
+
+    /// The trail as a layer of the game's frame, each tile as bright as its strength.
+    pub(crate) fn layer(&self) -> [[u8; N_COLS]; N_ROWS] {
+        let mut layer = [[0; N_COLS]; N_ROWS];
+        for scent in self.scents.iter() {
+            layer[scent.row][scent.col] = scent.strength;
+        }
+        layer
+    }
+}